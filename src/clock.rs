@@ -0,0 +1,79 @@
+use crate::TARGET_FPS;
+
+/// Keeps the emulated processor in sync with a fixed clock frequency.
+///
+/// The clock tracks how many cycles should have been executed since it was started and
+/// tells the main loop how many cycles are due. If the host cannot keep up, the schedule
+/// is moved forward instead of trying to catch up with an ever growing backlog.
+pub struct Clock {
+    frequency: u64,
+    start_time: u64,
+    start_cycle_count: u64,
+    skipped_cycles: u64,
+}
+
+impl Clock {
+    pub fn new(frequency: u64, current_time: u64, current_cycle_count: u64) -> Self {
+        Self {
+            frequency,
+            start_time: current_time,
+            start_cycle_count: current_cycle_count,
+            skipped_cycles: 0,
+        }
+    }
+
+    /// The maximum number of cycles that are handed out at once (the cycles of one frame).
+    fn max_cycles_due(&self) -> u64 {
+        (self.frequency / TARGET_FPS).max(1)
+    }
+
+    /// Returns the number of cycles that have to be executed to catch up with the clock.
+    pub fn cycles_due(&mut self, current_time: u64, current_cycle_count: u64) -> u64 {
+        let elapsed_ms = current_time.saturating_sub(self.start_time) as u128;
+        let scheduled_cycles = (elapsed_ms * self.frequency as u128 / 1000) as u64;
+        let target_cycle_count =
+            self.start_cycle_count + scheduled_cycles.saturating_sub(self.skipped_cycles);
+        let cycles_due = target_cycle_count.saturating_sub(current_cycle_count);
+        let max_cycles_due = self.max_cycles_due();
+        if cycles_due > max_cycles_due {
+            self.skipped_cycles += cycles_due - max_cycles_due;
+            return max_cycles_due;
+        }
+        cycles_due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_cycles_due_at_start() {
+        let mut clock = Clock::new(1_000_000, 1000, 0);
+        assert_eq!(clock.cycles_due(1000, 0), 0);
+    }
+
+    #[test]
+    fn cycles_due_grow_with_time() {
+        let mut clock = Clock::new(60_000, 1000, 0);
+        assert_eq!(clock.cycles_due(1001, 0), 60);
+        assert_eq!(clock.cycles_due(1010, 60), 540);
+        assert_eq!(clock.cycles_due(1010, 600), 0);
+    }
+
+    #[test]
+    fn executed_cycles_ahead_of_schedule_are_not_due() {
+        let mut clock = Clock::new(60_000, 0, 0);
+        assert_eq!(clock.cycles_due(5, 1000), 0);
+    }
+
+    #[test]
+    fn backlog_is_dropped_instead_of_caught_up() {
+        let mut clock = Clock::new(60_000, 0, 0);
+        // 1000 ms passed but nothing got executed: only one frame's worth of cycles is due
+        assert_eq!(clock.cycles_due(1000, 0), 1000);
+        // afterwards the clock continues from the new position
+        assert_eq!(clock.cycles_due(1000, 1000), 0);
+        assert_eq!(clock.cycles_due(1001, 1000), 60);
+    }
+}
@@ -1,8 +1,18 @@
+mod breakpoint;
+mod dap;
+mod dap_protocol;
+mod expression;
+mod gdb;
+mod gdb_protocol;
+mod history;
 mod segmented_reader;
 mod tcp_protocol;
+mod watchpoint;
 
 use std::{
-    collections::{HashSet, VecDeque},
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    ops::Range,
     thread,
     time::Duration,
 };
@@ -10,28 +20,47 @@ use std::{
 use crossbeam_channel::{bounded, select, tick, Receiver, Sender, TryRecvError};
 use crossbeam_utils::sync::WaitGroup;
 
-use self::tcp_protocol::{PollReturn, TcpHandler};
+use self::breakpoint::{Breakpoint, BreakpointAction, LogMessage};
+use self::expression::Expression;
+use self::history::{CallStackChange, History};
+use self::tcp_protocol::{ClientId, PollReturn, Role, TcpHandler};
+use self::watchpoint::{Watchpoint, WatchpointHit};
 use crate::{memory::Memory, opcodes::Opcode, processor::Processor, Address, Register, Word};
 
 const CHANNEL_BOUND: usize = 100;
 const TCP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const HISTORY_EXHAUSTED_MESSAGE: &str = "Reached the beginning of the execution history.";
 
 struct Debugger {
     receiver: Receiver<DebugMessage>,
     breakpoint_sender: Sender<DebugCommand>,
     started: bool,
     start_notifications: Vec<WaitGroup>,
+    has_controller: bool,
+    /// The locations of the breakpoints and the watchpoints set by the controlling client.
+    breakpoints: Vec<Address>,
+    watchpoints: Vec<Watchpoint>,
+    /// The responses that announced the current break, sent again to clients that connect
+    /// while breaking.
+    break_responses: Vec<tcp_protocol::Response>,
 }
 
 pub struct DebugHandle {
     state: BreakpointHandleState,
-    breakpoints: HashSet<Address>,
+    breakpoints: HashMap<Address, Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
     sender: Option<Sender<DebugMessage>>,
     receiver: Option<Receiver<DebugCommand>>,
     receive_cache: VecDeque<DebugCommand>,
     should_pause: bool,
-    call_stack: Vec<Address>,
+    /// The call stacks of the cores, indexed by their core IDs.
+    call_stacks: Vec<Vec<Address>>,
     did_execute_last_cycle: bool,
+    modified_memory: Vec<Range<usize>>,
+    executed_instruction: Address,
+    run_until: Option<RunUntil>,
+    history: History,
+    reverse_continuing: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -40,6 +69,24 @@ pub enum ShouldExecuteInstruction {
     No,
 }
 
+/// Condition to start breaking again while running (breakpoints and pause requests still
+/// take precedence).
+#[derive(Debug, PartialEq)]
+enum RunUntil {
+    /// Break as soon as the call stack depth of the given core is at most the given value.
+    CallStackDepth { core_id: Word, depth: usize },
+    /// Break as soon as the instruction at the given address is reached.
+    Location(Address),
+}
+
+#[derive(Debug, PartialEq)]
+enum StepBackResult {
+    Done,
+    /// The last recorded instruction has been executed by another core.
+    OtherCore,
+    HistoryExhausted,
+}
+
 #[derive(Debug, PartialEq)]
 enum BreakpointHandleState {
     WaitingForStart,
@@ -52,8 +99,14 @@ enum DebugMessage {
     Stop,
     /// Request to wait for start and drop the sent wait group after starting.
     WaitForStart(WaitGroup),
-    /// Notification that we hit a breakpoint and will start breaking.
-    HitBreakpoint(Address),
+    /// Notification that we hit a breakpoint (for the given number of times) and will start
+    /// breaking.
+    HitBreakpoint(Address, u64),
+    /// Message of the logpoint at the given address.
+    Log(Address, String),
+    /// Notification that the instruction at the given address triggered a watchpoint and we
+    /// will start breaking.
+    HitWatchpoint(Address, WatchpointHit),
     /// Notification that the debugger is (still) breaking at the given instruction address.
     Breaking(Address),
     /// Notification that the debugger started breaking at the given instruction address due to a pause request.
@@ -63,18 +116,45 @@ enum DebugMessage {
         registers: Vec<Word>,
         call_stack: Vec<Address>,
     },
+    /// Contents of the memory starting at the given address.
+    Memory { address: Address, bytes: Vec<u8> },
+    /// Notification that stepping backwards reached the beginning of the execution history. The
+    /// debugger breaks afterwards.
+    HistoryExhausted,
 }
 
 enum DebugCommand {
-    SetBreakpoints(Vec<Address>),
+    /// Breakpoints replace existing ones at the same location.
+    SetBreakpoints(Vec<(Address, Breakpoint)>),
     RemoveBreakpoints(Vec<Address>),
+    SetWatchpoints(Vec<Watchpoint>),
+    RemoveWatchpoints(Vec<Watchpoint>),
     /// Continue normal execution i.e. stop breaking.
     Continue,
     /// Execute one instruction while breaking.
     StepOne,
+    /// Like `StepOne`, but calls are executed until they return.
+    StepOver,
+    /// Run until the current function returns.
+    StepOut,
+    /// Run until the instruction at the given address is reached.
+    RunToLocation(Address),
+    /// Undo the last executed instruction while breaking.
+    StepBack,
+    /// Undo instructions until a breakpoint is reached (ignore counts and logpoints are not
+    /// taken into account) or the history is exhausted.
+    ReverseContinue,
     /// Instructs breakpoint handler to break as soon as possible.
     Pause,
     SetRegister(u8, Word),
+    ReadMemory {
+        address: Address,
+        length: Word,
+    },
+    WriteMemory {
+        address: Address,
+        bytes: Vec<u8>,
+    },
     Terminate,
 }
 
@@ -83,35 +163,105 @@ enum ShouldTerminate {
     No,
 }
 
-pub fn start_debugger() -> DebugHandle {
+/// The protocol that debugger frontends use to communicate with the debugger. Only the JSON
+/// protocol supports observers, the other protocols disconnect further clients while a client is
+/// connected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DebugProtocol {
+    /// NUL-terminated JSON messages (see `tcp_protocol`)
+    #[default]
+    Json,
+    /// The Debug Adapter Protocol (supported by VS Code and other editors)
+    Dap,
+    /// The GDB remote serial protocol (supported by `gdb` and `lldb`)
+    Gdb,
+}
+
+impl DebugProtocol {
+    pub fn default_port(self) -> u16 {
+        match self {
+            DebugProtocol::Json => tcp_protocol::DEFAULT_PORT,
+            DebugProtocol::Dap => dap_protocol::DEFAULT_PORT,
+            DebugProtocol::Gdb => gdb_protocol::DEFAULT_PORT,
+        }
+    }
+
+    /// Returns whether the frontend can debug a machine with multiple cores. The Debug Adapter
+    /// Protocol frontend presents the machine as a single thread, so the state it shows would
+    /// belong to whichever core broke.
+    pub fn supports_multiple_cores(self) -> bool {
+        match self {
+            DebugProtocol::Json | DebugProtocol::Gdb => true,
+            DebugProtocol::Dap => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DebuggerOptions {
+    pub protocol: DebugProtocol,
+    /// The address that frontends connect to (port 0 lets the system choose a free port).
+    pub address: SocketAddr,
+    /// The maximum number of bytes of the execution history that is recorded to be able to
+    /// step backwards.
+    pub history_bytes: usize,
+}
+
+pub fn start_debugger(options: DebuggerOptions) -> DebugHandle {
     let (sender, receiver) = bounded(CHANNEL_BOUND);
     let (breakpoint_sender, breakpoint_receiver) = bounded(CHANNEL_BOUND);
 
-    thread::spawn(move || Debugger::new(receiver, breakpoint_sender).run());
+    thread::spawn(move || match options.protocol {
+        DebugProtocol::Json => Debugger::new(receiver, breakpoint_sender).run(options.address),
+        DebugProtocol::Dap => {
+            dap::DapDebugger::new(receiver, breakpoint_sender).run(options.address)
+        }
+        DebugProtocol::Gdb => gdb::GdbStub::new(receiver, breakpoint_sender).run(options.address),
+    });
 
-    DebugHandle {
-        state: BreakpointHandleState::WaitingForStart,
-        breakpoints: HashSet::new(),
-        sender: Some(sender),
-        receiver: Some(breakpoint_receiver),
-        receive_cache: VecDeque::new(),
-        should_pause: false,
-        call_stack: Vec::new(),
-        did_execute_last_cycle: true,
-    }
+    DebugHandle::new(sender, breakpoint_receiver, options.history_bytes)
 }
 
 impl DebugHandle {
+    fn new(
+        sender: Sender<DebugMessage>,
+        receiver: Receiver<DebugCommand>,
+        history_bytes: usize,
+    ) -> Self {
+        Self {
+            state: BreakpointHandleState::WaitingForStart,
+            breakpoints: HashMap::new(),
+            watchpoints: Vec::new(),
+            sender: Some(sender),
+            receiver: Some(receiver),
+            receive_cache: VecDeque::new(),
+            should_pause: false,
+            call_stacks: Vec::new(),
+            did_execute_last_cycle: true,
+            modified_memory: Vec::new(),
+            executed_instruction: 0,
+            run_until: None,
+            history: History::new(history_bytes),
+            reverse_continuing: false,
+        }
+    }
+
     pub fn dummy() -> Self {
         Self {
             state: BreakpointHandleState::Running,
-            breakpoints: HashSet::with_capacity(0),
+            breakpoints: HashMap::with_capacity(0),
+            watchpoints: Vec::with_capacity(0),
             sender: None,
             receiver: None,
             receive_cache: VecDeque::with_capacity(0),
             should_pause: false,
-            call_stack: Vec::with_capacity(0),
+            call_stacks: Vec::with_capacity(0),
             did_execute_last_cycle: true,
+            modified_memory: Vec::with_capacity(0),
+            executed_instruction: 0,
+            run_until: None,
+            history: History::new(0),
+            reverse_continuing: false,
         }
     }
 
@@ -136,28 +286,55 @@ impl DebugHandle {
 
         if self.state == Breaking {
             if self.did_execute_last_cycle {
-                self.send_break_state(&processor.registers);
+                self.send_break_state(processor);
                 self.send(DebugMessage::Breaking(instruction_pointer));
             }
         } else {
-            self.start_breaking_if_requested(instruction_pointer, processor);
+            self.start_breaking_if_requested(instruction_pointer, processor, memory);
         }
 
         let result;
         if self.state == Breaking {
-            result = self.breaking(processor);
+            result = self.breaking(processor, memory);
         } else {
             result = ShouldExecuteInstruction::Yes
         }
 
         if let ShouldExecuteInstruction::Yes = result {
-            self.track_call_stack(memory, instruction_pointer);
+            let call_stack_change =
+                self.track_call_stack(memory, processor.core_id(), instruction_pointer);
+            self.executed_instruction = instruction_pointer;
+            self.history.record_before(processor, call_stack_change);
+            processor.data_accesses =
+                (!self.watchpoints.is_empty() || self.history.is_enabled()).then(Vec::new);
         }
 
         self.did_execute_last_cycle = result == ShouldExecuteInstruction::Yes;
         return result;
     }
 
+    /// Records the executed instruction in the history and starts breaking if it triggered a
+    /// watchpoint.
+    pub fn after_instruction_execution(&mut self, processor: &mut Processor, memory: &Memory) {
+        let Some(data_accesses) = processor.data_accesses.take() else {
+            return;
+        };
+        self.history.record_after(processor, &data_accesses);
+        let hit = data_accesses.iter().find_map(|access| {
+            self.watchpoints
+                .iter()
+                .find_map(|watchpoint| watchpoint.check(access, memory))
+        });
+        if let Some(hit) = hit {
+            self.state = BreakpointHandleState::Breaking;
+            self.receive_cache.clear();
+            self.send_break_state(processor);
+            self.send(DebugMessage::HitWatchpoint(self.executed_instruction, hit));
+            // the break state has already been sent
+            self.did_execute_last_cycle = false;
+        }
+    }
+
     /// Wait for start command from debugger interface
     /// or directly continue if not in debug mode.
     pub fn wait_for_start(&self) {
@@ -168,65 +345,228 @@ impl DebugHandle {
         }
     }
 
-    fn start_breaking_if_requested(&mut self, instruction_pointer: Word, processor: &Processor) {
+    /// Returns the ranges of the memory that have been written by the debugger since the last
+    /// call. The instruction cache has to be regenerated for these ranges.
+    pub fn take_modified_memory(&mut self) -> Vec<Range<usize>> {
+        std::mem::take(&mut self.modified_memory)
+    }
+
+    fn start_breaking_if_requested(
+        &mut self,
+        instruction_pointer: Word,
+        processor: &Processor,
+        memory: &mut Memory,
+    ) {
         use BreakpointHandleState::*;
 
         if self.state == Breaking {
             return;
         }
 
-        self.receive_updates_non_blocking();
+        self.receive_updates_non_blocking(memory);
 
         let mut should_start_breaking = None;
-        let hit_breakpoint = self.breakpoints.contains(&instruction_pointer);
+        let breakpoint_action = self
+            .breakpoints
+            .get_mut(&instruction_pointer)
+            .map(|breakpoint| (breakpoint.hit(processor, memory), breakpoint.hit_count()));
+        let hit_breakpoint = match breakpoint_action {
+            Some((BreakpointAction::Break, hit_count)) => Some(hit_count),
+            Some((BreakpointAction::Log(message), _)) => {
+                self.send(DebugMessage::Log(instruction_pointer, message));
+                None
+            }
+            Some((BreakpointAction::Ignore, _)) | None => None,
+        };
+
+        let reached_target = match self.run_until {
+            Some(RunUntil::CallStackDepth { core_id, depth }) => {
+                processor.core_id() == core_id && self.call_stack(core_id).len() <= depth
+            }
+            Some(RunUntil::Location(location)) => instruction_pointer == location,
+            None => false,
+        };
 
         if self.should_pause {
             should_start_breaking = Some(DebugMessage::Pausing(instruction_pointer));
-        } else if hit_breakpoint {
-            should_start_breaking = Some(DebugMessage::HitBreakpoint(instruction_pointer));
+        } else if let Some(hit_count) = hit_breakpoint {
+            should_start_breaking =
+                Some(DebugMessage::HitBreakpoint(instruction_pointer, hit_count));
+        } else if reached_target {
+            should_start_breaking = Some(DebugMessage::Breaking(instruction_pointer));
         }
 
         self.should_pause = false;
 
         if let Some(break_message) = should_start_breaking {
             self.state = Breaking;
+            self.run_until = None;
             self.receive_cache.clear();
-            self.send_break_state(&processor.registers);
+            self.send_break_state(processor);
             self.send(break_message);
         }
     }
 
-    fn breaking(&mut self, processor: &mut Processor) -> ShouldExecuteInstruction {
+    fn breaking(
+        &mut self,
+        processor: &mut Processor,
+        memory: &mut Memory,
+    ) -> ShouldExecuteInstruction {
         use DebugCommand::*;
 
-        self.receive_updates_non_blocking();
+        self.receive_updates_non_blocking(memory);
+
+        if self.reverse_continuing {
+            return self.continue_reverse(processor, memory);
+        }
 
         if let Some(message) = self.receive_cache.pop_front() {
             match message {
-                Terminate => std::process::exit(0),
                 StepOne => return ShouldExecuteInstruction::Yes,
                 Continue => {
                     self.state = BreakpointHandleState::Running;
                     return ShouldExecuteInstruction::Yes;
                 }
+                StepOver => {
+                    // the call stack is back at the current depth after the next instruction,
+                    // unless it is a call
+                    let core_id = processor.core_id();
+                    self.run_until = Some(RunUntil::CallStackDepth {
+                        core_id,
+                        depth: self.call_stack(core_id).len(),
+                    });
+                    self.state = BreakpointHandleState::Running;
+                    return ShouldExecuteInstruction::Yes;
+                }
+                StepOut => {
+                    // stepping out of the outermost function continues normally
+                    let core_id = processor.core_id();
+                    self.run_until = self
+                        .call_stack(core_id)
+                        .len()
+                        .checked_sub(1)
+                        .map(|depth| RunUntil::CallStackDepth { core_id, depth });
+                    self.state = BreakpointHandleState::Running;
+                    return ShouldExecuteInstruction::Yes;
+                }
+                RunToLocation(location) => {
+                    self.run_until = Some(RunUntil::Location(location));
+                    self.state = BreakpointHandleState::Running;
+                    return ShouldExecuteInstruction::Yes;
+                }
+                StepBack => match self.step_back(processor, memory) {
+                    // wait until the core that executed the instruction is scheduled
+                    StepBackResult::OtherCore => self.receive_cache.push_front(StepBack),
+                    result => {
+                        if let StepBackResult::HistoryExhausted = result {
+                            self.send(DebugMessage::HistoryExhausted);
+                        }
+                        self.send_break_state(processor);
+                        self.send(DebugMessage::Breaking(processor.get_instruction_pointer()));
+                    }
+                },
+                ReverseContinue => {
+                    self.reverse_continuing = true;
+                }
                 SetRegister(register, value) => {
                     processor.registers[Register(register)] = value;
                 }
-                Pause | SetBreakpoints(_) | RemoveBreakpoints(_) => panic!("BreakpointHandle: Message should never be added to the message cache but handled immediately."),
+                Pause
+                | Terminate
+                | SetBreakpoints(_)
+                | RemoveBreakpoints(_)
+                | SetWatchpoints(_)
+                | RemoveWatchpoints(_)
+                | ReadMemory { .. }
+                | WriteMemory { .. } => panic!("BreakpointHandle: Message should never be added to the message cache but handled immediately."),
             }
         }
 
         ShouldExecuteInstruction::No
     }
 
+    /// Undoes one instruction of a reverse continue. Stops at breakpoints, at the beginning of
+    /// the history and on pause requests.
+    fn continue_reverse(
+        &mut self,
+        processor: &mut Processor,
+        memory: &mut Memory,
+    ) -> ShouldExecuteInstruction {
+        let break_message = if std::mem::take(&mut self.should_pause) {
+            Some(DebugMessage::Pausing(processor.get_instruction_pointer()))
+        } else {
+            match self.step_back(processor, memory) {
+                StepBackResult::Done => {
+                    let instruction_pointer = processor.get_instruction_pointer();
+                    self.breakpoints
+                        .get(&instruction_pointer)
+                        .filter(|breakpoint| {
+                            breakpoint.log_message.is_none()
+                                && breakpoint.is_condition_met(processor, memory)
+                        })
+                        .map(|breakpoint| {
+                            DebugMessage::HitBreakpoint(instruction_pointer, breakpoint.hit_count())
+                        })
+                }
+                StepBackResult::OtherCore => None,
+                StepBackResult::HistoryExhausted => {
+                    self.send(DebugMessage::HistoryExhausted);
+                    Some(DebugMessage::Breaking(processor.get_instruction_pointer()))
+                }
+            }
+        };
+        if let Some(break_message) = break_message {
+            self.reverse_continuing = false;
+            self.send_break_state(processor);
+            self.send(break_message);
+        }
+        ShouldExecuteInstruction::No
+    }
+
+    /// Undoes the last recorded instruction if it has been executed by the given processor.
+    fn step_back(&mut self, processor: &mut Processor, memory: &mut Memory) -> StepBackResult {
+        match self.history.last_core_id() {
+            None => return StepBackResult::HistoryExhausted,
+            Some(core_id) if core_id != processor.core_id() => return StepBackResult::OtherCore,
+            Some(_) => {}
+        }
+        let Some(undone) = self.history.step_back(processor, memory) else {
+            return StepBackResult::HistoryExhausted;
+        };
+        let call_stack = self.call_stack_mut(processor.core_id());
+        match undone.call_stack_change {
+            Some(CallStackChange::Pushed) => {
+                call_stack.pop();
+            }
+            Some(CallStackChange::Popped(address)) => call_stack.push(address),
+            None => {}
+        }
+        self.modified_memory.extend(undone.modified_memory);
+        StepBackResult::Done
+    }
+
     #[inline]
-    fn send_break_state<const SIZE: usize>(&self, registers: &crate::processor::Registers<SIZE>) {
+    fn send_break_state(&self, processor: &Processor) {
         self.send(DebugMessage::BreakState {
-            registers: registers.contents().to_vec(),
-            call_stack: self.call_stack.clone(),
+            registers: processor.registers.contents().to_vec(),
+            call_stack: self.call_stack(processor.core_id()).to_vec(),
         });
     }
 
+    fn call_stack(&self, core_id: Word) -> &[Address] {
+        self.call_stacks
+            .get(core_id as usize)
+            .map_or(&[], |call_stack| call_stack.as_slice())
+    }
+
+    fn call_stack_mut(&mut self, core_id: Word) -> &mut Vec<Address> {
+        let index = core_id as usize;
+        if self.call_stacks.len() <= index {
+            self.call_stacks.resize_with(index + 1, Vec::new);
+        }
+        &mut self.call_stacks[index]
+    }
+
     #[inline]
     fn send(&self, message: DebugMessage) {
         if let Some(sender) = &self.sender {
@@ -236,54 +576,139 @@ impl DebugHandle {
         }
     }
 
-    fn receive_updates_non_blocking(&mut self) {
-        loop {
-            if let Some(ref receiver) = self.receiver {
-                match receiver.try_recv() {
-                    Ok(message) => self.handle_message(message),
-                    Err(TryRecvError::Disconnected) => {
-                        panic!("Cannot receive breakpoint updates after debugger has been stopped.")
-                    }
-                    Err(TryRecvError::Empty) => break,
+    fn receive_updates_non_blocking(&mut self, memory: &mut Memory) {
+        while let Some(ref receiver) = self.receiver {
+            match receiver.try_recv() {
+                Ok(message) => self.handle_message(message, memory),
+                Err(TryRecvError::Disconnected) => {
+                    panic!("Cannot receive breakpoint updates after debugger has been stopped.")
                 }
+                Err(TryRecvError::Empty) => break,
             }
         }
     }
 
     #[inline]
-    fn handle_message(&mut self, message: DebugCommand) {
+    fn handle_message(&mut self, message: DebugCommand, memory: &mut Memory) {
         match message {
+            DebugCommand::Terminate => std::process::exit(0),
             DebugCommand::Pause => {
                 self.should_pause = true;
             }
-            DebugCommand::SetBreakpoints(locations) => {
-                self.breakpoints.extend(locations);
+            DebugCommand::SetBreakpoints(breakpoints) => {
+                self.breakpoints.extend(breakpoints);
             }
             DebugCommand::RemoveBreakpoints(locations) => {
                 for location in locations {
                     self.breakpoints.remove(&location);
                 }
             }
+            DebugCommand::SetWatchpoints(watchpoints) => {
+                self.watchpoints.extend(watchpoints);
+            }
+            DebugCommand::RemoveWatchpoints(watchpoints) => {
+                self.watchpoints
+                    .retain(|watchpoint| !watchpoints.contains(watchpoint));
+            }
+            DebugCommand::ReadMemory { address, length } => {
+                let range = clamp_to_memory(address, length as usize);
+                self.send(DebugMessage::Memory {
+                    address,
+                    bytes: memory.data()[range].to_vec(),
+                });
+            }
+            DebugCommand::WriteMemory { address, bytes } => {
+                let range = clamp_to_memory(address, bytes.len());
+                memory.data_mut()[range.clone()].copy_from_slice(&bytes[..range.len()]);
+                self.send(DebugMessage::Memory {
+                    address,
+                    bytes: memory.data()[range.clone()].to_vec(),
+                });
+                self.modified_memory.push(range);
+            }
             _ => self.receive_cache.push_back(message),
         }
     }
 
-    fn track_call_stack(&mut self, memory: &mut Memory, instruction_pointer: Address) {
+    fn track_call_stack(
+        &mut self,
+        memory: &mut Memory,
+        core_id: Word,
+        instruction_pointer: Address,
+    ) -> Option<CallStackChange> {
         let opcode = memory.read_opcode(instruction_pointer);
+        let call_stack = self.call_stack_mut(core_id);
         match opcode {
             Ok(Opcode::CallImmediate { .. })
             | Ok(Opcode::CallRegister { .. })
             | Ok(Opcode::CallPointer { .. }) => {
-                self.call_stack.push(instruction_pointer);
-            }
-            Ok(Opcode::Return {}) => {
-                self.call_stack.pop();
+                call_stack.push(instruction_pointer);
+                Some(CallStackChange::Pushed)
             }
-            _ => {}
+            Ok(Opcode::Return {}) => call_stack.pop().map(CallStackChange::Popped),
+            _ => None,
         }
     }
 }
 
+/// The register names of the expressions of conditions.
+fn register_name(index: usize) -> String {
+    match Register(index as u8) {
+        Processor::FRAME_POINTER => "fp".to_string(),
+        Processor::FLAGS => "flags".to_string(),
+        Processor::INSTRUCTION_POINTER => "ip".to_string(),
+        Processor::STACK_POINTER => "sp".to_string(),
+        _ => format!("r{}", index),
+    }
+}
+
+/// Parses the condition and log message of the breakpoint.
+fn create_breakpoint(
+    location: tcp_protocol::BreakpointLocation,
+) -> Result<(Address, Breakpoint), String> {
+    use tcp_protocol::BreakpointLocation::*;
+
+    match location {
+        Plain(location) => Ok((location, Breakpoint::default())),
+        Detailed {
+            location,
+            condition,
+            ignore_count,
+            log_message,
+        } => {
+            let condition = condition
+                .map(|condition| Expression::parse(&condition))
+                .transpose()
+                .map_err(|error| {
+                    format!(
+                        "Invalid condition of breakpoint at {:#x}: {}",
+                        location, error
+                    )
+                })?;
+            let log_message = log_message
+                .map(|message| LogMessage::parse(&message))
+                .transpose()
+                .map_err(|error| {
+                    format!(
+                        "Invalid log message of logpoint at {:#x}: {}",
+                        location, error
+                    )
+                })?;
+            Ok((
+                location,
+                Breakpoint::new(condition, ignore_count, log_message),
+            ))
+        }
+    }
+}
+
+/// Returns the part of the block with the given start address and length that lies within
+/// the memory.
+fn clamp_to_memory(address: Address, length: usize) -> Range<usize> {
+    let start = (address as usize).min(Memory::SIZE);
+    start..start.saturating_add(length).min(Memory::SIZE)
+}
+
 impl Debugger {
     fn new(receiver: Receiver<DebugMessage>, breakpoint_sender: Sender<DebugCommand>) -> Self {
         Self {
@@ -291,11 +716,15 @@ impl Debugger {
             breakpoint_sender,
             started: false,
             start_notifications: Vec::new(),
+            has_controller: false,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            break_responses: Vec::new(),
         }
     }
 
-    fn run(mut self) {
-        let mut tcp = TcpHandler::start();
+    fn run(mut self, address: SocketAddr) {
+        let mut tcp = TcpHandler::start(address);
         let tcp_poll = tick(TCP_POLL_INTERVAL);
 
         loop {
@@ -315,6 +744,10 @@ impl Debugger {
                     self.handle_debug_message(message, &mut tcp)
                 }
             };
+            // the controlling client may also be lost while sending
+            if self.has_controller && !tcp.has_controller() {
+                self.detach();
+            }
         }
     }
 
@@ -327,18 +760,13 @@ impl Debugger {
 
         match result {
             Ok(PollReturn::Nothing | PollReturn::ClientDisconnected) => {}
-            Ok(PollReturn::ClientConnected) => {
-                let message = &tcp_protocol::Response::Hello {
-                    pid: std::process::id(),
-                };
-                self.handle_tcp_result(tcp.send(message));
-            }
+            Ok(PollReturn::ClientConnected(id, role)) => self.greet(tcp, id, role),
             Ok(PollReturn::ReceivedRequests(requests)) => {
                 for request in requests {
                     if let tcp_protocol::Request::Terminate {} = request {
                         should_terminate = ShouldTerminate::Yes;
                     }
-                    self.handle_request(request);
+                    self.handle_request(request, tcp);
                 }
             }
             Err(_) => self.handle_tcp_result(result),
@@ -348,36 +776,96 @@ impl Debugger {
     }
 
     fn handle_debug_message(&mut self, message: DebugMessage, tcp: &mut TcpHandler) {
-        match message {
+        let response = match message {
             DebugMessage::Stop => unreachable!(),
             DebugMessage::WaitForStart(wait_group) => {
                 if !self.started {
                     self.start_notifications.push(wait_group);
                 }
+                return;
             }
-            DebugMessage::HitBreakpoint(location) => {
-                let message = tcp_protocol::Response::HitBreakpoint { location };
-                self.handle_tcp_result(tcp.send(&message));
-            }
-            DebugMessage::Breaking(location) => {
-                let message = tcp_protocol::Response::Breaking { location };
-                self.handle_tcp_result(tcp.send(&message));
+            DebugMessage::HitBreakpoint(location, hit_count) => {
+                tcp_protocol::Response::HitBreakpoint {
+                    location,
+                    hit_count,
+                }
             }
-            DebugMessage::Pausing(location) => {
-                let message = tcp_protocol::Response::Pausing { location };
-                self.handle_tcp_result(tcp.send(&message));
+            DebugMessage::Log(location, message) => {
+                tcp_protocol::Response::Log { location, message }
             }
+            DebugMessage::HitWatchpoint(location, hit) => tcp_protocol::Response::HitWatchpoint {
+                location,
+                address: hit.address,
+                kind: hit.kind,
+                old_value: hit.old_value,
+                new_value: hit.new_value,
+            },
+            DebugMessage::Breaking(location) => tcp_protocol::Response::Breaking { location },
+            DebugMessage::Pausing(location) => tcp_protocol::Response::Pausing { location },
             DebugMessage::BreakState {
                 registers,
                 call_stack,
-            } => {
-                let message = tcp_protocol::Response::BreakState {
-                    registers,
-                    call_stack,
-                };
-                self.handle_tcp_result(tcp.send(&message));
+            } => tcp_protocol::Response::BreakState {
+                registers,
+                call_stack,
+            },
+            DebugMessage::Memory { address, bytes } => tcp_protocol::Response::Memory {
+                address,
+                bytes: tcp_protocol::HexBytes(bytes),
+            },
+            DebugMessage::HistoryExhausted => tcp_protocol::Response::Error {
+                message: HISTORY_EXHAUSTED_MESSAGE.to_string(),
+            },
+        };
+        match response {
+            // a break is announced by the break state followed by the reason
+            tcp_protocol::Response::BreakState { .. } => {
+                self.break_responses = vec![response.clone()];
             }
+            tcp_protocol::Response::HitBreakpoint { .. }
+            | tcp_protocol::Response::HitWatchpoint { .. }
+            | tcp_protocol::Response::Breaking { .. }
+            | tcp_protocol::Response::Pausing { .. } => self.break_responses.push(response.clone()),
+            _ => {}
         }
+        self.handle_tcp_result(tcp.send(&response));
+    }
+
+    /// Sends the hello message and, while breaking, the current break to a new client.
+    fn greet(&mut self, tcp: &mut TcpHandler, id: ClientId, role: Role) {
+        if role == Role::Controller {
+            self.has_controller = true;
+        }
+        let hello = tcp_protocol::Response::Hello {
+            pid: std::process::id(),
+            role,
+        };
+        self.handle_tcp_result(tcp.send_to(id, &hello));
+        for response in &self.break_responses {
+            self.handle_tcp_result(tcp.send_to(id, response));
+        }
+    }
+
+    /// Removes the breakpoints and watchpoints of the lost controlling client and continues
+    /// the execution, so that the machine keeps running until another client connects. The
+    /// new client can pause it by sending `StartExecution` with `stop_on_entry`.
+    fn detach(&mut self) {
+        self.has_controller = false;
+        if !self.breakpoints.is_empty() {
+            let locations = std::mem::take(&mut self.breakpoints);
+            self.send_to_breakpoint_handler(DebugCommand::RemoveBreakpoints(locations));
+        }
+        if !self.watchpoints.is_empty() {
+            let watchpoints = std::mem::take(&mut self.watchpoints);
+            self.send_to_breakpoint_handler(DebugCommand::RemoveWatchpoints(watchpoints));
+        }
+        self.resume(DebugCommand::Continue);
+    }
+
+    /// Sends a command that ends the current break.
+    fn resume(&mut self, command: DebugCommand) {
+        self.break_responses.clear();
+        self.send_to_breakpoint_handler(command);
     }
 
     fn handle_tcp_result<T>(&self, result: tcp_protocol::Result<T>) {
@@ -390,7 +878,7 @@ impl Debugger {
         }
     }
 
-    fn handle_request(&mut self, request: tcp_protocol::Request) {
+    fn handle_request(&mut self, request: tcp_protocol::Request, tcp: &mut TcpHandler) {
         match request {
             tcp_protocol::Request::StartExecution { stop_on_entry } => {
                 if stop_on_entry {
@@ -400,20 +888,57 @@ impl Debugger {
                 self.start_notifications.clear(); // ==> notify all
             }
             tcp_protocol::Request::SetBreakpoints { locations } => {
-                self.send_to_breakpoint_handler(DebugCommand::SetBreakpoints(locations))
+                let mut breakpoints = Vec::with_capacity(locations.len());
+                for location in locations {
+                    match create_breakpoint(location) {
+                        Ok(breakpoint) => {
+                            if !self.breakpoints.contains(&breakpoint.0) {
+                                self.breakpoints.push(breakpoint.0);
+                            }
+                            breakpoints.push(breakpoint);
+                        }
+                        Err(message) => {
+                            let message = tcp_protocol::Response::Error { message };
+                            self.handle_tcp_result(tcp.send(&message));
+                        }
+                    }
+                }
+                self.send_to_breakpoint_handler(DebugCommand::SetBreakpoints(breakpoints))
             }
             tcp_protocol::Request::RemoveBreakpoints { locations } => {
+                self.breakpoints
+                    .retain(|location| !locations.contains(location));
                 self.send_to_breakpoint_handler(DebugCommand::RemoveBreakpoints(locations))
             }
-            tcp_protocol::Request::Continue {} => {
-                self.send_to_breakpoint_handler(DebugCommand::Continue)
+            tcp_protocol::Request::SetWatchpoints { watchpoints } => {
+                self.watchpoints.extend(watchpoints.iter().cloned());
+                self.send_to_breakpoint_handler(DebugCommand::SetWatchpoints(watchpoints))
+            }
+            tcp_protocol::Request::RemoveWatchpoints { watchpoints } => {
+                self.watchpoints
+                    .retain(|watchpoint| !watchpoints.contains(watchpoint));
+                self.send_to_breakpoint_handler(DebugCommand::RemoveWatchpoints(watchpoints))
             }
-            tcp_protocol::Request::StepOne {} => {
-                self.send_to_breakpoint_handler(DebugCommand::StepOne)
+            tcp_protocol::Request::Continue {} => self.resume(DebugCommand::Continue),
+            tcp_protocol::Request::StepOne {} => self.resume(DebugCommand::StepOne),
+            tcp_protocol::Request::StepOver {} => self.resume(DebugCommand::StepOver),
+            tcp_protocol::Request::StepOut {} => self.resume(DebugCommand::StepOut),
+            tcp_protocol::Request::RunToLocation { location } => {
+                self.resume(DebugCommand::RunToLocation(location))
             }
+            tcp_protocol::Request::StepBack {} => self.resume(DebugCommand::StepBack),
+            tcp_protocol::Request::ReverseContinue {} => self.resume(DebugCommand::ReverseContinue),
             tcp_protocol::Request::SetRegister { register, value } => {
                 self.send_to_breakpoint_handler(DebugCommand::SetRegister(register, value))
             }
+            tcp_protocol::Request::ReadMemory { address, length } => {
+                self.send_to_breakpoint_handler(DebugCommand::ReadMemory { address, length })
+            }
+            tcp_protocol::Request::WriteMemory { address, bytes } => self
+                .send_to_breakpoint_handler(DebugCommand::WriteMemory {
+                    address,
+                    bytes: bytes.0,
+                }),
             tcp_protocol::Request::Terminate {} => {
                 self.send_to_breakpoint_handler(DebugCommand::Terminate);
             }
@@ -421,11 +946,447 @@ impl Debugger {
     }
 
     fn send_to_breakpoint_handler(&mut self, message: DebugCommand) {
-        match self.breakpoint_sender.try_send(message) {
-            Ok(_) | Err(crossbeam_channel::TrySendError::Full(_)) => {}
-            Err(crossbeam_channel::TrySendError::Disconnected(_)) => {
-                panic!("Breakpoint channel closed before debugger was stopped.")
+        send_command(&self.breakpoint_sender, message);
+    }
+}
+
+/// Sends the command to the debug handle (dropping it if the channel is full).
+fn send_command(sender: &Sender<DebugCommand>, command: DebugCommand) {
+    match sender.try_send(command) {
+        Ok(_) | Err(crossbeam_channel::TrySendError::Full(_)) => {}
+        Err(crossbeam_channel::TrySendError::Disconnected(_)) => {
+            panic!("Breakpoint channel closed before debugger was stopped.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpStream;
+
+    use crate::{
+        address_constants::ENTRY_POINT,
+        display::MockDisplay,
+        machine::{
+            tests::{create_machine_with_opcodes, create_multicore_machine},
+            Machine,
+        },
+        Instruction, Size,
+    };
+
+    use super::{
+        tcp_protocol::tests::{poll_until_event, read_response},
+        watchpoint::WatchKind,
+        *,
+    };
+
+    const SUBROUTINE: Address = ENTRY_POINT + 5 * Instruction::SIZE as Address;
+    const AFTER_CALL: Address = ENTRY_POINT + 2 * Instruction::SIZE as Address;
+    const HISTORY_BYTES: usize = 1 << 20;
+
+    /// Creates a machine that calls a subroutine and is breaking at its entry point. The
+    /// channels replace the debugger thread.
+    fn create_machine_with_debugger() -> (
+        Machine<MockDisplay>,
+        Sender<DebugCommand>,
+        Receiver<DebugMessage>,
+    ) {
+        let mut machine = create_machine_with_opcodes(&subroutine_program());
+        let (sender, receiver) = attach_debugger(&mut machine);
+        (machine, sender, receiver)
+    }
+
+    fn subroutine_program() -> [Opcode; 7] {
+        [
+            Opcode::MoveRegisterImmediate {
+                register: Register(1),
+                immediate: 1,
+            },
+            Opcode::CallImmediate {
+                immediate: SUBROUTINE,
+            },
+            Opcode::MoveRegisterImmediate {
+                register: Register(2),
+                immediate: 2,
+            },
+            Opcode::NoOp {},
+            Opcode::NoOp {},
+            Opcode::MoveRegisterImmediate {
+                register: Register(3),
+                immediate: 3,
+            },
+            Opcode::Return {},
+        ]
+    }
+
+    /// Attaches a debug handle that is breaking at the current instruction.
+    fn attach_debugger(
+        machine: &mut Machine<MockDisplay>,
+    ) -> (Sender<DebugCommand>, Receiver<DebugMessage>) {
+        let (sender, receiver) = bounded(CHANNEL_BOUND);
+        let (command_sender, command_receiver) = bounded(CHANNEL_BOUND);
+        let mut debug_handle = DebugHandle::new(sender, command_receiver, HISTORY_BYTES);
+        debug_handle.state = BreakpointHandleState::Breaking;
+        machine.attach_debug_handle(debug_handle);
+        (command_sender, receiver)
+    }
+
+    /// Executes cycles until the handler reports that it is breaking and returns the message
+    /// (other messages are dropped).
+    fn run_until_break(
+        machine: &mut Machine<MockDisplay>,
+        receiver: &Receiver<DebugMessage>,
+    ) -> DebugMessage {
+        for _ in 0..100 {
+            machine.execute_next_instruction();
+            let message = receiver.try_iter().find(|message| {
+                matches!(
+                    message,
+                    DebugMessage::Breaking(_)
+                        | DebugMessage::HitBreakpoint(..)
+                        | DebugMessage::HitWatchpoint(..)
+                )
+            });
+            if let Some(message) = message {
+                return message;
             }
         }
+        panic!("the debug handle did not start breaking");
+    }
+
+    fn assert_breaking_at(message: DebugMessage, location: Address) {
+        assert!(matches!(message, DebugMessage::Breaking(actual) if actual == location));
+    }
+
+    #[test]
+    fn step_over_executes_call() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        sender.send(DebugCommand::StepOver).unwrap();
+        assert_breaking_at(
+            run_until_break(&mut machine, &receiver),
+            ENTRY_POINT + Instruction::SIZE as Address,
+        );
+        sender.send(DebugCommand::StepOver).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), AFTER_CALL);
+        assert_eq!(machine.processors[0].registers[Register(3)], 3);
+        assert_eq!(machine.processors[0].registers[Register(2)], 0);
+    }
+
+    #[test]
+    fn step_out_returns_to_caller() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        sender
+            .send(DebugCommand::RunToLocation(SUBROUTINE))
+            .unwrap();
+        run_until_break(&mut machine, &receiver);
+        assert_breaking_at(run_until_break(&mut machine, &receiver), SUBROUTINE);
+        assert_eq!(machine.processors[0].registers[Register(1)], 1);
+        sender.send(DebugCommand::StepOut).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), AFTER_CALL);
+        assert_eq!(machine.processors[0].registers[Register(3)], 3);
+    }
+
+    #[test]
+    fn call_stacks_are_tracked_per_core() {
+        let mut machine = create_multicore_machine(&subroutine_program(), 2);
+        let (sender, receiver) = attach_debugger(&mut machine);
+        sender
+            .send(DebugCommand::RunToLocation(SUBROUTINE))
+            .unwrap();
+        run_until_break(&mut machine, &receiver);
+        // both cores executed the call, but the boot core reaches the subroutine first
+        let call_stack = loop {
+            machine.execute_next_instruction();
+            if let Ok(DebugMessage::BreakState { call_stack, .. }) = receiver.try_recv() {
+                break call_stack;
+            }
+        };
+        assert_eq!(call_stack, [ENTRY_POINT + Instruction::SIZE as Address]);
+        assert_breaking_at(receiver.recv().unwrap(), SUBROUTINE);
+        assert_eq!(machine.processors[1].get_instruction_pointer(), SUBROUTINE);
+    }
+
+    #[test]
+    fn breakpoints_take_precedence_over_step_over() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        sender
+            .send(DebugCommand::SetBreakpoints(vec![(
+                SUBROUTINE,
+                Breakpoint::default(),
+            )]))
+            .unwrap();
+        sender.send(DebugCommand::StepOne).unwrap();
+        sender.send(DebugCommand::StepOver).unwrap();
+        run_until_break(&mut machine, &receiver);
+        run_until_break(&mut machine, &receiver);
+        assert!(matches!(
+            run_until_break(&mut machine, &receiver),
+            DebugMessage::HitBreakpoint(SUBROUTINE, 1)
+        ));
+        sender.send(DebugCommand::Continue).unwrap();
+        for _ in 0..10 {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(machine.processors[0].registers[Register(2)], 2);
+    }
+
+    #[test]
+    fn conditional_breakpoints_and_logpoints_in_loop() {
+        let mut machine = create_machine_with_opcodes(&[
+            Opcode::AddTargetSourceImmediate {
+                target: Register(1),
+                source: Register(1),
+                immediate: 1,
+            },
+            Opcode::JumpImmediate {
+                immediate: ENTRY_POINT,
+            },
+        ]);
+        let (sender, receiver) = attach_debugger(&mut machine);
+        let jump = ENTRY_POINT + Instruction::SIZE as Address;
+        let breakpoint = |condition: &str, ignore_count, log_message: Option<&str>| {
+            Breakpoint::new(
+                Some(Expression::parse(condition).unwrap()),
+                ignore_count,
+                log_message.map(|message| LogMessage::parse(message).unwrap()),
+            )
+        };
+        sender
+            .send(DebugCommand::SetBreakpoints(vec![
+                (ENTRY_POINT, breakpoint("r1 % 100 == 50", 2, None)),
+                (jump, breakpoint("r1 % 100 == 0", 0, Some("r1 = {r1}"))),
+            ]))
+            .unwrap();
+        sender.send(DebugCommand::Continue).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        let mut logs = Vec::new();
+        let hit_count = loop {
+            machine.execute_next_instruction();
+            match receiver.try_recv() {
+                Ok(DebugMessage::Log(location, message)) => {
+                    assert_eq!(location, jump);
+                    logs.push(message);
+                }
+                Ok(DebugMessage::HitBreakpoint(ENTRY_POINT, hit_count)) => break hit_count,
+                _ => {}
+            }
+        };
+        assert_eq!(hit_count, 3);
+        assert_eq!(machine.processors[0].registers[Register(1)], 250);
+        assert_eq!(logs, ["r1 = 100", "r1 = 200"]);
+    }
+
+    #[test]
+    fn watchpoint_breaks_after_write() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        let stack_pointer = machine.processors[0].registers[Processor::STACK_POINTER];
+        sender
+            .send(DebugCommand::SetWatchpoints(vec![Watchpoint {
+                address: stack_pointer,
+                length: Word::SIZE as Word,
+                kind: WatchKind::Write,
+                value: None,
+            }]))
+            .unwrap();
+        sender.send(DebugCommand::Continue).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        // the call pushes the return address
+        let message = run_until_break(&mut machine, &receiver);
+        assert!(matches!(
+            message,
+            DebugMessage::HitWatchpoint(location, WatchpointHit { address, new_value, .. })
+                if location == ENTRY_POINT + Instruction::SIZE as Address
+                    && address == stack_pointer
+                    && new_value == AFTER_CALL
+        ));
+        assert_eq!(machine.processors[0].get_instruction_pointer(), SUBROUTINE);
+    }
+
+    #[test]
+    fn step_back_restores_registers_memory_and_call_stack() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        let stack_pointer = machine.processors[0].registers[Processor::STACK_POINTER];
+        sender
+            .send(DebugCommand::RunToLocation(SUBROUTINE))
+            .unwrap();
+        run_until_break(&mut machine, &receiver);
+        assert_breaking_at(run_until_break(&mut machine, &receiver), SUBROUTINE);
+        assert_eq!(machine.memory.read_data(stack_pointer), AFTER_CALL);
+
+        sender.send(DebugCommand::StepBack).unwrap();
+        machine.execute_next_instruction();
+        let messages: Vec<_> = receiver.try_iter().collect();
+        assert!(matches!(
+            messages.as_slice(),
+            [DebugMessage::BreakState { call_stack, .. }, DebugMessage::Breaking(location)]
+                if call_stack.is_empty() && *location == ENTRY_POINT + Instruction::SIZE as Address
+        ));
+        let processor = &machine.processors[0];
+        assert_eq!(processor.registers[Processor::STACK_POINTER], stack_pointer);
+        assert_eq!(processor.registers[Register(1)], 1);
+        assert_eq!(machine.memory.read_data(stack_pointer), 0);
+
+        sender.send(DebugCommand::StepBack).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        assert_eq!(machine.processors[0].registers[Register(1)], 0);
+        assert_eq!(machine.processors[0].get_cycle_count(), 0);
+
+        sender.send(DebugCommand::StepBack).unwrap();
+        machine.execute_next_instruction();
+        let messages: Vec<_> = receiver.try_iter().collect();
+        assert!(matches!(
+            messages.as_slice(),
+            [DebugMessage::HistoryExhausted, DebugMessage::BreakState { .. }, DebugMessage::Breaking(location)]
+                if *location == ENTRY_POINT
+        ));
+
+        // execution continues normally from the restored state
+        sender.send(DebugCommand::StepOver).unwrap();
+        run_until_break(&mut machine, &receiver);
+        sender.send(DebugCommand::StepOver).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), AFTER_CALL);
+        assert_eq!(machine.processors[0].registers[Register(3)], 3);
+    }
+
+    #[test]
+    fn reverse_continue_stops_at_previous_breakpoint() {
+        let mut machine = create_machine_with_opcodes(&[
+            Opcode::AddTargetSourceImmediate {
+                target: Register(1),
+                source: Register(1),
+                immediate: 1,
+            },
+            Opcode::JumpImmediate {
+                immediate: ENTRY_POINT,
+            },
+        ]);
+        let (sender, receiver) = attach_debugger(&mut machine);
+        let jump = ENTRY_POINT + Instruction::SIZE as Address;
+        let set_breakpoint = |condition: &str| {
+            let condition = Expression::parse(condition).unwrap();
+            sender
+                .send(DebugCommand::SetBreakpoints(vec![(
+                    jump,
+                    Breakpoint::new(Some(condition), 0, None),
+                )]))
+                .unwrap();
+        };
+        set_breakpoint("r1 == 2");
+        sender.send(DebugCommand::Continue).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        run_until_break(&mut machine, &receiver);
+        set_breakpoint("r1 % 5 == 2");
+        sender.send(DebugCommand::Continue).unwrap();
+        run_until_break(&mut machine, &receiver);
+        assert_eq!(machine.processors[0].registers[Register(1)], 7);
+
+        sender.send(DebugCommand::ReverseContinue).unwrap();
+        assert!(matches!(
+            run_until_break(&mut machine, &receiver),
+            DebugMessage::HitBreakpoint(location, _) if location == jump
+        ));
+        assert_eq!(machine.processors[0].registers[Register(1)], 2);
+
+        sender.send(DebugCommand::ReverseContinue).unwrap();
+        assert_breaking_at(run_until_break(&mut machine, &receiver), ENTRY_POINT);
+        assert_eq!(machine.processors[0].registers[Register(1)], 0);
+    }
+
+    #[test]
+    fn writing_code_keeps_instruction_cache_coherent() {
+        let (mut machine, sender, receiver) = create_machine_with_debugger();
+        let instruction = Opcode::MoveRegisterImmediate {
+            register: Register(1),
+            immediate: 42,
+        }
+        .as_instruction();
+        sender
+            .send(DebugCommand::WriteMemory {
+                address: ENTRY_POINT,
+                bytes: instruction.to_be_bytes().to_vec(),
+            })
+            .unwrap();
+        sender.send(DebugCommand::StepOne).unwrap();
+        run_until_break(&mut machine, &receiver);
+        run_until_break(&mut machine, &receiver);
+        assert_eq!(machine.processors[0].registers[Register(1)], 42);
+    }
+
+    #[test]
+    fn lost_controller_is_detached_and_break_is_replayed() {
+        let (_, receiver) = bounded(1);
+        let (command_sender, commands) = bounded(CHANNEL_BOUND);
+        let mut debugger = Debugger::new(receiver, command_sender);
+        let mut tcp = TcpHandler::start("127.0.0.1:0".parse().unwrap());
+
+        debugger.handle_request(
+            tcp_protocol::Request::SetBreakpoints {
+                locations: vec![tcp_protocol::BreakpointLocation::Plain(SUBROUTINE)],
+            },
+            &mut tcp,
+        );
+        debugger.handle_debug_message(
+            DebugMessage::BreakState {
+                registers: vec![0; 256],
+                call_stack: Vec::new(),
+            },
+            &mut tcp,
+        );
+        debugger.handle_debug_message(DebugMessage::Pausing(ENTRY_POINT), &mut tcp);
+        assert!(matches!(
+            debugger.break_responses.as_slice(),
+            [
+                tcp_protocol::Response::BreakState { .. },
+                tcp_protocol::Response::Pausing {
+                    location: ENTRY_POINT
+                }
+            ]
+        ));
+        let connect = |debugger: &mut Debugger, tcp: &mut TcpHandler| {
+            let mut client = TcpStream::connect(tcp.local_address()).unwrap();
+            client
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let PollReturn::ClientConnected(id, role) = poll_until_event(tcp) else {
+                panic!("the client did not connect");
+            };
+            debugger.greet(tcp, id, role);
+            assert!(read_response(&mut client).starts_with(r#"{"Hello":"#));
+            client
+        };
+
+        // a client connecting while breaking receives the current break
+        let mut client = connect(&mut debugger, &mut tcp);
+        assert!(read_response(&mut client).starts_with(r#"{"BreakState":"#));
+        assert_eq!(
+            read_response(&mut client),
+            format!(r#"{{"Pausing":{{"location":{}}}}}"#, ENTRY_POINT)
+        );
+        drop(client);
+        assert!(matches!(
+            poll_until_event(&mut tcp),
+            PollReturn::ClientDisconnected
+        ));
+
+        debugger.detach();
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::SetBreakpoints(_))
+        ));
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::RemoveBreakpoints(locations)) if locations == [SUBROUTINE]
+        ));
+        assert!(matches!(commands.try_recv(), Ok(DebugCommand::Continue)));
+        assert!(debugger.break_responses.is_empty());
+
+        // the break ended with the detach, so it is not replayed to the next client
+        let mut client = connect(&mut debugger, &mut tcp);
+        debugger.handle_debug_message(DebugMessage::Breaking(SUBROUTINE), &mut tcp);
+        assert_eq!(
+            read_response(&mut client),
+            format!(r#"{{"Breaking":{{"location":{}}}}}"#, SUBROUTINE)
+        );
     }
 }
@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use crate::{memory::Memory, processor::Processor};
+
+use super::expression::{Expression, ParseError};
+
+#[derive(Debug)]
+enum LogMessagePart {
+    Text(String),
+    Expression {
+        expression: Expression,
+        hexadecimal: bool,
+    },
+}
+
+/// The message of a logpoint. Expressions are enclosed in braces (`{r1}`, or `{r1:x}` for
+/// hexadecimal output) and braces are escaped by doubling them.
+#[derive(Debug)]
+pub struct LogMessage(Vec<LogMessagePart>);
+
+impl LogMessage {
+    pub fn parse(template: &str) -> Result<Self, ParseError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut characters = template.char_indices().peekable();
+        while let Some((position, character)) = characters.next() {
+            match character {
+                '{' | '}' if characters.next_if(|&(_, next)| next == character).is_some() => {
+                    text.push(character);
+                }
+                '{' => {
+                    let start = position + 1;
+                    let Some(end) = template[start..].find('}').map(|length| start + length) else {
+                        return Err(ParseError {
+                            position,
+                            message: "unclosed '{'".to_string(),
+                        });
+                    };
+                    let (source, hexadecimal) = match template[start..end].strip_suffix(":x") {
+                        Some(source) => (source, true),
+                        None => (&template[start..end], false),
+                    };
+                    let expression = Expression::parse(source).map_err(|error| ParseError {
+                        position: start + error.position,
+                        message: error.message,
+                    })?;
+                    parts.push(LogMessagePart::Text(std::mem::take(&mut text)));
+                    parts.push(LogMessagePart::Expression {
+                        expression,
+                        hexadecimal,
+                    });
+                    while characters.next_if(|&(index, _)| index <= end).is_some() {}
+                }
+                '}' => {
+                    return Err(ParseError {
+                        position,
+                        message: "unmatched '}'".to_string(),
+                    })
+                }
+                _ => text.push(character),
+            }
+        }
+        parts.push(LogMessagePart::Text(text));
+        Ok(Self(parts))
+    }
+
+    /// Formats the message. Expressions that cannot be evaluated are replaced by an error
+    /// description.
+    pub fn format(&self, processor: &Processor, memory: &Memory) -> String {
+        let mut message = String::new();
+        for part in &self.0 {
+            let _ = match part {
+                LogMessagePart::Text(text) => write!(message, "{}", text),
+                LogMessagePart::Expression {
+                    expression,
+                    hexadecimal,
+                } => match (expression.evaluate(processor, memory), hexadecimal) {
+                    (Ok(value), true) => write!(message, "{:#x}", value),
+                    (Ok(value), false) => write!(message, "{}", value),
+                    (Err(error), _) => write!(message, "<{}>", error),
+                },
+            };
+        }
+        message
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BreakpointAction {
+    Ignore,
+    Break,
+    Log(String),
+}
+
+/// A breakpoint is hit when the instruction at its location is about to be executed and its
+/// condition is met. Conditions that cannot be evaluated count as met. The first
+/// `ignore_count` hits are ignored. Logpoints (breakpoints with a log message) send the
+/// message instead of breaking.
+#[derive(Debug, Default)]
+pub struct Breakpoint {
+    pub condition: Option<Expression>,
+    pub ignore_count: u64,
+    pub log_message: Option<LogMessage>,
+    hit_count: u64,
+}
+
+impl Breakpoint {
+    pub fn new(
+        condition: Option<Expression>,
+        ignore_count: u64,
+        log_message: Option<LogMessage>,
+    ) -> Self {
+        Self {
+            condition,
+            ignore_count,
+            log_message,
+            hit_count: 0,
+        }
+    }
+
+    pub fn hit_count(&self) -> u64 {
+        self.hit_count
+    }
+
+    pub fn is_condition_met(&self, processor: &Processor, memory: &Memory) -> bool {
+        match &self.condition {
+            Some(condition) => !matches!(condition.evaluate(processor, memory), Ok(0)),
+            None => true,
+        }
+    }
+
+    pub fn hit(&mut self, processor: &Processor, memory: &Memory) -> BreakpointAction {
+        if !self.is_condition_met(processor, memory) {
+            return BreakpointAction::Ignore;
+        }
+        self.hit_count += 1;
+        if self.hit_count <= self.ignore_count {
+            return BreakpointAction::Ignore;
+        }
+        match &self.log_message {
+            Some(message) => BreakpointAction::Log(message.format(processor, memory)),
+            None => BreakpointAction::Break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Register, Word};
+
+    use super::*;
+
+    #[test]
+    fn log_message_is_formatted() {
+        let mut processor = Processor::new(false, 0, 1);
+        processor.registers[Register(1)] = 255;
+        let message = LogMessage::parse("{{r1}} = {r1}, {r1:x}, {r1 / 0}").unwrap();
+        assert_eq!(
+            message.format(&processor, &Memory::new()),
+            "{r1} = 255, 0xff, <division by zero>"
+        );
+        assert_eq!(LogMessage::parse("{r1").unwrap_err().position, 0);
+        assert_eq!(LogMessage::parse("a }").unwrap_err().position, 2);
+        assert_eq!(LogMessage::parse("ab {r1 +}").unwrap_err().position, 8);
+    }
+
+    #[test]
+    fn condition_and_ignore_count_are_applied() {
+        let mut processor = Processor::new(false, 0, 1);
+        let memory = Memory::new();
+        let mut breakpoint =
+            Breakpoint::new(Some(Expression::parse("r1 % 2 == 0").unwrap()), 1, None);
+        let actions: Vec<_> = (0..5 as Word)
+            .map(|value| {
+                processor.registers[Register(1)] = value;
+                breakpoint.hit(&processor, &memory)
+            })
+            .collect();
+        use BreakpointAction::*;
+        assert_eq!(actions, [Ignore, Ignore, Break, Ignore, Break]);
+        assert_eq!(breakpoint.hit_count(), 3);
+    }
+}
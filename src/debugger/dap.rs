@@ -0,0 +1,934 @@
+use std::{collections::VecDeque, net::SocketAddr};
+
+use crossbeam_channel::{select, tick, Receiver, Sender};
+use crossbeam_utils::sync::WaitGroup;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use super::{
+    create_breakpoint,
+    dap_protocol::{
+        self, DapHandler, DisassembleArguments, DisconnectArguments, LaunchArguments, Message,
+        PollReturn, ReadMemoryArguments, Request, SetBreakpointsArguments,
+        SetInstructionBreakpointsArguments, SetVariableArguments, VariablesArguments,
+        WriteMemoryArguments,
+    },
+    expression::Expression,
+    register_name, send_command,
+    tcp_protocol::BreakpointLocation,
+    DebugCommand, DebugMessage, ShouldTerminate, HISTORY_EXHAUSTED_MESSAGE, TCP_POLL_INTERVAL,
+};
+use crate::{
+    memory::Memory, opcodes::Opcode, processor::Processor, Address, Instruction, Size, Word,
+};
+
+/// The cores are not distinguished, so there is only a single thread (multiple cores are
+/// rejected, see `DebugProtocol::supports_multiple_cores`).
+const THREAD_ID: i64 = 1;
+const REGISTERS_REFERENCE: i64 = 1;
+const STACK_REFERENCE: i64 = 2;
+/// The number of words below the stack pointer that are shown as variables.
+const NUM_STACK_VARIABLES: Word = 16;
+
+type RequestResult = Result<Option<Value>, String>;
+
+/// Requests that are answered as soon as the debug handle sent the contents of the memory.
+#[derive(Debug)]
+enum PendingMemoryRequest {
+    ReadMemory {
+        request_seq: i64,
+        count: usize,
+    },
+    WriteMemory {
+        request_seq: i64,
+    },
+    StackVariables {
+        request_seq: i64,
+    },
+    Disassemble {
+        request_seq: i64,
+        /// The address of the first instruction (might lie before the memory).
+        start: i64,
+        count: usize,
+    },
+}
+
+/// Serves the Debug Adapter Protocol instead of the custom protocol of `Debugger`. Breakpoints
+/// are set on instructions since ROMs have no debug information. Stack frames are derived from
+/// the tracked call stack and the registers and the top of the stack are shown as variables.
+pub struct DapDebugger {
+    receiver: Receiver<DebugMessage>,
+    command_sender: Sender<DebugCommand>,
+    started: bool,
+    start_notifications: Vec<WaitGroup>,
+    has_client: bool,
+    stop_on_entry: bool,
+    terminate_on_disconnect: bool,
+    registers: Vec<Word>,
+    call_stack: Vec<Address>,
+    breakpoints: Vec<Address>,
+    pending_memory_requests: VecDeque<PendingMemoryRequest>,
+    outgoing: Vec<Message>,
+}
+
+impl DapDebugger {
+    pub fn new(receiver: Receiver<DebugMessage>, command_sender: Sender<DebugCommand>) -> Self {
+        Self {
+            receiver,
+            command_sender,
+            started: false,
+            start_notifications: Vec::new(),
+            has_client: false,
+            stop_on_entry: false,
+            terminate_on_disconnect: true,
+            registers: Vec::new(),
+            call_stack: Vec::new(),
+            breakpoints: Vec::new(),
+            pending_memory_requests: VecDeque::new(),
+            outgoing: Vec::new(),
+        }
+    }
+
+    pub fn run(self, address: SocketAddr) {
+        self.serve(DapHandler::start(address));
+    }
+
+    fn serve(mut self, mut dap: DapHandler) {
+        let dap_poll = tick(TCP_POLL_INTERVAL);
+
+        loop {
+            let mut should_terminate = ShouldTerminate::No;
+            select! {
+                recv(dap_poll) -> _ => match dap.poll() {
+                    Ok(PollReturn::Nothing | PollReturn::ClientDisconnected) => {}
+                    Ok(PollReturn::ClientConnected) => self.has_client = true,
+                    Ok(PollReturn::ReceivedRequests(requests)) => {
+                        for request in requests {
+                            if let ShouldTerminate::Yes = self.handle_request(request) {
+                                should_terminate = ShouldTerminate::Yes;
+                            }
+                        }
+                    }
+                    Err(error) => eprintln!("Failed DAP operation: {}", error),
+                },
+                recv(self.receiver) -> message => {
+                    let message = message.expect("Debugger cannot receive message on debug interface.");
+                    if let DebugMessage::Stop = message {
+                        self.send_event("exited", json!({ "exitCode": 0 }));
+                        self.send_event("terminated", Value::Null);
+                        should_terminate = ShouldTerminate::Yes;
+                    } else {
+                        self.handle_debug_message(message);
+                    }
+                }
+            };
+            for message in self.outgoing.drain(..) {
+                if let Err(error) = dap.send(&message) {
+                    eprintln!("Failed DAP operation: {}", error);
+                }
+            }
+            // the client is also dropped after failed operations
+            if self.has_client && !dap.has_client() {
+                self.has_client = false;
+                self.detach();
+            }
+            if let ShouldTerminate::Yes = should_terminate {
+                break;
+            }
+        }
+    }
+
+    fn handle_request(&mut self, request: Request) -> ShouldTerminate {
+        let mut should_terminate = ShouldTerminate::No;
+        let arguments = request.arguments;
+        let result = match request.command.as_str() {
+            "initialize" => Ok(Some(capabilities())),
+            "launch" => parse_arguments(arguments).map(|arguments: LaunchArguments| {
+                self.stop_on_entry = arguments.stop_on_entry;
+                self.terminate_on_disconnect = true;
+                Some(Value::Null)
+            }),
+            "attach" => {
+                self.terminate_on_disconnect = false;
+                Ok(Some(Value::Null))
+            }
+            "configurationDone" => {
+                if !self.started && self.stop_on_entry {
+                    self.send_command(DebugCommand::Pause);
+                }
+                self.start();
+                Ok(Some(Value::Null))
+            }
+            "setBreakpoints" => parse_arguments(arguments)
+                .map(|arguments: SetBreakpointsArguments| self.set_source_breakpoints(arguments)),
+            "setInstructionBreakpoints" => parse_arguments(arguments)
+                .map(|arguments| self.set_instruction_breakpoints(arguments)),
+            "threads" => Ok(Some(
+                json!({ "threads": [{ "id": THREAD_ID, "name": "Processor" }] }),
+            )),
+            "stackTrace" => Ok(Some(self.stack_trace())),
+            "scopes" => Ok(Some(json!({
+                "scopes": [
+                    {
+                        "name": "Registers",
+                        "presentationHint": "registers",
+                        "variablesReference": REGISTERS_REFERENCE,
+                        "expensive": false,
+                    },
+                    {
+                        "name": "Stack",
+                        "variablesReference": STACK_REFERENCE,
+                        "expensive": true,
+                    },
+                ]
+            }))),
+            "variables" => parse_arguments(arguments)
+                .and_then(|arguments| self.variables(arguments, request.seq)),
+            "setVariable" => {
+                parse_arguments(arguments).and_then(|arguments| self.set_variable(arguments))
+            }
+            "continue" => {
+                self.send_command(DebugCommand::Continue);
+                Ok(Some(json!({ "allThreadsContinued": true })))
+            }
+            "next" => self.run_command(DebugCommand::StepOver),
+            "stepIn" => self.run_command(DebugCommand::StepOne),
+            "stepOut" => self.run_command(DebugCommand::StepOut),
+            "stepBack" => self.run_command(DebugCommand::StepBack),
+            "reverseContinue" => self.run_command(DebugCommand::ReverseContinue),
+            "pause" => self.run_command(DebugCommand::Pause),
+            "readMemory" => parse_arguments(arguments)
+                .and_then(|arguments| self.read_memory(arguments, request.seq)),
+            "writeMemory" => parse_arguments(arguments)
+                .and_then(|arguments| self.write_memory(arguments, request.seq)),
+            "disassemble" => parse_arguments(arguments)
+                .and_then(|arguments| self.disassemble(arguments, request.seq)),
+            "disconnect" => parse_arguments(arguments).map(|arguments: DisconnectArguments| {
+                match arguments
+                    .terminate_debuggee
+                    .unwrap_or(self.terminate_on_disconnect)
+                {
+                    true => {
+                        self.terminate();
+                        should_terminate = ShouldTerminate::Yes;
+                    }
+                    false => self.detach(),
+                }
+                Some(Value::Null)
+            }),
+            "terminate" => {
+                self.terminate();
+                should_terminate = ShouldTerminate::Yes;
+                Ok(Some(Value::Null))
+            }
+            command => Err(format!("Unsupported request '{}'.", command)),
+        };
+
+        match result {
+            Ok(Some(body)) => self.respond(request.seq, &request.command, Ok(body)),
+            // answered when the contents of the memory arrive
+            Ok(None) => {}
+            Err(message) => self.respond(request.seq, &request.command, Err(message)),
+        }
+        if request.command == "initialize" {
+            self.send_event("initialized", Value::Null);
+        }
+        should_terminate
+    }
+
+    fn handle_debug_message(&mut self, message: DebugMessage) {
+        match message {
+            DebugMessage::Stop => unreachable!(),
+            DebugMessage::WaitForStart(wait_group) => {
+                if !self.started {
+                    self.start_notifications.push(wait_group);
+                }
+            }
+            DebugMessage::HitBreakpoint(..) => self.stopped("instruction breakpoint", None),
+            DebugMessage::Log(_, message) => self.output("console", message),
+            DebugMessage::HitWatchpoint(location, hit) => {
+                let description = format!(
+                    "{:?} access to {:#x} by the instruction at {:#x}",
+                    hit.kind, hit.address, location
+                );
+                self.stopped("data breakpoint", Some(description));
+            }
+            DebugMessage::Breaking(_) => self.stopped("step", None),
+            DebugMessage::Pausing(_) => {
+                let reason = match std::mem::take(&mut self.stop_on_entry) {
+                    true => "entry",
+                    false => "pause",
+                };
+                self.stopped(reason, None);
+            }
+            DebugMessage::BreakState {
+                registers,
+                call_stack,
+            } => {
+                self.registers = registers;
+                self.call_stack = call_stack;
+            }
+            DebugMessage::Memory { address, bytes } => self.complete_memory_request(address, bytes),
+            DebugMessage::HistoryExhausted => {
+                self.output("console", HISTORY_EXHAUSTED_MESSAGE.to_string())
+            }
+        }
+    }
+
+    fn start(&mut self) {
+        self.started = true;
+        self.start_notifications.clear(); // ==> notify all
+    }
+
+    fn terminate(&mut self) {
+        // the debug handle only receives commands after starting
+        self.start();
+        self.send_command(DebugCommand::Terminate);
+    }
+
+    /// Removes all breakpoints and continues the execution, so that the machine keeps running
+    /// without a client.
+    fn detach(&mut self) {
+        if !self.breakpoints.is_empty() {
+            let locations = std::mem::take(&mut self.breakpoints);
+            self.send_command(DebugCommand::RemoveBreakpoints(locations));
+        }
+        self.send_command(DebugCommand::Continue);
+        self.stop_on_entry = false;
+        self.terminate_on_disconnect = true;
+    }
+
+    fn run_command(&mut self, command: DebugCommand) -> RequestResult {
+        self.send_command(command);
+        Ok(Some(Value::Null))
+    }
+
+    fn set_source_breakpoints(&mut self, arguments: SetBreakpointsArguments) -> Option<Value> {
+        let breakpoint = json!({
+            "verified": false,
+            "message": "ROMs have no debug information, use instruction breakpoints instead.",
+        });
+        Some(json!({ "breakpoints": vec![breakpoint; arguments.breakpoints.len()] }))
+    }
+
+    /// Replaces all instruction breakpoints.
+    fn set_instruction_breakpoints(
+        &mut self,
+        arguments: SetInstructionBreakpointsArguments,
+    ) -> Option<Value> {
+        if !self.breakpoints.is_empty() {
+            let locations = std::mem::take(&mut self.breakpoints);
+            self.send_command(DebugCommand::RemoveBreakpoints(locations));
+        }
+        let mut breakpoints = Vec::new();
+        let mut results = Vec::new();
+        for breakpoint in arguments.breakpoints {
+            let created =
+                parse_memory_reference(&breakpoint.instruction_reference, breakpoint.offset)
+                    .and_then(|location| {
+                        let ignore_count = breakpoint
+                            .hit_condition
+                            .as_deref()
+                            .map(parse_hit_condition)
+                            .transpose()?
+                            .unwrap_or(0);
+                        create_breakpoint(BreakpointLocation::Detailed {
+                            location,
+                            condition: breakpoint.condition,
+                            ignore_count,
+                            log_message: None,
+                        })
+                    });
+            match created {
+                Ok((location, created)) => {
+                    results.push(json!({
+                        "verified": true,
+                        "instructionReference": format!("{:#x}", location),
+                    }));
+                    self.breakpoints.push(location);
+                    breakpoints.push((location, created));
+                }
+                Err(message) => results.push(json!({ "verified": false, "message": message })),
+            }
+        }
+        self.send_command(DebugCommand::SetBreakpoints(breakpoints));
+        Some(json!({ "breakpoints": results }))
+    }
+
+    /// The innermost frame is at the instruction pointer, the others are at the calls.
+    fn stack_trace(&self) -> Value {
+        let Some(&instruction_pointer) = self
+            .registers
+            .get(Processor::INSTRUCTION_POINTER.0 as usize)
+        else {
+            return json!({ "stackFrames": [], "totalFrames": 0 });
+        };
+        let frames: Vec<_> = std::iter::once(instruction_pointer)
+            .chain(self.call_stack.iter().rev().copied())
+            .enumerate()
+            .map(|(id, address)| {
+                json!({
+                    "id": id,
+                    "name": format!("{:#010x}", address),
+                    "line": 0,
+                    "column": 0,
+                    "instructionPointerReference": format!("{:#x}", address),
+                })
+            })
+            .collect();
+        json!({ "totalFrames": frames.len(), "stackFrames": frames })
+    }
+
+    fn variables(&mut self, arguments: VariablesArguments, request_seq: i64) -> RequestResult {
+        match arguments.variables_reference {
+            REGISTERS_REFERENCE => {
+                let variables: Vec<_> = self
+                    .registers
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &value)| variable(register_name(index), value))
+                    .collect();
+                Ok(Some(json!({ "variables": variables })))
+            }
+            STACK_REFERENCE => {
+                let Some(&stack_pointer) = self.registers.get(Processor::STACK_POINTER.0 as usize)
+                else {
+                    return Ok(Some(json!({ "variables": [] })));
+                };
+                let start = stack_pointer.saturating_sub(NUM_STACK_VARIABLES * Word::SIZE as Word);
+                self.read_memory_for(
+                    start,
+                    stack_pointer - start,
+                    PendingMemoryRequest::StackVariables { request_seq },
+                );
+                Ok(None)
+            }
+            reference => Err(format!("Unknown variables reference {}.", reference)),
+        }
+    }
+
+    fn set_variable(&mut self, arguments: SetVariableArguments) -> RequestResult {
+        if arguments.variables_reference != REGISTERS_REFERENCE {
+            return Err("Only registers can be modified.".to_string());
+        }
+        let Some(index) =
+            (0..self.registers.len()).find(|&index| register_name(index) == arguments.name)
+        else {
+            return Err(format!("Unknown register '{}'.", arguments.name));
+        };
+        let value = match Expression::parse(&arguments.value) {
+            Ok(Expression::Constant(value)) => value,
+            _ => return Err(format!("'{}' is not a number.", arguments.value)),
+        };
+        self.registers[index] = value;
+        self.send_command(DebugCommand::SetRegister(index as u8, value));
+        Ok(Some(json!({ "value": format!("{:#010x}", value) })))
+    }
+
+    fn read_memory(&mut self, arguments: ReadMemoryArguments, request_seq: i64) -> RequestResult {
+        let address = parse_memory_reference(&arguments.memory_reference, arguments.offset)?;
+        let count = arguments.count.clamp(0, Word::MAX as i64) as Word;
+        self.read_memory_for(
+            address,
+            count,
+            PendingMemoryRequest::ReadMemory {
+                request_seq,
+                count: count as usize,
+            },
+        );
+        Ok(None)
+    }
+
+    fn write_memory(&mut self, arguments: WriteMemoryArguments, request_seq: i64) -> RequestResult {
+        let address = parse_memory_reference(&arguments.memory_reference, arguments.offset)?;
+        let bytes = dap_protocol::decode_base64(&arguments.data)
+            .ok_or_else(|| "The data is not valid base64.".to_string())?;
+        self.pending_memory_requests
+            .push_back(PendingMemoryRequest::WriteMemory { request_seq });
+        self.send_command(DebugCommand::WriteMemory { address, bytes });
+        Ok(None)
+    }
+
+    fn disassemble(&mut self, arguments: DisassembleArguments, request_seq: i64) -> RequestResult {
+        const MAX_INSTRUCTION_COUNT: i64 = (Memory::SIZE / Instruction::SIZE) as i64;
+        let base = parse_memory_reference(&arguments.memory_reference, arguments.offset)?;
+        if arguments.instruction_count > MAX_INSTRUCTION_COUNT {
+            return Err(format!(
+                "At most {} instructions can be disassembled.",
+                MAX_INSTRUCTION_COUNT
+            ));
+        }
+        let count = arguments.instruction_count.max(0) as usize;
+        let range = arguments
+            .instruction_offset
+            .checked_mul(Instruction::SIZE as i64)
+            .and_then(|offset| offset.checked_add(base as i64))
+            .and_then(|start| {
+                Some((
+                    start,
+                    start.checked_add((count * Instruction::SIZE) as i64)?,
+                ))
+            });
+        let Some((start, end)) = range else {
+            return Err("The instruction offset is out of range.".to_string());
+        };
+        let read_start = start.clamp(0, Memory::SIZE as i64);
+        let read_end = end.clamp(0, Memory::SIZE as i64);
+        self.read_memory_for(
+            read_start as Address,
+            (read_end - read_start) as Word,
+            PendingMemoryRequest::Disassemble {
+                request_seq,
+                start,
+                count,
+            },
+        );
+        Ok(None)
+    }
+
+    fn read_memory_for(&mut self, address: Address, length: Word, request: PendingMemoryRequest) {
+        self.pending_memory_requests.push_back(request);
+        self.send_command(DebugCommand::ReadMemory { address, length });
+    }
+
+    /// Answers the oldest pending request (the debug handle answers in order).
+    fn complete_memory_request(&mut self, address: Address, bytes: Vec<u8>) {
+        let Some(request) = self.pending_memory_requests.pop_front() else {
+            return;
+        };
+        let (request_seq, command, body) = match request {
+            PendingMemoryRequest::ReadMemory { request_seq, count } => (
+                request_seq,
+                "readMemory",
+                json!({
+                    "address": format!("{:#x}", address),
+                    "data": dap_protocol::encode_base64(&bytes),
+                    "unreadableBytes": count - bytes.len(),
+                }),
+            ),
+            PendingMemoryRequest::WriteMemory { request_seq } => (
+                request_seq,
+                "writeMemory",
+                json!({ "bytesWritten": bytes.len() }),
+            ),
+            PendingMemoryRequest::StackVariables { request_seq } => {
+                let variables: Vec<_> = bytes
+                    .chunks_exact(Word::SIZE)
+                    .enumerate()
+                    .rev()
+                    .map(|(index, word)| {
+                        let word_address = address as usize + index * Word::SIZE;
+                        let value = Word::from_be_bytes(word.try_into().unwrap());
+                        variable(format!("[{:#x}]", word_address), value)
+                    })
+                    .collect();
+                (request_seq, "variables", json!({ "variables": variables }))
+            }
+            PendingMemoryRequest::Disassemble {
+                request_seq,
+                start,
+                count,
+            } => {
+                let instructions: Vec<_> = (0..count)
+                    .map(|index| {
+                        let instruction_address = start + (index * Instruction::SIZE) as i64;
+                        disassemble_instruction(instruction_address, address, &bytes)
+                    })
+                    .collect();
+                (
+                    request_seq,
+                    "disassemble",
+                    json!({ "instructions": instructions }),
+                )
+            }
+        };
+        self.respond(request_seq, command, Ok(body));
+    }
+
+    fn stopped(&mut self, reason: &str, description: Option<String>) {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(description) = description {
+            body["description"] = json!(description);
+        }
+        self.send_event("stopped", body);
+    }
+
+    fn output(&mut self, category: &str, message: String) {
+        self.send_event(
+            "output",
+            json!({ "category": category, "output": message + "\n" }),
+        );
+    }
+
+    fn respond(&mut self, request_seq: i64, command: &str, result: Result<Value, String>) {
+        let (success, message, body) = match result {
+            Ok(body) => (true, None, body),
+            Err(message) => (false, Some(message), Value::Null),
+        };
+        self.outgoing.push(Message::Response {
+            request_seq,
+            success,
+            command: command.to_string(),
+            message,
+            body,
+        });
+    }
+
+    fn send_event(&mut self, event: &str, body: Value) {
+        self.outgoing.push(Message::Event {
+            event: event.to_string(),
+            body,
+        });
+    }
+
+    fn send_command(&mut self, command: DebugCommand) {
+        send_command(&self.command_sender, command);
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsHitConditionalBreakpoints": true,
+        "supportsInstructionBreakpoints": true,
+        "supportsSetVariable": true,
+        "supportsStepBack": true,
+        "supportsReadMemoryRequest": true,
+        "supportsWriteMemoryRequest": true,
+        "supportsDisassembleRequest": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+/// Missing arguments are treated like empty ones.
+fn parse_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T, String> {
+    let arguments = match arguments {
+        Value::Null => json!({}),
+        arguments => arguments,
+    };
+    serde_json::from_value(arguments).map_err(|error| format!("Invalid arguments: {}", error))
+}
+
+/// Memory references are (hexadecimal or decimal) addresses.
+fn parse_memory_reference(reference: &str, offset: i64) -> Result<Address, String> {
+    let address = match reference.strip_prefix("0x") {
+        Some(digits) => i64::from_str_radix(digits, 16),
+        None => reference.parse(),
+    }
+    .map_err(|_| format!("Invalid memory reference '{}'.", reference))?;
+    address
+        .checked_add(offset)
+        .and_then(|address| Address::try_from(address).ok())
+        .ok_or_else(|| format!("Memory reference '{}' is out of range.", reference))
+}
+
+/// Returns the number of hits to ignore to break on the given hit.
+fn parse_hit_condition(condition: &str) -> Result<u64, String> {
+    condition
+        .trim()
+        .parse::<u64>()
+        .map(|hit| hit.saturating_sub(1))
+        .map_err(|_| format!("Invalid hit condition '{}'.", condition))
+}
+
+fn variable(name: String, value: Word) -> Value {
+    json!({
+        "name": name,
+        "value": format!("{:#010x}", value),
+        "type": "Word",
+        "variablesReference": 0,
+        "memoryReference": format!("{:#x}", value),
+    })
+}
+
+/// Disassembles the instruction at the address from the bytes that have been read starting
+/// at `bytes_address`.
+fn disassemble_instruction(address: i64, bytes_address: Address, bytes: &[u8]) -> Value {
+    let instruction = usize::try_from(address - bytes_address as i64)
+        .ok()
+        .and_then(|offset| bytes.get(offset..offset + Instruction::SIZE));
+    let Some(instruction) = instruction else {
+        return json!({
+            "address": format!("{:#x}", address),
+            "instruction": "??",
+            "presentationHint": "invalid",
+        });
+    };
+    let instruction_bytes: String = instruction
+        .iter()
+        .map(|byte| format!("{:02x} ", byte))
+        .collect();
+    let text = match Opcode::try_from(Instruction::from_be_bytes(instruction.try_into().unwrap())) {
+        Ok(opcode) => opcode.to_string(),
+        Err(_) => "<invalid>".to_string(),
+    };
+    json!({
+        "address": format!("{:#x}", address),
+        "instructionBytes": instruction_bytes.trim_end(),
+        "instruction": text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, net::TcpStream, time::Duration};
+
+    use crossbeam_channel::bounded;
+
+    use super::*;
+    use crate::{address_constants::ENTRY_POINT, Register};
+
+    fn create_debugger() -> (DapDebugger, Receiver<DebugCommand>) {
+        let (_, receiver) = bounded(1);
+        let (command_sender, command_receiver) = bounded(super::super::CHANNEL_BOUND);
+        (DapDebugger::new(receiver, command_sender), command_receiver)
+    }
+
+    fn request(debugger: &mut DapDebugger, seq: i64, command: &str, arguments: Value) -> Value {
+        debugger.handle_request(Request {
+            seq,
+            command: command.to_string(),
+            arguments,
+        });
+        let responses: Vec<_> = debugger
+            .outgoing
+            .drain(..)
+            .filter_map(|message| match message {
+                Message::Response {
+                    request_seq,
+                    success,
+                    body,
+                    ..
+                } if request_seq == seq => {
+                    assert!(success, "request {} failed", command);
+                    Some(body)
+                }
+                _ => None,
+            })
+            .collect();
+        responses.into_iter().next().unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn initialize_is_followed_by_initialized_event() {
+        let (mut debugger, _commands) = create_debugger();
+        debugger.handle_request(Request {
+            seq: 1,
+            command: "initialize".to_string(),
+            arguments: json!({ "adapterID": "bss2k" }),
+        });
+        assert!(matches!(
+            debugger.outgoing.as_slice(),
+            [Message::Response { success: true, body, .. }, Message::Event { event, .. }]
+                if body["supportsInstructionBreakpoints"] == true && event == "initialized"
+        ));
+        debugger.outgoing.clear();
+        debugger.handle_request(Request {
+            seq: 2,
+            command: "evaluate".to_string(),
+            arguments: Value::Null,
+        });
+        assert!(matches!(
+            debugger.outgoing.as_slice(),
+            [Message::Response {
+                success: false,
+                message: Some(_),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn instruction_breakpoints_replace_previous_ones() {
+        let (mut debugger, commands) = create_debugger();
+        let body = request(
+            &mut debugger,
+            1,
+            "setInstructionBreakpoints",
+            json!({ "breakpoints": [
+                { "instructionReference": "0x100", "offset": 8, "hitCondition": "3" },
+                { "instructionReference": "0x200", "condition": "r1 +" },
+            ] }),
+        );
+        assert_eq!(body["breakpoints"][0]["verified"], true);
+        assert_eq!(body["breakpoints"][0]["instructionReference"], "0x108");
+        assert_eq!(body["breakpoints"][1]["verified"], false);
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::SetBreakpoints(breakpoints))
+                if breakpoints.len() == 1
+                    && breakpoints[0].0 == 0x108
+                    && breakpoints[0].1.ignore_count == 2
+        ));
+
+        request(
+            &mut debugger,
+            2,
+            "setInstructionBreakpoints",
+            json!({ "breakpoints": [] }),
+        );
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::RemoveBreakpoints(locations)) if locations == [0x108]
+        ));
+    }
+
+    #[test]
+    fn stack_trace_and_disassembly_at_break() {
+        let (mut debugger, commands) = create_debugger();
+        let mut registers = vec![0; 256];
+        registers[Processor::INSTRUCTION_POINTER.0 as usize] = ENTRY_POINT;
+        debugger.handle_debug_message(DebugMessage::BreakState {
+            registers,
+            call_stack: vec![0x100, 0x200],
+        });
+        debugger.handle_debug_message(DebugMessage::HitBreakpoint(ENTRY_POINT, 1));
+        assert!(matches!(
+            debugger.outgoing.pop(),
+            Some(Message::Event { event, body }) if event == "stopped"
+                && body["reason"] == "instruction breakpoint"
+        ));
+
+        let body = request(&mut debugger, 1, "stackTrace", json!({ "threadId": 1 }));
+        let addresses: Vec<_> = body["stackFrames"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|frame| frame["instructionPointerReference"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            addresses,
+            [
+                format!("{:#x}", ENTRY_POINT),
+                "0x200".into(),
+                "0x100".into()
+            ]
+        );
+
+        let body = request(
+            &mut debugger,
+            2,
+            "disassemble",
+            json!({
+                "memoryReference": "0x8",
+                "instructionOffset": -2,
+                "instructionCount": 3,
+            }),
+        );
+        assert_eq!(body, Value::Null);
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::ReadMemory {
+                address: 0,
+                length: 16
+            })
+        ));
+        let instruction = Opcode::MoveRegisterImmediate {
+            register: Register(1),
+            immediate: 42,
+        }
+        .as_instruction();
+        let mut bytes = instruction.to_be_bytes().to_vec();
+        bytes.extend([0x7E; 8]);
+        debugger.handle_debug_message(DebugMessage::Memory { address: 0, bytes });
+        let Some(Message::Response {
+            request_seq: 2,
+            body,
+            ..
+        }) = debugger.outgoing.pop()
+        else {
+            panic!("the disassembly has not been sent");
+        };
+        let instructions: Vec<_> = body["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|instruction| instruction["instruction"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            instructions,
+            ["??", "MoveRegisterImmediate R1, 0x2a", "<invalid>"]
+        );
+    }
+
+    #[test]
+    fn out_of_range_references_are_rejected() {
+        let (mut debugger, commands) = create_debugger();
+        let requests = [
+            (
+                "readMemory",
+                json!({ "memoryReference": format!("{:#x}", i64::MAX), "offset": 1, "count": 4 }),
+            ),
+            (
+                "disassemble",
+                json!({
+                    "memoryReference": "0x0",
+                    "instructionOffset": i64::MAX,
+                    "instructionCount": 1,
+                }),
+            ),
+            (
+                "disassemble",
+                json!({ "memoryReference": "0x0", "instructionCount": i64::MAX }),
+            ),
+        ];
+        for (seq, (command, arguments)) in (1..).zip(requests) {
+            debugger.handle_request(Request {
+                seq,
+                command: command.to_string(),
+                arguments,
+            });
+            assert!(matches!(
+                debugger.outgoing.pop(),
+                Some(Message::Response { success: false, .. })
+            ));
+        }
+        assert!(commands.try_recv().is_err());
+    }
+
+    #[test]
+    fn client_dropped_after_invalid_message_is_detached() {
+        let (message_sender, receiver) = bounded(super::super::CHANNEL_BOUND);
+        let (command_sender, commands) = bounded(super::super::CHANNEL_BOUND);
+        let dap = DapHandler::start("127.0.0.1:0".parse().unwrap());
+        let mut client = TcpStream::connect(dap.local_address()).unwrap();
+        let debugger = DapDebugger::new(receiver, command_sender);
+        let thread = std::thread::spawn(move || debugger.serve(dap));
+        let mut send = |content: &str| {
+            let message = format!("Content-Length: {}\r\n\r\n{}", content.len(), content);
+            client.write_all(message.as_bytes()).unwrap();
+        };
+        let timeout = Duration::from_secs(5);
+
+        send(
+            &json!({
+                "seq": 1,
+                "type": "request",
+                "command": "setInstructionBreakpoints",
+                "arguments": { "breakpoints": [{ "instructionReference": "0x100" }] },
+            })
+            .to_string(),
+        );
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::SetBreakpoints(breakpoints)) if breakpoints.len() == 1
+        ));
+        send("{ not json");
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::RemoveBreakpoints(locations)) if locations == [0x100]
+        ));
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::Continue)
+        ));
+
+        message_sender.send(DebugMessage::Stop).unwrap();
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    io::{self, Read},
+    net::{SocketAddr, TcpListener, TcpStream},
+};
+
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    segmented_reader,
+    tcp_protocol::{listen, reject_pending_clients, write_all_non_blocking, Error, Result},
+};
+use crate::memory::Memory;
+
+/// The port of the Debug Adapter Protocol unless another one is configured.
+pub const DEFAULT_PORT: u16 = 57018;
+const CONTENT_LENGTH_HEADER: &str = "Content-Length:";
+const HEADER_END: &[u8] = b"\r\n\r\n";
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+/// Enough for writing the whole memory at once (base64 needs four characters per three bytes).
+const MAX_CONTENT_LENGTH: usize = 2 * Memory::SIZE;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// A request of the Debug Adapter Protocol. The arguments depend on the command.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub seq: i64,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// A message sent to the client. Its sequence number is added by the `DapHandler`.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Message {
+    Response {
+        request_seq: i64,
+        success: bool,
+        command: String,
+        /// The error message of failed requests.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+        #[serde(skip_serializing_if = "Value::is_null")]
+        body: Value,
+    },
+    Event {
+        event: String,
+        #[serde(skip_serializing_if = "Value::is_null")]
+        body: Value,
+    },
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    seq: i64,
+    #[serde(flatten)]
+    message: &'a Message,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchArguments {
+    #[serde(default)]
+    pub stop_on_entry: bool,
+}
+
+/// Breakpoints in source files, which cannot be resolved since ROMs have no debug information.
+#[derive(Debug, Deserialize)]
+pub struct SetBreakpointsArguments {
+    #[serde(default)]
+    pub breakpoints: Vec<IgnoredAny>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionBreakpoint {
+    pub instruction_reference: String,
+    #[serde(default)]
+    pub offset: i64,
+    pub condition: Option<String>,
+    pub hit_condition: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetInstructionBreakpointsArguments {
+    pub breakpoints: Vec<InstructionBreakpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVariableArguments {
+    pub variables_reference: i64,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadMemoryArguments {
+    pub memory_reference: String,
+    #[serde(default)]
+    pub offset: i64,
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteMemoryArguments {
+    pub memory_reference: String,
+    #[serde(default)]
+    pub offset: i64,
+    /// The bytes in base64 encoding.
+    pub data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisassembleArguments {
+    pub memory_reference: String,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default)]
+    pub instruction_offset: i64,
+    pub instruction_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisconnectArguments {
+    pub terminate_debuggee: Option<bool>,
+}
+
+/// Splits the input into the contents of messages that are preceded by headers (one of
+/// them being `Content-Length`), as used by the Debug Adapter Protocol.
+#[derive(Debug, Default)]
+pub struct MessageReader {
+    buffer: Vec<u8>,
+}
+
+impl MessageReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the available input and returns the contents of all completed messages.
+    pub fn read(&mut self, mut from: impl Read) -> segmented_reader::Result<Vec<Vec<u8>>> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        let length = from.read(&mut chunk).map_err(segmented_reader::Error::Io)?;
+        if length == 0 {
+            return Err(segmented_reader::Error::Disconnected);
+        }
+        self.buffer.extend_from_slice(&chunk[..length]);
+
+        let mut messages = Vec::new();
+        while let Some(message) = self.next_message().map_err(segmented_reader::Error::Io)? {
+            messages.push(message);
+        }
+        Ok(messages)
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn next_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(header_length) = self
+            .buffer
+            .windows(HEADER_END.len())
+            .position(|window| window == HEADER_END)
+        else {
+            return Ok(None);
+        };
+        let invalid_data = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+        let headers = std::str::from_utf8(&self.buffer[..header_length])
+            .map_err(|_| invalid_data("headers are not valid UTF-8"))?;
+        let content_length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix(CONTENT_LENGTH_HEADER))
+            .ok_or_else(|| invalid_data("missing Content-Length header"))?
+            .trim()
+            .parse()
+            .map_err(|_| invalid_data("invalid Content-Length header"))?;
+        if content_length > MAX_CONTENT_LENGTH {
+            return Err(invalid_data(
+                "Content-Length exceeds the maximum message size",
+            ));
+        }
+
+        let start = header_length + HEADER_END.len();
+        let end = start
+            .checked_add(content_length)
+            .ok_or_else(|| invalid_data("invalid Content-Length header"))?;
+        if self.buffer.len() < end {
+            return Ok(None);
+        }
+        let message = self.buffer[start..end].to_vec();
+        self.buffer.drain(..end);
+        Ok(Some(message))
+    }
+}
+
+pub struct DapHandler {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    reader: MessageReader,
+    sequence: i64,
+}
+
+pub enum PollReturn {
+    Nothing,
+    ClientConnected,
+    ClientDisconnected,
+    ReceivedRequests(Vec<Request>),
+}
+
+impl DapHandler {
+    pub fn start(address: SocketAddr) -> Self {
+        let listener = listen(address, "DAP");
+
+        Self {
+            listener,
+            client: None,
+            reader: MessageReader::new(),
+            sequence: 0,
+        }
+    }
+
+    pub fn poll(&mut self) -> Result<PollReturn> {
+        if self.client.is_some() {
+            reject_pending_clients(&self.listener);
+        }
+        match self.client {
+            None => self.accept(),
+            Some(ref mut client) => match self.reader.read(client) {
+                Ok(messages) => {
+                    let requests = messages
+                        .iter()
+                        .map(|message| serde_json::from_slice(message))
+                        .collect::<serde_json::Result<_>>();
+                    match requests {
+                        Ok(requests) => Ok(PollReturn::ReceivedRequests(requests)),
+                        Err(error) => {
+                            self.disconnect();
+                            Err(Error::Serde(error))
+                        }
+                    }
+                }
+                Err(segmented_reader::Error::Disconnected) => {
+                    self.disconnect();
+                    Ok(PollReturn::ClientDisconnected)
+                }
+                Err(segmented_reader::Error::Io(error))
+                    if error.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    Ok(PollReturn::Nothing)
+                }
+                Err(segmented_reader::Error::Io(error)) => {
+                    self.disconnect();
+                    Err(Error::Io(error))
+                }
+            },
+        }
+    }
+
+    /// Returns whether a client is connected. Failed operations disconnect the client.
+    pub fn has_client(&self) -> bool {
+        self.client.is_some()
+    }
+
+    #[cfg(test)]
+    pub fn local_address(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<()> {
+        let Some(client) = &mut self.client else {
+            return Ok(());
+        };
+        self.sequence += 1;
+        let json = serde_json::to_vec(&Envelope {
+            seq: self.sequence,
+            message,
+        })
+        .map_err(Error::Serde)?;
+        let mut buffer = format!("{} {}\r\n\r\n", CONTENT_LENGTH_HEADER, json.len()).into_bytes();
+        buffer.extend(json);
+
+        let result = write_all_non_blocking(client, &buffer);
+        if result.is_err() {
+            self.disconnect();
+        }
+        result.map_err(Error::Io)
+    }
+
+    fn disconnect(&mut self) {
+        self.client = None; // Dropping the stream disconnects it, if it is still active.
+        self.reader.clear();
+    }
+
+    fn accept(&mut self) -> Result<PollReturn> {
+        match self.listener.accept() {
+            Ok((client, _)) => {
+                client.set_nodelay(true).map_err(Error::Io)?;
+                client.set_nonblocking(true).map_err(Error::Io)?;
+                self.client = Some(client);
+                self.sequence = 0;
+                Ok(PollReturn::ClientConnected)
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(PollReturn::Nothing),
+            Err(error) => Err(Error::Io(error)),
+        }
+    }
+}
+
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (index, &byte)| {
+                group | (byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => {
+                    let digit = (group >> (18 - 6 * index)) & 0b11_1111;
+                    text.push(BASE64_ALPHABET[digit as usize] as char);
+                }
+                false => text.push('='),
+            }
+        }
+    }
+    text
+}
+
+/// Returns `None` if the text is not valid base64 (with padding).
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    for chunk in text.chunks(4) {
+        let padding = chunk
+            .iter()
+            .rev()
+            .take_while(|&&digit| digit == b'=')
+            .count();
+        if padding > 2 {
+            return None;
+        }
+        let mut group = 0u32;
+        for (index, digit) in chunk[..4 - padding].iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|symbol| symbol == digit)?;
+            group |= (value as u32) << (18 - 6 * index);
+        }
+        bytes.extend_from_slice(&group.to_be_bytes()[1..4 - padding]);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn messages_are_split_by_content_length() -> segmented_reader::Result<()> {
+        let mut reader = MessageReader::new();
+        let input = b"Content-Length: 2\r\n\r\n{}Content-Type: json\r\nContent-Length: 3\r\n\r\n[1";
+        assert_eq!(reader.read(&input[..])?, [b"{}".to_vec()]);
+        assert_eq!(reader.read(&b"]"[..])?, [b"[1]".to_vec()]);
+        assert!(reader.read(&b"Length: 1\r\n\r\n1"[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_content_length_is_rejected() {
+        for length in [usize::MAX, MAX_CONTENT_LENGTH + 1] {
+            let mut reader = MessageReader::new();
+            let input = format!("Content-Length: {}\r\n\r\n{{}}", length);
+            assert!(reader.read(input.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn messages_are_serialized_with_sequence_number() -> serde_json::Result<()> {
+        let message = Message::Event {
+            event: "stopped".to_string(),
+            body: json!({ "reason": "pause" }),
+        };
+        assert_eq!(
+            serde_json::to_value(Envelope {
+                seq: 3,
+                message: &message
+            })?,
+            json!({ "seq": 3, "type": "event", "event": "stopped", "body": { "reason": "pause" } })
+        );
+        let response = Message::Response {
+            request_seq: 1,
+            success: false,
+            command: "next".to_string(),
+            message: Some("failed".to_string()),
+            body: Value::Null,
+        };
+        assert_eq!(
+            serde_json::to_value(Envelope {
+                seq: 4,
+                message: &response
+            })?,
+            json!({
+                "seq": 4,
+                "type": "response",
+                "request_seq": 1,
+                "success": false,
+                "command": "next",
+                "message": "failed"
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+        for length in 0..8 {
+            let bytes: Vec<u8> = (0..length).map(|byte| 0xF0 ^ byte).collect();
+            assert_eq!(decode_base64(&encode_base64(&bytes)), Some(bytes));
+        }
+        assert_eq!(decode_base64("Zg="), None);
+        assert_eq!(decode_base64("Z==="), None);
+        assert_eq!(decode_base64("Z!=="), None);
+    }
+}
@@ -0,0 +1,487 @@
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use crate::{memory::Memory, processor::Processor, Register, Size, Word};
+
+/// An expression over the registers and the (physical) memory of a core, e.g.
+/// `r1 == 10 && [sp - 4] != 0`.
+///
+/// Operands are numbers (decimal, `0x` hexadecimal or `0b` binary), registers (`r0` to
+/// `r255`, `ip`, `sp`, `fp` and `flags`) and memory reads (`[address]` for words,
+/// `half[address]` and `byte[address]`). The operators and their precedence are the same as
+/// in Rust. All values are unsigned words, comparisons and logical operators yield 0 or 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Constant(Word),
+    Register(Register),
+    Memory {
+        address: Box<Expression>,
+        size: usize,
+    },
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    BitwiseNot,
+    LogicalNot,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    BitwiseAnd,
+    BitwiseXor,
+    BitwiseOr,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    LogicalAnd,
+    LogicalOr,
+}
+
+impl BinaryOperator {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        use BinaryOperator::*;
+
+        Some(match symbol {
+            "*" => Multiply,
+            "/" => Divide,
+            "%" => Remainder,
+            "+" => Add,
+            "-" => Subtract,
+            "<<" => ShiftLeft,
+            ">>" => ShiftRight,
+            "&" => BitwiseAnd,
+            "^" => BitwiseXor,
+            "|" => BitwiseOr,
+            "==" => Equal,
+            "!=" => NotEqual,
+            "<" => Less,
+            "<=" => LessOrEqual,
+            ">" => Greater,
+            ">=" => GreaterOrEqual,
+            "&&" => LogicalAnd,
+            "||" => LogicalOr,
+            _ => return None,
+        })
+    }
+
+    fn precedence(self) -> u8 {
+        use BinaryOperator::*;
+
+        match self {
+            LogicalOr => 1,
+            LogicalAnd => 2,
+            Equal | NotEqual | Less | LessOrEqual | Greater | GreaterOrEqual => 3,
+            BitwiseOr => 4,
+            BitwiseXor => 5,
+            BitwiseAnd => 6,
+            ShiftLeft | ShiftRight => 7,
+            Add | Subtract => 8,
+            Multiply | Divide | Remainder => 9,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EvaluationError {
+    DivisionByZero,
+    InvalidMemoryAccess(Word),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(Word),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+/// Sorted so that longer symbols are matched first.
+const SYMBOLS: [&str; 24] = [
+    "<<", ">>", "==", "!=", "<=", ">=", "&&", "||", "(", ")", "[", "]", "+", "-", "*", "/", "%",
+    "&", "|", "^", "~", "!", "<", ">",
+];
+
+/// The maximum nesting depth of parentheses, memory reads and unary operators, so that the
+/// recursive descent cannot overflow the stack.
+const MAX_NESTING_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    source: &'a str,
+    characters: Peekable<CharIndices<'a>>,
+    current: Option<(usize, Token)>,
+    depth: usize,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(source)?;
+        let expression = parser.parse_expression(0)?;
+        match parser.current {
+            None => Ok(expression),
+            Some((position, _)) => Err(ParseError {
+                position,
+                message: "unexpected token after the end of the expression".to_string(),
+            }),
+        }
+    }
+
+    pub fn evaluate(
+        &self,
+        processor: &Processor,
+        memory: &Memory,
+    ) -> Result<Word, EvaluationError> {
+        use BinaryOperator::*;
+
+        Ok(match self {
+            Expression::Constant(value) => *value,
+            Expression::Register(register) => processor.registers[*register],
+            Expression::Memory { address, size } => {
+                let address = address.evaluate(processor, memory)?;
+                let start = address as usize;
+                let bytes = start
+                    .checked_add(*size)
+                    .and_then(|end| memory.data().get(start..end))
+                    .ok_or(EvaluationError::InvalidMemoryAccess(address))?;
+                bytes
+                    .iter()
+                    .fold(0, |word, &byte| (word << u8::BITS) | byte as Word)
+            }
+            Expression::Unary(operator, operand) => {
+                let operand = operand.evaluate(processor, memory)?;
+                match operator {
+                    UnaryOperator::Negate => operand.wrapping_neg(),
+                    UnaryOperator::BitwiseNot => !operand,
+                    UnaryOperator::LogicalNot => (operand == 0).into(),
+                }
+            }
+            Expression::Binary(LogicalAnd, lhs, rhs) => (lhs.evaluate(processor, memory)? != 0
+                && rhs.evaluate(processor, memory)? != 0)
+                .into(),
+            Expression::Binary(LogicalOr, lhs, rhs) => (lhs.evaluate(processor, memory)? != 0
+                || rhs.evaluate(processor, memory)? != 0)
+                .into(),
+            Expression::Binary(operator, lhs, rhs) => {
+                let lhs = lhs.evaluate(processor, memory)?;
+                let rhs = rhs.evaluate(processor, memory)?;
+                match operator {
+                    Multiply => lhs.wrapping_mul(rhs),
+                    Divide => lhs
+                        .checked_div(rhs)
+                        .ok_or(EvaluationError::DivisionByZero)?,
+                    Remainder => lhs
+                        .checked_rem(rhs)
+                        .ok_or(EvaluationError::DivisionByZero)?,
+                    Add => lhs.wrapping_add(rhs),
+                    Subtract => lhs.wrapping_sub(rhs),
+                    ShiftLeft => lhs.checked_shl(rhs).unwrap_or(0),
+                    ShiftRight => lhs.checked_shr(rhs).unwrap_or(0),
+                    BitwiseAnd => lhs & rhs,
+                    BitwiseXor => lhs ^ rhs,
+                    BitwiseOr => lhs | rhs,
+                    Equal => (lhs == rhs).into(),
+                    NotEqual => (lhs != rhs).into(),
+                    Less => (lhs < rhs).into(),
+                    LessOrEqual => (lhs <= rhs).into(),
+                    Greater => (lhs > rhs).into(),
+                    GreaterOrEqual => (lhs >= rhs).into(),
+                    LogicalAnd | LogicalOr => unreachable!(),
+                }
+            }
+        })
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, ParseError> {
+        let mut parser = Self {
+            source,
+            characters: source.char_indices().peekable(),
+            current: None,
+            depth: 0,
+        };
+        parser.advance()?;
+        Ok(parser)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            position: self
+                .current
+                .as_ref()
+                .map_or(self.source.len(), |(position, _)| *position),
+            message: message.to_string(),
+        })
+    }
+
+    /// Reads the next token into `current`.
+    fn advance(&mut self) -> Result<(), ParseError> {
+        while self
+            .characters
+            .next_if(|(_, character)| character.is_whitespace())
+            .is_some()
+        {}
+        let Some(&(start, character)) = self.characters.peek() else {
+            self.current = None;
+            return Ok(());
+        };
+        let token = if character.is_ascii_alphanumeric() || character == '_' {
+            let mut end = start;
+            while let Some((position, character)) = self
+                .characters
+                .next_if(|(_, character)| character.is_ascii_alphanumeric() || *character == '_')
+            {
+                end = position + character.len_utf8();
+            }
+            let word = &self.source[start..end];
+            match character.is_ascii_digit() {
+                true => Token::Number(parse_number(word).ok_or_else(|| ParseError {
+                    position: start,
+                    message: format!("invalid number \"{}\"", word),
+                })?),
+                false => Token::Identifier(word.to_string()),
+            }
+        } else {
+            let rest = &self.source[start..];
+            let Some(&symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) else {
+                return Err(ParseError {
+                    position: start,
+                    message: format!("unexpected character '{}'", character),
+                });
+            };
+            for _ in 0..symbol.len() {
+                self.characters.next();
+            }
+            Token::Symbol(symbol)
+        };
+        self.current = Some((start, token));
+        Ok(())
+    }
+
+    /// Parses a nested part of the expression with the given function.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return self.error("the expression is nested too deeply");
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        match &self.current {
+            Some((_, Token::Symbol(current))) if *current == symbol => self.advance(),
+            _ => self.error(&format!("expected '{}'", symbol)),
+        }
+    }
+
+    fn parse_expression(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut lhs = self.parse_unary()?;
+        loop {
+            let operator = match &self.current {
+                Some((_, Token::Symbol(symbol))) => BinaryOperator::from_symbol(symbol),
+                _ => None,
+            };
+            let Some(operator) =
+                operator.filter(|operator| operator.precedence() >= min_precedence)
+            else {
+                return Ok(lhs);
+            };
+            self.advance()?;
+            let rhs = self.parse_expression(operator.precedence() + 1)?;
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, ParseError> {
+        let operator = match &self.current {
+            Some((_, Token::Symbol("-"))) => UnaryOperator::Negate,
+            Some((_, Token::Symbol("~"))) => UnaryOperator::BitwiseNot,
+            Some((_, Token::Symbol("!"))) => UnaryOperator::LogicalNot,
+            _ => return self.parse_primary(),
+        };
+        self.advance()?;
+        let operand = self.nested(Self::parse_unary)?;
+        Ok(Expression::Unary(operator, Box::new(operand)))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        let Some((_, token)) = self.current.clone() else {
+            return self.error("unexpected end of the expression");
+        };
+        match token {
+            Token::Number(value) => {
+                self.advance()?;
+                Ok(Expression::Constant(value))
+            }
+            Token::Symbol("(") => {
+                self.advance()?;
+                let expression = self.nested(|parser| parser.parse_expression(0))?;
+                self.expect_symbol(")")?;
+                Ok(expression)
+            }
+            Token::Symbol("[") => self.parse_memory(Word::SIZE),
+            Token::Identifier(name) if name == "byte" || name == "half" => {
+                self.advance()?;
+                match name.as_str() {
+                    "byte" => self.parse_memory(1),
+                    _ => self.parse_memory(2),
+                }
+            }
+            Token::Identifier(name) => match parse_register(&name) {
+                Some(register) => {
+                    self.advance()?;
+                    Ok(Expression::Register(register))
+                }
+                None => self.error(&format!("unknown register \"{}\"", name)),
+            },
+            Token::Symbol(_) => self.error("expected a number, register or memory read"),
+        }
+    }
+
+    fn parse_memory(&mut self, size: usize) -> Result<Expression, ParseError> {
+        self.expect_symbol("[")?;
+        let address = self.nested(|parser| parser.parse_expression(0))?;
+        self.expect_symbol("]")?;
+        Ok(Expression::Memory {
+            address: Box::new(address),
+            size,
+        })
+    }
+}
+
+fn parse_number(word: &str) -> Option<Word> {
+    let word = word.replace('_', "");
+    let (digits, radix) = match word.get(..2) {
+        Some("0x" | "0X") => (&word[2..], 16),
+        Some("0b" | "0B") => (&word[2..], 2),
+        _ => (word.as_str(), 10),
+    };
+    Word::from_str_radix(digits, radix).ok()
+}
+
+fn parse_register(name: &str) -> Option<Register> {
+    match name {
+        "ip" => Some(Processor::INSTRUCTION_POINTER),
+        "sp" => Some(Processor::STACK_POINTER),
+        "fp" => Some(Processor::FRAME_POINTER),
+        "flags" => Some(Processor::FLAGS),
+        _ => {
+            let number = name.strip_prefix('r')?;
+            // reject signs and leading zeros
+            match number.starts_with('0') && number.len() > 1 {
+                true => None,
+                false => number
+                    .parse()
+                    .ok()
+                    .filter(|_| number.chars().all(|c| c.is_ascii_digit()))
+                    .map(Register),
+            }
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+impl fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::DivisionByZero => write!(f, "division by zero"),
+            EvaluationError::InvalidMemoryAccess(address) => {
+                write!(f, "invalid memory access at {:#x}", address)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(source: &str) -> Result<Word, EvaluationError> {
+        let mut processor = Processor::new(false, 0, 1);
+        processor.registers[Register(1)] = 10;
+        processor.registers[Processor::STACK_POINTER] = 0x100;
+        let mut memory = Memory::new();
+        memory.write_data(0xFC, 0xDEAD_BEEF);
+        Expression::parse(source)
+            .unwrap()
+            .evaluate(&processor, &memory)
+    }
+
+    #[test]
+    fn operators_have_rust_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), Ok(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(9));
+        assert_eq!(evaluate("r1 & 3 == 2"), Ok(1));
+        assert_eq!(evaluate("1 << 4 | 1"), Ok(17));
+        assert_eq!(evaluate("r1 > 5 && !(r1 >= 11) || 0"), Ok(1));
+        assert_eq!(evaluate("-1"), Ok(Word::MAX));
+        assert_eq!(evaluate("~0x0F & 0b1111_0000"), Ok(0xF0));
+    }
+
+    #[test]
+    fn registers_and_memory_are_read() {
+        assert_eq!(evaluate("r1 * 2"), Ok(20));
+        assert_eq!(evaluate("[sp - 4]"), Ok(0xDEAD_BEEF));
+        assert_eq!(evaluate("half[sp - 2]"), Ok(0xBEEF));
+        assert_eq!(evaluate("byte[0xFC]"), Ok(0xDE));
+        assert_eq!(
+            evaluate("[0xFFFF_FFFF]"),
+            Err(EvaluationError::InvalidMemoryAccess(0xFFFF_FFFF))
+        );
+        assert_eq!(
+            evaluate("r1 / (r1 - 10)"),
+            Err(EvaluationError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn syntax_errors_are_reported() {
+        assert_eq!(Expression::parse("r1 +").unwrap_err().position, 4);
+        assert_eq!(Expression::parse("r256").unwrap_err().position, 0);
+        assert_eq!(Expression::parse("(r1").unwrap_err().position, 3);
+        assert_eq!(Expression::parse("r1 r2").unwrap_err().position, 3);
+        assert_eq!(Expression::parse("1 $ 2").unwrap_err().position, 2);
+        assert!(Expression::parse("0x").is_err());
+    }
+
+    #[test]
+    fn nesting_depth_is_limited() {
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Expression::parse(&nested(MAX_NESTING_DEPTH)).is_ok());
+        assert_eq!(
+            Expression::parse(&nested(MAX_NESTING_DEPTH + 1))
+                .unwrap_err()
+                .position,
+            MAX_NESTING_DEPTH + 1
+        );
+        assert!(Expression::parse(&"-".repeat(100_000)).is_err());
+        assert!(Expression::parse(&"[".repeat(100_000)).is_err());
+    }
+}
@@ -0,0 +1,783 @@
+use std::{collections::VecDeque, fmt::Write, net::SocketAddr};
+
+use crossbeam_channel::{select, tick, Receiver, Sender};
+use crossbeam_utils::sync::WaitGroup;
+
+use super::{
+    breakpoint::Breakpoint,
+    gdb_protocol::{GdbHandler, Input, PollReturn},
+    register_name, send_command,
+    tcp_protocol::{decode_hex, is_hex_digits},
+    watchpoint::{WatchKind, Watchpoint},
+    DebugCommand, DebugMessage, ShouldTerminate, TCP_POLL_INTERVAL,
+};
+use crate::{
+    processor::{Flag, Processor},
+    Address, Register, Word,
+};
+
+const NUM_REGISTERS: usize = 256;
+/// The number of hexadecimal digits of a register value.
+const REGISTER_DIGITS: usize = 2 * std::mem::size_of::<Word>();
+const OK_REPLY: &str = "OK";
+const ERROR_REPLY: &str = "E01";
+/// An empty reply tells the client that a packet is not supported.
+const UNSUPPORTED_REPLY: &str = "";
+const SUPPORTED_FEATURES: &str = "PacketSize=4000;qXfer:features:read+;swbreak+;hwbreak+;\
+                                  QStartNoAckMode+;ReverseStep+;ReverseContinue+";
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// Packets that are answered as soon as the debug handle sent the contents of the memory.
+#[derive(Debug)]
+enum PendingMemoryRequest {
+    Read { length: usize },
+    Write { length: usize },
+}
+
+/// Serves the GDB remote serial protocol, so that `gdb` and `lldb` can be used as debugger
+/// frontends. The cores are presented as a single thread and addresses are physical. Since
+/// the debuggers do not know the architecture, it is described by a target description
+/// (registers are sent in big-endian byte order, e.g. use `set endian big` in `gdb`).
+pub struct GdbStub {
+    receiver: Receiver<DebugMessage>,
+    command_sender: Sender<DebugCommand>,
+    started: bool,
+    start_notifications: Vec<WaitGroup>,
+    registers: Vec<Word>,
+    breakpoints: Vec<Address>,
+    watchpoints: Vec<Watchpoint>,
+    pending_memory_requests: VecDeque<PendingMemoryRequest>,
+    outgoing: Vec<Vec<u8>>,
+    has_client: bool,
+    /// Set after the client requested `QStartNoAckMode` (until the reply has been sent).
+    disable_acknowledgements: bool,
+    /// The reason of the current break, `None` while running.
+    stop_reply: Option<String>,
+    /// The client waits for the next stop (after a resume or `?` packet while running).
+    awaiting_stop_reply: bool,
+    interrupt_requested: bool,
+    history_exhausted: bool,
+    /// The target is terminated after the reply to the kill request has been sent.
+    kill_requested: bool,
+}
+
+impl GdbStub {
+    pub fn new(receiver: Receiver<DebugMessage>, command_sender: Sender<DebugCommand>) -> Self {
+        Self {
+            receiver,
+            command_sender,
+            started: false,
+            start_notifications: Vec::new(),
+            registers: Vec::new(),
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            pending_memory_requests: VecDeque::new(),
+            outgoing: Vec::new(),
+            has_client: false,
+            disable_acknowledgements: false,
+            stop_reply: None,
+            awaiting_stop_reply: false,
+            interrupt_requested: false,
+            history_exhausted: false,
+            kill_requested: false,
+        }
+    }
+
+    pub fn run(self, address: SocketAddr) {
+        self.serve(GdbHandler::start(address));
+    }
+
+    fn serve(mut self, mut gdb: GdbHandler) {
+        let gdb_poll = tick(TCP_POLL_INTERVAL);
+
+        loop {
+            let mut should_terminate = ShouldTerminate::No;
+            select! {
+                recv(gdb_poll) -> _ => match gdb.poll() {
+                    Ok(PollReturn::Nothing | PollReturn::ClientDisconnected) => {}
+                    Ok(PollReturn::ClientConnected) => {
+                        self.has_client = true;
+                        self.attach();
+                    }
+                    Ok(PollReturn::Received(inputs)) => {
+                        for input in inputs {
+                            self.handle_input(input);
+                        }
+                    }
+                    Err(error) => eprintln!("Failed GDB operation: {}", error),
+                },
+                recv(self.receiver) -> message => {
+                    let message = message.expect("Debugger cannot receive message on debug interface.");
+                    if let DebugMessage::Stop = message {
+                        self.reply("W00");
+                        should_terminate = ShouldTerminate::Yes;
+                    } else {
+                        self.handle_debug_message(message);
+                    }
+                }
+            };
+            for packet in self.outgoing.drain(..) {
+                if let Err(error) = gdb.send(&packet) {
+                    eprintln!("Failed GDB operation: {}", error);
+                }
+            }
+            if self.has_client && !gdb.has_client() {
+                self.has_client = false;
+                self.detach();
+            }
+            if std::mem::take(&mut self.disable_acknowledgements) {
+                gdb.disable_acknowledgements();
+            }
+            if std::mem::take(&mut self.kill_requested) {
+                self.terminate();
+                should_terminate = ShouldTerminate::Yes;
+            }
+            if let ShouldTerminate::Yes = should_terminate {
+                break;
+            }
+        }
+    }
+
+    fn handle_input(&mut self, input: Input) {
+        match input {
+            Input::Packet(packet) => self.handle_packet(&packet),
+            Input::Interrupt => {
+                if self.stop_reply.is_none() {
+                    self.interrupt_requested = true;
+                    self.send_command(DebugCommand::Pause);
+                }
+            }
+            Input::Corrupted => {}
+        }
+    }
+
+    fn handle_packet(&mut self, packet: &[u8]) {
+        let text = String::from_utf8_lossy(packet);
+        let arguments = text.get(1..).unwrap_or_default();
+        let reply = match packet.first() {
+            Some(b'?') => self.stop_reason(),
+            Some(b'g') => Some(self.read_registers()),
+            Some(b'G') => Some(self.write_registers(arguments)),
+            Some(b'p') => Some(self.read_register(arguments)),
+            Some(b'P') => Some(self.write_register(arguments)),
+            Some(b'm') => self.read_memory(arguments),
+            Some(b'M') => self.write_memory(packet, false),
+            Some(b'X') => self.write_memory(packet, true),
+            Some(b'Z') => Some(self.insert_point(arguments)),
+            Some(b'z') => Some(self.remove_point(arguments)),
+            Some(b'c') => self.resume(DebugCommand::Continue, arguments),
+            Some(b's') => self.resume(DebugCommand::StepOne, arguments),
+            Some(b'b') if arguments == "s" => self.resume(DebugCommand::StepBack, ""),
+            Some(b'b') if arguments == "c" => self.resume(DebugCommand::ReverseContinue, ""),
+            Some(b'D') => {
+                self.detach();
+                Some(OK_REPLY.to_string())
+            }
+            Some(b'k') => {
+                self.kill_requested = true;
+                None
+            }
+            // there is only a single thread
+            Some(b'H' | b'T') => Some(OK_REPLY.to_string()),
+            Some(b'q' | b'Q' | b'v') => self.handle_general_packet(&text),
+            _ => Some(UNSUPPORTED_REPLY.to_string()),
+        };
+        if let Some(reply) = reply {
+            self.reply(&reply);
+        }
+    }
+
+    /// Handles query (`q`, `Q`) and multi-letter (`v`) packets.
+    fn handle_general_packet(&mut self, text: &str) -> Option<String> {
+        let (name, arguments) = text
+            .split_once([':', ';'])
+            .unwrap_or((text, Default::default()));
+        match name {
+            "qSupported" => Some(SUPPORTED_FEATURES.to_string()),
+            "QStartNoAckMode" => {
+                self.disable_acknowledgements = true;
+                Some(OK_REPLY.to_string())
+            }
+            "qXfer" => Some(read_features(arguments)),
+            "qAttached" => Some("1".to_string()),
+            "qC" => Some("QC1".to_string()),
+            "qfThreadInfo" => Some("m1".to_string()),
+            "qsThreadInfo" => Some("l".to_string()),
+            "vCont?" => Some("vCont;c;s".to_string()),
+            // only the first action is taken into account, it applies to all cores anyway
+            "vCont" => match arguments.as_bytes().first() {
+                Some(b'c') => self.resume(DebugCommand::Continue, ""),
+                Some(b's') => self.resume(DebugCommand::StepOne, ""),
+                _ => Some(UNSUPPORTED_REPLY.to_string()),
+            },
+            "vKill" => {
+                self.kill_requested = true;
+                Some(OK_REPLY.to_string())
+            }
+            _ => Some(UNSUPPORTED_REPLY.to_string()),
+        }
+    }
+
+    fn handle_debug_message(&mut self, message: DebugMessage) {
+        match message {
+            DebugMessage::Stop => unreachable!(),
+            DebugMessage::WaitForStart(wait_group) => {
+                if !self.started {
+                    self.start_notifications.push(wait_group);
+                }
+            }
+            DebugMessage::HitBreakpoint(..) => self.stopped(format!("T{:02x}swbreak:;", SIGTRAP)),
+            DebugMessage::Log(_, message) => {
+                // console output is only allowed while the client waits for a stop
+                if self.awaiting_stop_reply {
+                    self.reply(&format!("O{}", encode_hex((message + "\n").as_bytes())));
+                }
+            }
+            DebugMessage::HitWatchpoint(_, hit) => {
+                let kind = match hit.kind {
+                    WatchKind::Read => "rwatch",
+                    WatchKind::Write | WatchKind::Access => "watch",
+                };
+                self.stopped(format!("T{:02x}{}:{:x};", SIGTRAP, kind, hit.address));
+            }
+            DebugMessage::Breaking(_) => {
+                let reply = match std::mem::take(&mut self.history_exhausted) {
+                    true => history_exhausted_reply(),
+                    false => format!("S{:02x}", SIGTRAP),
+                };
+                self.stopped(reply);
+            }
+            DebugMessage::Pausing(_) => {
+                let signal = match self.interrupt_requested {
+                    true => SIGINT,
+                    false => SIGTRAP,
+                };
+                self.stopped(format!("S{:02x}", signal));
+            }
+            DebugMessage::BreakState { registers, .. } => self.registers = registers,
+            DebugMessage::Memory { bytes, .. } => self.complete_memory_request(bytes),
+            // followed by a `Breaking` message
+            DebugMessage::HistoryExhausted => self.history_exhausted = true,
+        }
+    }
+
+    fn start(&mut self) {
+        self.started = true;
+        self.start_notifications.clear(); // ==> notify all
+    }
+
+    /// The client expects the target to be stopped after connecting.
+    fn attach(&mut self) {
+        if self.stop_reply.is_none() {
+            self.send_command(DebugCommand::Pause);
+        }
+        self.awaiting_stop_reply = false;
+        self.start();
+    }
+
+    /// Removes all breakpoints and watchpoints and continues the execution, so that the
+    /// machine keeps running without a client.
+    fn detach(&mut self) {
+        if !self.breakpoints.is_empty() {
+            let locations = std::mem::take(&mut self.breakpoints);
+            self.send_command(DebugCommand::RemoveBreakpoints(locations));
+        }
+        if !self.watchpoints.is_empty() {
+            let watchpoints = std::mem::take(&mut self.watchpoints);
+            self.send_command(DebugCommand::RemoveWatchpoints(watchpoints));
+        }
+        self.send_command(DebugCommand::Continue);
+        self.stop_reply = None;
+        self.awaiting_stop_reply = false;
+        self.interrupt_requested = false;
+    }
+
+    fn terminate(&mut self) {
+        // the debug handle only receives commands after starting
+        self.start();
+        self.send_command(DebugCommand::Terminate);
+    }
+
+    /// Replies to `?` immediately while breaking, otherwise as soon as the target stops.
+    fn stop_reason(&mut self) -> Option<String> {
+        if self.stop_reply.is_none() {
+            self.awaiting_stop_reply = true;
+        }
+        self.stop_reply.clone()
+    }
+
+    fn stopped(&mut self, reply: String) {
+        self.interrupt_requested = false;
+        if std::mem::take(&mut self.awaiting_stop_reply) {
+            self.reply(&reply);
+        }
+        self.stop_reply = Some(reply);
+    }
+
+    /// Resumes the execution at the given (optional) address. The reply is sent when the
+    /// target stops again.
+    fn resume(&mut self, command: DebugCommand, address: &str) -> Option<String> {
+        if self.stop_reply.is_none() {
+            return Some(ERROR_REPLY.to_string());
+        }
+        if !address.is_empty() {
+            let Some(address) = parse_hex(address) else {
+                return Some(ERROR_REPLY.to_string());
+            };
+            self.send_command(DebugCommand::SetRegister(
+                Processor::INSTRUCTION_POINTER.0,
+                address,
+            ));
+        }
+        self.send_command(command);
+        self.stop_reply = None;
+        self.awaiting_stop_reply = true;
+        None
+    }
+
+    fn read_registers(&self) -> String {
+        if self.registers.len() != NUM_REGISTERS {
+            return ERROR_REPLY.to_string();
+        }
+        self.registers
+            .iter()
+            .fold(String::new(), |mut result, value| {
+                let _ = write!(result, "{:08x}", value);
+                result
+            })
+    }
+
+    fn write_registers(&mut self, arguments: &str) -> String {
+        if self.registers.len() != NUM_REGISTERS
+            || !arguments.is_ascii()
+            || arguments.len() != NUM_REGISTERS * REGISTER_DIGITS
+        {
+            return ERROR_REPLY.to_string();
+        }
+        for index in 0..NUM_REGISTERS {
+            let digits = &arguments[index * REGISTER_DIGITS..(index + 1) * REGISTER_DIGITS];
+            let Some(value) = parse_hex(digits) else {
+                return ERROR_REPLY.to_string();
+            };
+            if self.registers[index] != value {
+                self.set_register(index, value);
+            }
+        }
+        OK_REPLY.to_string()
+    }
+
+    fn read_register(&self, arguments: &str) -> String {
+        parse_hex(arguments)
+            .and_then(|index| self.registers.get(index as usize))
+            .map_or_else(|| ERROR_REPLY.to_string(), |value| format!("{:08x}", value))
+    }
+
+    fn write_register(&mut self, arguments: &str) -> String {
+        let parsed = arguments
+            .split_once('=')
+            .and_then(|(index, value)| Some((parse_hex(index)? as usize, parse_hex(value)?)));
+        match parsed {
+            Some((index, value)) if index < self.registers.len() => {
+                self.set_register(index, value);
+                OK_REPLY.to_string()
+            }
+            _ => ERROR_REPLY.to_string(),
+        }
+    }
+
+    fn set_register(&mut self, index: usize, value: Word) {
+        self.registers[index] = value;
+        self.send_command(DebugCommand::SetRegister(index as u8, value));
+    }
+
+    fn read_memory(&mut self, arguments: &str) -> Option<String> {
+        let Some((address, length)) = parse_address_and_length(arguments) else {
+            return Some(ERROR_REPLY.to_string());
+        };
+        self.pending_memory_requests
+            .push_back(PendingMemoryRequest::Read {
+                length: length as usize,
+            });
+        self.send_command(DebugCommand::ReadMemory { address, length });
+        None
+    }
+
+    /// Handles `M` (hexadecimal data) and `X` (binary data) packets.
+    fn write_memory(&mut self, packet: &[u8], binary: bool) -> Option<String> {
+        let Some(separator) = packet.iter().position(|&byte| byte == b':') else {
+            return Some(ERROR_REPLY.to_string());
+        };
+        let header = String::from_utf8_lossy(&packet[1..separator]);
+        let data = &packet[separator + 1..];
+        let bytes = match binary {
+            true => Some(data.to_vec()),
+            false => std::str::from_utf8(data).ok().and_then(decode_hex),
+        };
+        let Some(((address, length), bytes)) = parse_address_and_length(&header).zip(bytes) else {
+            return Some(ERROR_REPLY.to_string());
+        };
+        if bytes.len() != length as usize {
+            return Some(ERROR_REPLY.to_string());
+        }
+        if bytes.is_empty() {
+            // used by clients to probe support for `X` packets
+            return Some(OK_REPLY.to_string());
+        }
+        self.pending_memory_requests
+            .push_back(PendingMemoryRequest::Write {
+                length: bytes.len(),
+            });
+        self.send_command(DebugCommand::WriteMemory { address, bytes });
+        None
+    }
+
+    /// Answers the oldest pending packet (the debug handle answers in order). Accesses beyond
+    /// the end of the memory fail.
+    fn complete_memory_request(&mut self, bytes: Vec<u8>) {
+        let Some(request) = self.pending_memory_requests.pop_front() else {
+            return;
+        };
+        let reply = match request {
+            PendingMemoryRequest::Read { length } if bytes.len() == length => encode_hex(&bytes),
+            PendingMemoryRequest::Write { length } if bytes.len() == length => OK_REPLY.to_string(),
+            _ => ERROR_REPLY.to_string(),
+        };
+        self.reply(&reply);
+    }
+
+    /// Handles `Z` packets (`type,address,kind`). Software and hardware breakpoints are the
+    /// same.
+    fn insert_point(&mut self, arguments: &str) -> String {
+        match parse_point(arguments) {
+            Some(Point::Breakpoint(location)) => {
+                if !self.breakpoints.contains(&location) {
+                    self.breakpoints.push(location);
+                }
+                self.send_command(DebugCommand::SetBreakpoints(vec![(
+                    location,
+                    Breakpoint::default(),
+                )]));
+                OK_REPLY.to_string()
+            }
+            Some(Point::Watchpoint(watchpoint)) => {
+                if !self.watchpoints.contains(&watchpoint) {
+                    self.watchpoints.push(watchpoint.clone());
+                    self.send_command(DebugCommand::SetWatchpoints(vec![watchpoint]));
+                }
+                OK_REPLY.to_string()
+            }
+            None => UNSUPPORTED_REPLY.to_string(),
+        }
+    }
+
+    /// Handles `z` packets.
+    fn remove_point(&mut self, arguments: &str) -> String {
+        match parse_point(arguments) {
+            Some(Point::Breakpoint(location)) => {
+                self.breakpoints.retain(|&existing| existing != location);
+                self.send_command(DebugCommand::RemoveBreakpoints(vec![location]));
+                OK_REPLY.to_string()
+            }
+            Some(Point::Watchpoint(watchpoint)) => {
+                self.watchpoints.retain(|existing| *existing != watchpoint);
+                self.send_command(DebugCommand::RemoveWatchpoints(vec![watchpoint]));
+                OK_REPLY.to_string()
+            }
+            None => UNSUPPORTED_REPLY.to_string(),
+        }
+    }
+
+    fn reply(&mut self, reply: &str) {
+        self.outgoing.push(reply.as_bytes().to_vec());
+    }
+
+    fn send_command(&mut self, command: DebugCommand) {
+        send_command(&self.command_sender, command);
+    }
+}
+
+#[derive(Debug)]
+enum Point {
+    Breakpoint(Address),
+    Watchpoint(Watchpoint),
+}
+
+/// Parses the arguments of `Z` and `z` packets. Returns `None` for unsupported types.
+fn parse_point(arguments: &str) -> Option<Point> {
+    let mut parts = arguments.splitn(3, ',');
+    let point_type = parts.next()?;
+    let address = parse_hex(parts.next()?)?;
+    // conditions and commands (after a semicolon) are not supported
+    let kind = parse_hex(parts.next()?.split(';').next()?)?;
+    let watch_kind = match point_type {
+        "0" | "1" => return Some(Point::Breakpoint(address)),
+        "2" => WatchKind::Write,
+        "3" => WatchKind::Read,
+        "4" => WatchKind::Access,
+        _ => return None,
+    };
+    Some(Point::Watchpoint(Watchpoint {
+        address,
+        length: kind,
+        kind: watch_kind,
+        value: None,
+    }))
+}
+
+fn history_exhausted_reply() -> String {
+    format!("T{:02x}replaylog:begin;", SIGTRAP)
+}
+
+/// Handles `qXfer:features:read:target.xml:offset,length` packets.
+fn read_features(arguments: &str) -> String {
+    let Some(range) = arguments.strip_prefix("features:read:target.xml:") else {
+        return UNSUPPORTED_REPLY.to_string();
+    };
+    let Some((offset, length)) = parse_address_and_length(range) else {
+        return ERROR_REPLY.to_string();
+    };
+    let description = target_description();
+    let start = (offset as usize).min(description.len());
+    let end = start.saturating_add(length as usize).min(description.len());
+    let marker = match end == description.len() {
+        true => 'l', // last chunk
+        false => 'm',
+    };
+    format!("{}{}", marker, &description[start..end])
+}
+
+/// Describes the register file: the general purpose registers followed by the frame pointer,
+/// the flags, the instruction pointer and the stack pointer (in the order of their indices).
+fn target_description() -> String {
+    let mut flags: Vec<_> = Flag::as_hashmap().into_iter().collect();
+    flags.sort_by_key(|&(_, shift)| shift);
+
+    let mut description = String::from(
+        "<?xml version=\"1.0\"?>\n\
+         <!DOCTYPE target SYSTEM \"gdb-target.dtd\">\n\
+         <target version=\"1.0\">\n\
+         <feature name=\"org.backseat-safe-system-2k.core\">\n",
+    );
+    let _ = writeln!(
+        description,
+        "<flags id=\"flags_type\" size=\"{}\">",
+        std::mem::size_of::<Word>()
+    );
+    for (name, shift) in flags {
+        let _ = writeln!(
+            description,
+            "<field name=\"{}\" start=\"{}\" end=\"{}\"/>",
+            name, shift, shift
+        );
+    }
+    description.push_str("</flags>\n");
+    for index in 0..NUM_REGISTERS {
+        let register_type = match Register(index as u8) {
+            Processor::FLAGS => "flags_type",
+            Processor::INSTRUCTION_POINTER => "code_ptr",
+            Processor::FRAME_POINTER | Processor::STACK_POINTER => "data_ptr",
+            _ => "uint32",
+        };
+        let _ = writeln!(
+            description,
+            "<reg name=\"{}\" bitsize=\"{}\" regnum=\"{}\" type=\"{}\"/>",
+            register_name(index),
+            Word::BITS,
+            index,
+            register_type
+        );
+    }
+    description.push_str("</feature>\n</target>\n");
+    description
+}
+
+fn parse_hex(digits: &str) -> Option<Word> {
+    // `from_str_radix` would also accept a sign
+    match is_hex_digits(digits) {
+        true => Word::from_str_radix(digits, 16).ok(),
+        false => None,
+    }
+}
+
+/// Parses `address,length`.
+fn parse_address_and_length(arguments: &str) -> Option<(Address, Word)> {
+    let (address, length) = arguments.split_once(',')?;
+    Some((parse_hex(address)?, parse_hex(length)?))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut result, byte| {
+        let _ = write!(result, "{:02x}", byte);
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write as _, net::TcpStream, time::Duration};
+
+    use crossbeam_channel::bounded;
+
+    use super::*;
+    use crate::address_constants::ENTRY_POINT;
+    use crate::debugger::gdb_protocol::frame;
+
+    fn create_stub() -> (GdbStub, Receiver<DebugCommand>) {
+        let (_, receiver) = bounded(1);
+        let (command_sender, command_receiver) = bounded(super::super::CHANNEL_BOUND);
+        (GdbStub::new(receiver, command_sender), command_receiver)
+    }
+
+    /// Handles the packet and returns the replies.
+    fn packet(stub: &mut GdbStub, packet: &str) -> Vec<String> {
+        stub.handle_packet(packet.as_bytes());
+        stub.outgoing
+            .drain(..)
+            .map(|reply| String::from_utf8(reply).unwrap())
+            .collect()
+    }
+
+    fn stop_at_entry_point(stub: &mut GdbStub) {
+        let mut registers = vec![0; NUM_REGISTERS];
+        registers[Processor::INSTRUCTION_POINTER.0 as usize] = ENTRY_POINT;
+        stub.handle_debug_message(DebugMessage::BreakState {
+            registers,
+            call_stack: Vec::new(),
+        });
+        stub.handle_debug_message(DebugMessage::Pausing(ENTRY_POINT));
+    }
+
+    #[test]
+    fn registers_are_read_and_written() {
+        let (mut stub, commands) = create_stub();
+        assert_eq!(packet(&mut stub, "g"), [ERROR_REPLY]);
+        stop_at_entry_point(&mut stub);
+
+        let registers = packet(&mut stub, "g").remove(0);
+        assert_eq!(registers.len(), NUM_REGISTERS * REGISTER_DIGITS);
+        assert_eq!(
+            &registers[0xfe * REGISTER_DIGITS..0xff * REGISTER_DIGITS],
+            format!("{:08x}", ENTRY_POINT)
+        );
+        assert_eq!(packet(&mut stub, "P1=0000002a"), [OK_REPLY]);
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::SetRegister(1, 0x2a))
+        ));
+        assert_eq!(packet(&mut stub, "p1"), ["0000002a"]);
+        assert_eq!(packet(&mut stub, "p100"), [ERROR_REPLY]);
+
+        let padding = "0".repeat(NUM_REGISTERS * REGISTER_DIGITS - 9);
+        assert_eq!(
+            packet(&mut stub, &format!("G0000000é{}", padding)),
+            [ERROR_REPLY]
+        );
+    }
+
+    #[test]
+    fn lost_client_is_detached() {
+        let (message_sender, receiver) = bounded(super::super::CHANNEL_BOUND);
+        let (command_sender, commands) = bounded(super::super::CHANNEL_BOUND);
+        let gdb = GdbHandler::start("127.0.0.1:0".parse().unwrap());
+        let mut client = TcpStream::connect(gdb.local_address()).unwrap();
+        let stub = GdbStub::new(receiver, command_sender);
+        let thread = std::thread::spawn(move || stub.serve(gdb));
+        let timeout = Duration::from_secs(5);
+
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::Pause)
+        ));
+        client.write_all(&frame(b"Z0,100,8")).unwrap();
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::SetBreakpoints(breakpoints)) if breakpoints.len() == 1
+        ));
+        drop(client);
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::RemoveBreakpoints(locations)) if locations == [0x100]
+        ));
+        assert!(matches!(
+            commands.recv_timeout(timeout),
+            Ok(DebugCommand::Continue)
+        ));
+
+        message_sender.send(DebugMessage::Stop).unwrap();
+        thread.join().unwrap();
+    }
+
+    #[test]
+    fn points_memory_and_stepping_are_forwarded() {
+        let (mut stub, commands) = create_stub();
+        stop_at_entry_point(&mut stub);
+
+        assert_eq!(packet(&mut stub, "Z0,100,8"), [OK_REPLY]);
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::SetBreakpoints(breakpoints))
+                if breakpoints.len() == 1 && breakpoints[0].0 == 0x100
+        ));
+        assert_eq!(packet(&mut stub, "Z3,200,4"), [OK_REPLY]);
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::SetWatchpoints(watchpoints))
+                if watchpoints[0].address == 0x200
+                    && watchpoints[0].length == 4
+                    && watchpoints[0].kind == WatchKind::Read
+        ));
+        assert_eq!(packet(&mut stub, "Z9,0,0"), [UNSUPPORTED_REPLY]);
+
+        assert!(packet(&mut stub, "m200,2").is_empty());
+        assert!(matches!(
+            commands.try_recv(),
+            Ok(DebugCommand::ReadMemory {
+                address: 0x200,
+                length: 2
+            })
+        ));
+        stub.handle_debug_message(DebugMessage::Memory {
+            address: 0x200,
+            bytes: vec![0xab, 0x01],
+        });
+        assert_eq!(stub.outgoing.pop().unwrap(), b"ab01");
+        for digits in ["zz", "+f"] {
+            assert_eq!(
+                packet(&mut stub, &format!("M200,1:{}", digits)),
+                [ERROR_REPLY]
+            );
+        }
+        assert_eq!(packet(&mut stub, "m+200,2"), [ERROR_REPLY]);
+        assert!(commands.try_recv().is_err());
+
+        assert!(packet(&mut stub, "s").is_empty());
+        assert!(matches!(commands.try_recv(), Ok(DebugCommand::StepOne)));
+        stub.handle_debug_message(DebugMessage::HitBreakpoint(0x100, 1));
+        assert_eq!(stub.outgoing.pop().unwrap(), b"T05swbreak:;");
+        assert_eq!(packet(&mut stub, "?"), ["T05swbreak:;"]);
+
+        assert!(packet(&mut stub, "bs").is_empty());
+        stub.handle_debug_message(DebugMessage::HistoryExhausted);
+        assert!(stub.outgoing.is_empty());
+        stub.handle_debug_message(DebugMessage::Breaking(ENTRY_POINT));
+        assert_eq!(stub.outgoing.pop().unwrap(), b"T05replaylog:begin;");
+    }
+
+    #[test]
+    fn target_description_is_read_in_chunks() {
+        let description = target_description();
+        assert!(description.contains("<reg name=\"r251\" bitsize=\"32\" regnum=\"251\""));
+        assert!(description.contains("<reg name=\"ip\" bitsize=\"32\" regnum=\"254\""));
+        assert!(description.contains("<field name=\"Carry\" start=\"1\" end=\"1\"/>"));
+
+        let first = read_features("features:read:target.xml:0,a");
+        assert_eq!(first, format!("m{}", &description[..10]));
+        let rest = read_features(&format!(
+            "features:read:target.xml:a,{:x}",
+            description.len()
+        ));
+        assert_eq!(rest, format!("l{}", &description[10..]));
+        assert_eq!(read_features("features:read:other.xml:0,10"), "");
+    }
+}
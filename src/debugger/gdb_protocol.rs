@@ -0,0 +1,332 @@
+use std::{
+    io::{self, Read},
+    net::{SocketAddr, TcpListener, TcpStream},
+};
+
+use super::{
+    segmented_reader,
+    tcp_protocol::{listen, reject_pending_clients, write_all_non_blocking, Error, Result},
+};
+
+/// The port of the GDB remote serial protocol unless another one is configured.
+pub const DEFAULT_PORT: u16 = 57019;
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+const PACKET_START: u8 = b'$';
+const CHECKSUM_START: u8 = b'#';
+const ESCAPE: u8 = b'}';
+const ESCAPE_XOR: u8 = 0x20;
+const INTERRUPT: u8 = 0x03;
+const ACK: u8 = b'+';
+const NACK: u8 = b'-';
+
+/// Input from a client of the GDB remote serial protocol.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Input {
+    /// The (unescaped) contents of a packet.
+    Packet(Vec<u8>),
+    /// Request to stop the running target (Ctrl-C).
+    Interrupt,
+    /// A packet with an invalid checksum, which has to be retransmitted.
+    Corrupted,
+}
+
+/// Splits the input into packets (`$data#checksum`) and interrupts. Acknowledgements are
+/// skipped.
+#[derive(Debug, Default)]
+pub struct PacketReader {
+    buffer: Vec<u8>,
+}
+
+impl PacketReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the available input and returns all completed packets and interrupts.
+    pub fn read(&mut self, mut from: impl Read) -> segmented_reader::Result<Vec<Input>> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        let length = from.read(&mut chunk).map_err(segmented_reader::Error::Io)?;
+        if length == 0 {
+            return Err(segmented_reader::Error::Disconnected);
+        }
+        self.buffer.extend_from_slice(&chunk[..length]);
+
+        let mut inputs = Vec::new();
+        while let Some(input) = self.next_input() {
+            inputs.push(input);
+        }
+        Ok(inputs)
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    fn next_input(&mut self) -> Option<Input> {
+        loop {
+            match *self.buffer.first()? {
+                INTERRUPT => {
+                    self.buffer.remove(0);
+                    return Some(Input::Interrupt);
+                }
+                PACKET_START => break,
+                // acknowledgements and garbage between packets
+                _ => {
+                    self.buffer.remove(0);
+                }
+            }
+        }
+        let checksum_start = self
+            .buffer
+            .iter()
+            .position(|&byte| byte == CHECKSUM_START)?;
+        let end = checksum_start + 3;
+        if self.buffer.len() < end {
+            return None;
+        }
+        let raw = &self.buffer[1..checksum_start];
+        let expected = std::str::from_utf8(&self.buffer[checksum_start + 1..end])
+            .ok()
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        let input = match expected == Some(checksum(raw)) {
+            true => Input::Packet(unescape(raw)),
+            false => Input::Corrupted,
+        };
+        self.buffer.drain(..end);
+        Some(input)
+    }
+}
+
+pub struct GdbHandler {
+    listener: TcpListener,
+    client: Option<TcpStream>,
+    reader: PacketReader,
+    acknowledge: bool,
+}
+
+pub enum PollReturn {
+    Nothing,
+    ClientConnected,
+    ClientDisconnected,
+    /// Packets and interrupts (corrupted packets have already been rejected).
+    Received(Vec<Input>),
+}
+
+impl GdbHandler {
+    pub fn start(address: SocketAddr) -> Self {
+        let listener = listen(address, "GDB");
+
+        Self {
+            listener,
+            client: None,
+            reader: PacketReader::new(),
+            acknowledge: true,
+        }
+    }
+
+    pub fn poll(&mut self) -> Result<PollReturn> {
+        if self.client.is_some() {
+            reject_pending_clients(&self.listener);
+        }
+        match self.client {
+            None => self.accept(),
+            Some(ref mut client) => match self.reader.read(client) {
+                Ok(inputs) => {
+                    let mut received = Vec::with_capacity(inputs.len());
+                    for input in inputs {
+                        let acknowledgement = match input {
+                            Input::Packet(_) if self.acknowledge => Some(ACK),
+                            Input::Corrupted => Some(NACK),
+                            _ => None,
+                        };
+                        // a failed write has already disconnected the client
+                        if let Some(acknowledgement) = acknowledgement {
+                            if self.write(&[acknowledgement]).is_err() {
+                                return Ok(PollReturn::ClientDisconnected);
+                            }
+                        }
+                        if input == Input::Corrupted {
+                            continue;
+                        }
+                        received.push(input);
+                    }
+                    Ok(PollReturn::Received(received))
+                }
+                Err(segmented_reader::Error::Disconnected) => {
+                    self.disconnect();
+                    Ok(PollReturn::ClientDisconnected)
+                }
+                Err(segmented_reader::Error::Io(error))
+                    if error.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    Ok(PollReturn::Nothing)
+                }
+                Err(segmented_reader::Error::Io(_)) => {
+                    self.disconnect();
+                    Ok(PollReturn::ClientDisconnected)
+                }
+            },
+        }
+    }
+
+    pub fn send(&mut self, data: &[u8]) -> Result<()> {
+        self.write(&frame(data))
+    }
+
+    /// Returns whether a client is connected. Failed operations disconnect the client.
+    pub fn has_client(&self) -> bool {
+        self.client.is_some()
+    }
+
+    #[cfg(test)]
+    pub fn local_address(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    /// Stops acknowledging packets (after the client requested `QStartNoAckMode`).
+    pub fn disable_acknowledgements(&mut self) {
+        self.acknowledge = false;
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<()> {
+        let Some(client) = &mut self.client else {
+            return Ok(());
+        };
+        let result = write_all_non_blocking(client, buffer);
+        if result.is_err() {
+            self.disconnect();
+        }
+        result.map_err(Error::Io)
+    }
+
+    fn disconnect(&mut self) {
+        self.client = None; // Dropping the stream disconnects it, if it is still active.
+        self.reader.clear();
+    }
+
+    fn accept(&mut self) -> Result<PollReturn> {
+        match self.listener.accept() {
+            Ok((client, _)) => {
+                client.set_nodelay(true).map_err(Error::Io)?;
+                client.set_nonblocking(true).map_err(Error::Io)?;
+                self.client = Some(client);
+                self.acknowledge = true;
+                Ok(PollReturn::ClientConnected)
+            }
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(PollReturn::Nothing),
+            Err(error) => Err(Error::Io(error)),
+        }
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |sum, &byte| sum.wrapping_add(byte))
+}
+
+/// Frames the data as a packet. Characters with a special meaning are escaped.
+pub fn frame(data: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(data.len());
+    for &byte in data {
+        match byte {
+            PACKET_START | CHECKSUM_START | ESCAPE | b'*' => {
+                escaped.extend([ESCAPE, byte ^ ESCAPE_XOR]);
+            }
+            _ => escaped.push(byte),
+        }
+    }
+    let mut packet = Vec::with_capacity(escaped.len() + 4);
+    packet.push(PACKET_START);
+    packet.extend_from_slice(&escaped);
+    packet.extend(format!("#{:02x}", checksum(&escaped)).bytes());
+    packet
+}
+
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            ESCAPE => result.extend(bytes.next().map(|&escaped| escaped ^ ESCAPE_XOR)),
+            _ => result.push(byte),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, thread, time::Duration};
+
+    use super::*;
+
+    fn poll_until_event(handler: &mut GdbHandler) -> PollReturn {
+        for _ in 0..100 {
+            match handler.poll() {
+                Ok(PollReturn::Nothing) => thread::sleep(Duration::from_millis(10)),
+                Ok(event) => return event,
+                Err(error) => panic!("polling failed: {}", error),
+            }
+        }
+        panic!("no client event");
+    }
+
+    #[test]
+    fn further_clients_are_rejected_until_the_client_disconnects() {
+        let mut handler = GdbHandler::start("127.0.0.1:0".parse().unwrap());
+        let client = TcpStream::connect(handler.local_address()).unwrap();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected
+        ));
+
+        let mut rejected = TcpStream::connect(handler.local_address()).unwrap();
+        rejected
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        handler.poll().unwrap();
+        assert!(matches!(rejected.read(&mut [0]), Ok(0)));
+
+        drop(client);
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientDisconnected
+        ));
+        let mut client = TcpStream::connect(handler.local_address()).unwrap();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected
+        ));
+        client.write_all(b"$?#3f").unwrap();
+        let PollReturn::Received(inputs) = poll_until_event(&mut handler) else {
+            panic!("the packet was not received");
+        };
+        assert_eq!(inputs, [Input::Packet(b"?".to_vec())]);
+    }
+
+    #[test]
+    fn packets_and_interrupts_are_read() -> segmented_reader::Result<()> {
+        let mut reader = PacketReader::new();
+        let input = b"+$g#67\x03$m0,4#fd$X0,1:}]#";
+        assert_eq!(
+            reader.read(&input[..])?,
+            [
+                Input::Packet(b"g".to_vec()),
+                Input::Interrupt,
+                Input::Packet(b"m0,4".to_vec())
+            ]
+        );
+        // `}]` is an escaped `}`
+        assert_eq!(
+            reader.read(&b"f9$s#00"[..])?,
+            [Input::Packet(b"X0,1:}".to_vec()), Input::Corrupted]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        assert_eq!(frame(b"OK"), b"$OK#9a");
+        assert_eq!(frame(b"a#b"), b"$a}\x03b#43");
+        assert_eq!(unescape(b"a}\x03b"), b"a#b");
+    }
+}
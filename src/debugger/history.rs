@@ -0,0 +1,268 @@
+use std::{collections::VecDeque, ops::Range};
+
+use crate::{
+    memory::Memory,
+    mmu::MemoryAccess,
+    processor::{DataAccess, Processor, ProcessorState},
+    Address, Register, Word,
+};
+
+/// The change of the call stack (as tracked by the debugger) caused by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallStackChange {
+    /// The address of a call instruction has been pushed.
+    Pushed,
+    /// The given address has been popped by a return instruction.
+    Popped(Address),
+}
+
+/// Everything needed to undo an executed instruction.
+#[derive(Debug)]
+struct Entry {
+    core_id: Word,
+    /// The previous values of the changed registers.
+    registers: Vec<(Register, Word)>,
+    /// The previous contents of the written (physical) memory ranges in the order of the
+    /// writes.
+    memory: Vec<(usize, Vec<u8>)>,
+    state: ProcessorState,
+    call_stack_change: Option<CallStackChange>,
+}
+
+#[derive(Debug)]
+pub struct UndoneInstruction {
+    /// The restored ranges of the memory. The instruction cache has to be regenerated for them.
+    pub modified_memory: Vec<Range<usize>>,
+    pub call_stack_change: Option<CallStackChange>,
+}
+
+impl Entry {
+    /// Returns the (approximate) number of bytes that the entry occupies.
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.registers.len() * std::mem::size_of::<(Register, Word)>()
+            + self
+                .memory
+                .iter()
+                .map(|(_, bytes)| std::mem::size_of::<(usize, Vec<u8>)>() + bytes.len())
+                .sum::<usize>()
+    }
+}
+
+/// Records the most recently executed instructions to be able to restore the state of the
+/// processors and the memory before them. The recorded entries occupy at most `capacity`
+/// bytes, the oldest ones are dropped first. Effects on the periphery (e.g. the display or the
+/// terminal) are not undone.
+#[derive(Debug)]
+pub struct History {
+    capacity: usize,
+    /// The number of bytes that the entries occupy.
+    size: usize,
+    entries: VecDeque<Entry>,
+    registers_before: Vec<Word>,
+    state_before: Option<ProcessorState>,
+    call_stack_change: Option<CallStackChange>,
+}
+
+impl History {
+    /// The capacity is given in bytes, zero disables the history.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            size: 0,
+            entries: VecDeque::new(),
+            registers_before: Vec::new(),
+            state_before: None,
+            call_stack_change: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Returns the ID of the core that executed the last recorded instruction.
+    pub fn last_core_id(&self) -> Option<Word> {
+        self.entries.back().map(|entry| entry.core_id)
+    }
+
+    /// Has to be called right before the processor executes an instruction.
+    pub fn record_before(
+        &mut self,
+        processor: &Processor,
+        call_stack_change: Option<CallStackChange>,
+    ) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.registers_before.clear();
+        self.registers_before
+            .extend_from_slice(processor.registers.contents());
+        self.state_before = Some(processor.state());
+        self.call_stack_change = call_stack_change;
+    }
+
+    /// Has to be called after the instruction has been executed. The data accesses have to be
+    /// recorded by the processor during the execution.
+    pub fn record_after(&mut self, processor: &Processor, data_accesses: &[DataAccess]) {
+        let Some(state) = self.state_before.take() else {
+            return;
+        };
+        let registers = self
+            .registers_before
+            .iter()
+            .zip(processor.registers.contents())
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(index, (&before, _))| (Register(index as u8), before))
+            .collect();
+        let memory = data_accesses
+            .iter()
+            .filter(|access| access.access == MemoryAccess::Write)
+            .map(|access| (access.range.start, access.old_bytes.clone()))
+            .collect();
+        let entry = Entry {
+            core_id: processor.core_id(),
+            registers,
+            memory,
+            state,
+            call_stack_change: self.call_stack_change.take(),
+        };
+        let entry_size = entry.size();
+        if entry_size > self.capacity {
+            // the earlier entries cannot be undone without this one
+            self.entries.clear();
+            self.size = 0;
+            return;
+        }
+        while self.size + entry_size > self.capacity {
+            let oldest = self.entries.pop_front().unwrap();
+            self.size -= oldest.size();
+        }
+        self.size += entry_size;
+        self.entries.push_back(entry);
+    }
+
+    /// Restores the state before the last recorded instruction, which must have been executed
+    /// by the given processor. Returns `None` if there is no recorded instruction left.
+    pub fn step_back(
+        &mut self,
+        processor: &mut Processor,
+        memory: &mut Memory,
+    ) -> Option<UndoneInstruction> {
+        let entry = self.entries.pop_back()?;
+        self.size -= entry.size();
+        debug_assert_eq!(entry.core_id, processor.core_id());
+        for (register, value) in entry.registers {
+            processor.registers[register] = value;
+        }
+        processor.restore_state(entry.state);
+        // later writes may overlap earlier ones, so they are undone first
+        let modified_memory = entry
+            .memory
+            .into_iter()
+            .rev()
+            .map(|(address, bytes)| {
+                let range = address..address + bytes.len();
+                memory.data_mut()[range.clone()].copy_from_slice(&bytes);
+                range
+            })
+            .collect();
+        Some(UndoneInstruction {
+            modified_memory,
+            call_stack_change: entry.call_stack_change,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execute(
+        history: &mut History,
+        processor: &mut Processor,
+        memory: &mut Memory,
+        register_value: Word,
+        memory_value: Word,
+    ) {
+        history.record_before(processor, None);
+        processor.registers[Register(1)] = register_value;
+        processor.increase_cycle_count(1);
+        let access = DataAccess {
+            range: 0x100..0x104,
+            access: MemoryAccess::Write,
+            old_bytes: memory.data()[0x100..0x104].to_vec(),
+        };
+        memory.write_data(0x100, memory_value);
+        history.record_after(processor, &[access]);
+    }
+
+    #[test]
+    fn instructions_are_undone_in_reverse_order() {
+        let mut history = History::new(1024);
+        let mut processor = Processor::new(false, 0, 1);
+        let mut memory = Memory::new();
+        execute(&mut history, &mut processor, &mut memory, 1, 10);
+        execute(&mut history, &mut processor, &mut memory, 2, 20);
+
+        let undone = history.step_back(&mut processor, &mut memory).unwrap();
+        assert_eq!(undone.modified_memory.len(), 1);
+        assert_eq!(undone.modified_memory[0], 0x100..0x104);
+        assert_eq!(processor.registers[Register(1)], 1);
+        assert_eq!(memory.read_data(0x100), 10);
+        assert_eq!(processor.get_cycle_count(), 1);
+
+        history.step_back(&mut processor, &mut memory).unwrap();
+        assert_eq!(processor.registers[Register(1)], 0);
+        assert_eq!(memory.read_data(0x100), 0);
+        assert_eq!(processor.get_cycle_count(), 0);
+        assert!(history.step_back(&mut processor, &mut memory).is_none());
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped() {
+        let mut processor = Processor::new(false, 0, 1);
+        let mut memory = Memory::new();
+        let mut unlimited = History::new(usize::MAX);
+        execute(
+            &mut unlimited,
+            &mut Processor::new(false, 0, 1),
+            &mut memory,
+            1,
+            1,
+        );
+        let entry_size = unlimited.size;
+
+        let mut history = History::new(2 * entry_size);
+        for value in 1..=3 {
+            execute(&mut history, &mut processor, &mut memory, value, value);
+        }
+        assert!(history.step_back(&mut processor, &mut memory).is_some());
+        assert!(history.step_back(&mut processor, &mut memory).is_some());
+        assert!(history.step_back(&mut processor, &mut memory).is_none());
+        assert_eq!(processor.registers[Register(1)], 1);
+
+        let mut disabled = History::new(0);
+        execute(&mut disabled, &mut processor, &mut memory, 4, 4);
+        assert_eq!(disabled.last_core_id(), None);
+    }
+
+    #[test]
+    fn entries_exceeding_the_capacity_clear_the_history() {
+        let mut history = History::new(1024);
+        let mut processor = Processor::new(false, 0, 1);
+        let mut memory = Memory::new();
+        execute(&mut history, &mut processor, &mut memory, 1, 1);
+
+        history.record_before(&processor, None);
+        let access = DataAccess {
+            range: 0..2048,
+            access: MemoryAccess::Write,
+            old_bytes: vec![0; 2048],
+        };
+        history.record_after(&processor, &[access]);
+        assert_eq!(history.last_core_id(), None);
+        assert_eq!(history.size, 0);
+    }
+}
@@ -1,18 +1,23 @@
 use std::{
     fmt,
     io::{self, ErrorKind, Write},
-    net::{TcpListener, TcpStream},
+    net::{SocketAddr, TcpListener, TcpStream},
 };
 
 use crossbeam_utils::Backoff;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{Address, Word};
 
-use super::segmented_reader::{self, Segment, SegmentedReader};
+use super::{
+    segmented_reader::{self, Segment, SegmentedReader},
+    watchpoint::{WatchKind, Watchpoint},
+};
 
-const TCP_INTERFACE_ADDRESS: &str = "127.0.0.1:57017";
-const DEBUGGER_PORT_PREFIX: &str = "Debugger-Port:";
+/// The port of the JSON protocol unless another one is configured.
+pub const DEFAULT_PORT: u16 = 57017;
+/// Printed with the port of the listener to let frontends know where to connect.
+pub const DEBUGGER_PORT_PREFIX: &str = "Debugger-Port:";
 
 #[derive(Debug, Deserialize)]
 pub enum Request {
@@ -20,29 +25,97 @@ pub enum Request {
         stop_on_entry: bool,
     },
     SetBreakpoints {
-        locations: Vec<Address>,
+        locations: Vec<BreakpointLocation>,
     },
     RemoveBreakpoints {
         locations: Vec<Address>,
     },
+    SetWatchpoints {
+        watchpoints: Vec<Watchpoint>,
+    },
+    /// Remove the watchpoints that are equal to the given ones.
+    RemoveWatchpoints {
+        watchpoints: Vec<Watchpoint>,
+    },
     /// Continue normal execution i.e. stop breaking.
     Continue {},
     /// Execute one instruction while breaking.
     StepOne {},
+    /// Execute one instruction while breaking, but run calls until they return.
+    StepOver {},
+    /// Run until the current function returns.
+    StepOut {},
+    /// Run until the instruction at the given location is reached.
+    RunToLocation {
+        location: Address,
+    },
+    /// Undo the last executed instruction while breaking (see `history::History`).
+    StepBack {},
+    /// Undo instructions until a breakpoint is reached or the execution history is exhausted.
+    ReverseContinue {},
     SetRegister {
         register: u8,
         value: Word,
     },
+    /// Read the given number of bytes starting at the (physical) address. Bytes beyond the
+    /// end of the memory are omitted.
+    ReadMemory {
+        address: Address,
+        length: Word,
+    },
+    /// Write the bytes starting at the (physical) address. Bytes beyond the end of the memory
+    /// are dropped.
+    WriteMemory {
+        address: Address,
+        bytes: HexBytes,
+    },
     Terminate {},
 }
 
-#[derive(Debug, Serialize)]
+/// A breakpoint is either given by its location or with a condition (see
+/// `expression::Expression`), the number of hits to ignore and a log message, which turns it
+/// into a logpoint (see `breakpoint::LogMessage`).
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum BreakpointLocation {
+    Plain(Address),
+    Detailed {
+        location: Address,
+        #[serde(default)]
+        condition: Option<String>,
+        #[serde(default)]
+        ignore_count: u64,
+        #[serde(default)]
+        log_message: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub enum Response {
     Hello {
         pid: u32,
+        role: Role,
     },
     HitBreakpoint {
         location: Address,
+        hit_count: u64,
+    },
+    /// Message of the logpoint at `location`.
+    Log {
+        location: Address,
+        message: String,
+    },
+    /// A request could not be fulfilled.
+    Error {
+        message: String,
+    },
+    /// The instruction at `location` accessed the memory at `address`, which is watched.
+    HitWatchpoint {
+        location: Address,
+        address: Address,
+        kind: WatchKind,
+        old_value: Word,
+        new_value: Word,
     },
     Breaking {
         location: Address,
@@ -54,18 +127,85 @@ pub enum Response {
         registers: Vec<Word>,
         call_stack: Vec<Address>,
     },
+    /// The contents of the memory starting at the address (sent in response to `ReadMemory`
+    /// and, containing the written bytes, to `WriteMemory`).
+    Memory {
+        address: Address,
+        bytes: HexBytes,
+    },
+}
+
+/// Bytes that are transmitted as a string of hexadecimal digits (two per byte).
+#[derive(Clone, Debug, PartialEq)]
+pub struct HexBytes(pub Vec<u8>);
+
+impl Serialize for HexBytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let digits: String = self.0.iter().map(|byte| format!("{:02x}", byte)).collect();
+        serializer.serialize_str(&digits)
+    }
+}
+
+impl<'de> Deserialize<'de> for HexBytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let digits = String::deserialize(deserializer)?;
+        if digits.len() % 2 != 0 {
+            return Err(de::Error::custom("odd number of hexadecimal digits"));
+        }
+        decode_hex(&digits)
+            .map(HexBytes)
+            .ok_or_else(|| de::Error::custom("invalid hexadecimal digits"))
+    }
+}
+
+/// Decodes a string of hexadecimal digits (two per byte). Returns `None` if the number of
+/// digits is odd or the string contains anything else.
+pub fn decode_hex(digits: &str) -> Option<Vec<u8>> {
+    // `from_str_radix` would also accept a sign
+    if !digits.len().is_multiple_of(2) || !is_hex_digits(digits) {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+        .collect()
+}
+
+/// Returns whether the string only consists of hexadecimal digits.
+pub fn is_hex_digits(digits: &str) -> bool {
+    digits.bytes().all(|digit| digit.is_ascii_hexdigit())
+}
+
+/// Identifies a connected client of a `TcpHandler`.
+pub type ClientId = u64;
+
+/// The first client controls the debugger. Clients that connect while there is a controlling
+/// client can only observe: they receive all responses, but their requests are rejected.
+/// Observers that do not keep up with reading the responses are disconnected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Role {
+    Controller,
+    Observer,
+}
+
+struct Client {
+    id: ClientId,
+    role: Role,
+    stream: TcpStream,
+    reader: SegmentedReader,
 }
 
 pub struct TcpHandler {
     listener: TcpListener,
-    client: Option<TcpStream>,
-    reader: SegmentedReader,
+    clients: Vec<Client>,
+    next_client_id: u64,
 }
 
 pub enum PollReturn {
     Nothing,
-    ClientConnected,
+    ClientConnected(ClientId, Role),
     ClientDisconnected,
+    /// Requests of the controlling client.
     ReceivedRequests(Vec<Request>),
 }
 
@@ -78,116 +218,210 @@ pub enum Error {
 pub type Result<T> = std::result::Result<T, Error>;
 
 impl TcpHandler {
-    pub fn start() -> Self {
-        let listener =
-            TcpListener::bind(TCP_INTERFACE_ADDRESS).expect("Cannot open debug TCP interface.");
-        listener
-            .set_nonblocking(true)
-            .expect("Cannot set tcp listener to non-blocking.");
-
-        if let Ok(address) = listener.local_addr() {
-            println!("{}{}", DEBUGGER_PORT_PREFIX, address.port());
-        }
-
+    pub fn start(address: SocketAddr) -> Self {
         Self {
-            listener,
-            client: None,
-            reader: SegmentedReader::new(),
+            listener: listen(address, "TCP"),
+            clients: Vec::new(),
+            next_client_id: 0,
         }
     }
 
+    #[cfg(test)]
+    pub fn local_address(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    /// Accepts a new client or reads the requests of the connected ones. Requests of observers
+    /// are answered with an error.
     pub fn poll(&mut self) -> Result<PollReturn> {
-        match self.client {
-            None => self.tcp_accept(),
-            Some(ref mut client) => match self.reader.read(client) {
-                Ok(segments) => {
-                    let result = self.parse_requests(&segments);
-                    if result.is_err() {
-                        self.disconnect();
+        if let Some(connected) = self.accept()? {
+            return Ok(connected);
+        }
+        for index in 0..self.clients.len() {
+            let client = &mut self.clients[index];
+            let role = client.role;
+            let result = match client.reader.read(&mut client.stream) {
+                Ok(segments) => match role {
+                    Role::Controller => Self::parse_requests(&client.reader, &segments),
+                    Role::Observer if segments.is_empty() => continue,
+                    Role::Observer => {
+                        // the input of observers is discarded (including incomplete requests)
+                        client.reader.clear();
+                        let message = Response::Error {
+                            message: "Observers cannot send requests.".to_string(),
+                        };
+                        Self::send_to_client(client, &message).map(|_| PollReturn::Nothing)
                     }
-                    result
-                }
+                },
                 Err(segmented_reader::Error::Disconnected) => {
-                    self.disconnect();
-                    Ok(PollReturn::ClientDisconnected)
+                    self.clients.remove(index);
+                    return Ok(PollReturn::ClientDisconnected);
                 }
                 Err(segmented_reader::Error::Io(error))
                     if error.kind() == io::ErrorKind::WouldBlock =>
                 {
-                    Ok(PollReturn::Nothing)
+                    continue
                 }
-                Err(segmented_reader::Error::Io(error)) => {
-                    self.disconnect();
-                    Err(Error::Io(error))
-                }
-            },
+                Err(segmented_reader::Error::Io(error)) => Err(Error::Io(error)),
+            };
+            // clients that cannot be served are dropped
+            if result.is_err() {
+                self.clients.remove(index);
+            }
+            if role == Role::Controller || result.is_err() {
+                return result;
+            }
         }
+        Ok(PollReturn::Nothing)
     }
 
+    pub fn has_controller(&self) -> bool {
+        self.clients
+            .iter()
+            .any(|client| client.role == Role::Controller)
+    }
+
+    /// Sends the message to all clients.
     pub fn send(&mut self, message: &Response) -> Result<()> {
-        let mut json = serde_json::to_vec(message).map_err(Error::Serde)?;
-        json.push(0);
+        let mut result = Ok(());
+        self.clients
+            .retain_mut(|client| match Self::send_to_client(client, message) {
+                Ok(()) => true,
+                Err(error) => {
+                    result = Err(error);
+                    false
+                }
+            });
+        result
+    }
 
-        self.write_all(&json[..])
+    /// Sends the message to a single client.
+    pub fn send_to(&mut self, id: ClientId, message: &Response) -> Result<()> {
+        let Some(index) = self.clients.iter().position(|client| client.id == id) else {
+            return Ok(());
+        };
+        let result = Self::send_to_client(&mut self.clients[index], message);
+        if result.is_err() {
+            self.clients.remove(index);
+        }
+        result
     }
 
-    fn disconnect(&mut self) {
-        self.client = None; // Dropping the stream disconnects it, if it is still active.
-        self.reader.clear();
+    fn send_to_client(client: &mut Client, message: &Response) -> Result<()> {
+        let mut json = serde_json::to_vec(message).map_err(Error::Serde)?;
+        json.push(0);
+
+        match client.role {
+            Role::Controller => write_all_non_blocking(&mut client.stream, &json[..]),
+            // an observer that does not read must not block the debugger, so it is dropped
+            Role::Observer => write_all_without_waiting(&mut client.stream, &json[..]),
+        }
+        .map_err(Error::Io)
     }
 
-    fn tcp_accept(&mut self) -> Result<PollReturn> {
+    fn accept(&mut self) -> Result<Option<PollReturn>> {
         match self.listener.accept() {
-            Ok((client, _)) => {
-                client.set_nodelay(true).map_err(Error::Io)?;
-                self.client = Some(client);
-                Ok(PollReturn::ClientConnected)
+            Ok((stream, _)) => {
+                stream.set_nodelay(true).map_err(Error::Io)?;
+                stream.set_nonblocking(true).map_err(Error::Io)?;
+                let id = self.next_client_id;
+                self.next_client_id += 1;
+                let role = match self.has_controller() {
+                    true => Role::Observer,
+                    false => Role::Controller,
+                };
+                self.clients.push(Client {
+                    id,
+                    role,
+                    stream,
+                    reader: SegmentedReader::new(),
+                });
+                Ok(Some(PollReturn::ClientConnected(id, role)))
             }
-            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(PollReturn::Nothing),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(None),
             Err(error) => Err(Error::Io(error)),
         }
     }
 
-    fn parse_requests(&mut self, segments: &Vec<Segment>) -> Result<PollReturn> {
+    fn parse_requests(reader: &SegmentedReader, segments: &Vec<Segment>) -> Result<PollReturn> {
         let mut requests = Vec::new();
         for segment in segments {
-            let slice = self.reader.segment(segment);
+            let slice = reader.segment(segment);
             let request: Request = serde_json::from_slice(slice).map_err(Error::Serde)?;
             requests.push(request);
         }
 
         Ok(PollReturn::ReceivedRequests(requests))
     }
+}
 
-    /// Non-blocking version of write_all.
-    fn write_all(&mut self, mut buffer: &[u8]) -> Result<()> {
-        let client = match &mut self.client {
-            Some(client) => client,
-            None => return Ok(()),
-        };
+/// Opens a non-blocking listener for the debug interface and prints its port (which is chosen
+/// by the system if the given port is 0).
+pub fn listen(address: SocketAddr, interface: &str) -> TcpListener {
+    let listener = TcpListener::bind(address).unwrap_or_else(|error| {
+        panic!(
+            "Cannot open debug {} interface on {}: {}",
+            interface, address, error
+        )
+    });
+    listener
+        .set_nonblocking(true)
+        .unwrap_or_else(|_| panic!("Cannot set {} listener to non-blocking.", interface));
 
-        let backoff = Backoff::new();
-        while !buffer.is_empty() {
-            match client.write(buffer) {
-                Ok(0) => {
-                    self.disconnect();
-                    return Err(Error::Io(io::Error::new(
-                        ErrorKind::WriteZero,
-                        "failed to write whole buffer",
-                    )));
-                }
-                Ok(n) => buffer = &buffer[n..],
-                Err(ref error) if error.kind() == ErrorKind::Interrupted => {}
-                Err(ref error) if error.kind() == ErrorKind::WouldBlock => backoff.spin(),
-                Err(error) => {
-                    self.disconnect();
-                    return Err(Error::Io(error));
-                }
+    if let Ok(address) = listener.local_addr() {
+        println!("{}{}", DEBUGGER_PORT_PREFIX, address.port());
+    }
+    listener
+}
+
+/// Disconnects the clients waiting to be accepted. Used by the protocols that only support a
+/// single client, since only the JSON protocol supports observers.
+pub fn reject_pending_clients(listener: &TcpListener) {
+    while let Ok((client, _)) = listener.accept() {
+        drop(client);
+    }
+}
+
+/// Non-blocking version of write_all.
+pub fn write_all_non_blocking(client: &mut impl Write, mut buffer: &[u8]) -> io::Result<()> {
+    let backoff = Backoff::new();
+    while !buffer.is_empty() {
+        match client.write(buffer) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
             }
+            Ok(n) => buffer = &buffer[n..],
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(ref error) if error.kind() == ErrorKind::WouldBlock => backoff.spin(),
+            Err(error) => return Err(error),
         }
+    }
 
-        Ok(())
+    Ok(())
+}
+
+/// Like `write_all_non_blocking`, but fails instead of waiting until the client can receive
+/// more data. The client cannot be used afterwards, since only a part of the buffer may have
+/// been written.
+fn write_all_without_waiting(client: &mut impl Write, mut buffer: &[u8]) -> io::Result<()> {
+    while !buffer.is_empty() {
+        match client.write(buffer) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ));
+            }
+            Ok(n) => buffer = &buffer[n..],
+            Err(ref error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
     }
+
+    Ok(())
 }
 
 impl fmt::Display for Error {
@@ -198,3 +432,153 @@ impl fmt::Display for Error {
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{io::Read, thread, time::Duration};
+
+    use super::*;
+
+    pub(crate) fn poll_until_event(handler: &mut TcpHandler) -> PollReturn {
+        for _ in 0..100 {
+            match handler.poll() {
+                Ok(PollReturn::Nothing) => thread::sleep(Duration::from_millis(10)),
+                Ok(event) => return event,
+                Err(error) => panic!("polling failed: {}", error),
+            }
+        }
+        panic!("no client event");
+    }
+
+    pub(crate) fn read_response(client: &mut TcpStream) -> String {
+        let mut response = Vec::new();
+        let mut byte = [0];
+        while client.read(&mut byte).unwrap() == 1 && byte[0] != 0 {
+            response.push(byte[0]);
+        }
+        String::from_utf8(response).unwrap()
+    }
+
+    #[test]
+    fn memory_is_transmitted_as_hex() -> serde_json::Result<()> {
+        let response = Response::Memory {
+            address: 0x10,
+            bytes: HexBytes(vec![0x00, 0xAB, 0x7F]),
+        };
+        assert_eq!(
+            serde_json::to_string(&response)?,
+            r#"{"Memory":{"address":16,"bytes":"00ab7f"}}"#
+        );
+
+        let request: Request =
+            serde_json::from_str(r#"{"WriteMemory":{"address":16,"bytes":"00AB7f"}}"#)?;
+        assert!(matches!(
+            request,
+            Request::WriteMemory { address: 16, bytes } if bytes == HexBytes(vec![0x00, 0xAB, 0x7F])
+        ));
+        assert!(
+            serde_json::from_str::<Request>(r#"{"WriteMemory":{"address":0,"bytes":"abc"}}"#)
+                .is_err()
+        );
+        for digits in ["zz", "+f"] {
+            let json = format!(r#"{{"WriteMemory":{{"address":0,"bytes":"{digits}"}}}}"#);
+            assert!(serde_json::from_str::<Request>(&json).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn observers_receive_responses_but_cannot_send_requests() {
+        let mut handler = TcpHandler::start("127.0.0.1:0".parse().unwrap());
+        let address = handler.local_address();
+        let connect = || {
+            let client = TcpStream::connect(address).unwrap();
+            client
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            client
+        };
+
+        let mut controller = connect();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected(_, Role::Controller)
+        ));
+        let mut observer = connect();
+        let PollReturn::ClientConnected(observer_id, Role::Observer) =
+            poll_until_event(&mut handler)
+        else {
+            panic!("the second client is not an observer");
+        };
+
+        observer.write_all(b"{\"Continue\":{}}\0").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        assert!(matches!(handler.poll(), Ok(PollReturn::Nothing)));
+        assert!(read_response(&mut observer).starts_with(r#"{"Error":"#));
+        controller.write_all(b"{\"StepOne\":{}}\0").unwrap();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ReceivedRequests(requests)
+                if matches!(requests.as_slice(), [Request::StepOne {}])
+        ));
+
+        handler.send(&Response::Breaking { location: 8 }).unwrap();
+        handler
+            .send_to(observer_id, &Response::Pausing { location: 16 })
+            .unwrap();
+        assert_eq!(
+            read_response(&mut controller),
+            r#"{"Breaking":{"location":8}}"#
+        );
+        assert_eq!(
+            read_response(&mut observer),
+            r#"{"Breaking":{"location":8}}"#
+        );
+        assert_eq!(
+            read_response(&mut observer),
+            r#"{"Pausing":{"location":16}}"#
+        );
+
+        // the observer does not take over, the next client controls the debugger instead
+        drop(controller);
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientDisconnected
+        ));
+        assert!(!handler.has_controller());
+        let _controller = connect();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected(_, Role::Controller)
+        ));
+    }
+
+    #[test]
+    fn observers_that_do_not_read_are_dropped() {
+        let mut handler = TcpHandler::start("127.0.0.1:0".parse().unwrap());
+        let mut controller = TcpStream::connect(handler.local_address()).unwrap();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected(_, Role::Controller)
+        ));
+        let observer = TcpStream::connect(handler.local_address()).unwrap();
+        assert!(matches!(
+            poll_until_event(&mut handler),
+            PollReturn::ClientConnected(_, Role::Observer)
+        ));
+        let reader = thread::spawn(move || io::copy(&mut controller, &mut io::sink()).unwrap());
+
+        // more than the socket buffers of the observer can hold
+        let response = Response::Memory {
+            address: 0,
+            bytes: HexBytes(vec![0; 1 << 20]),
+        };
+        assert!((0..64).any(|_| handler.send(&response).is_err()));
+        assert!(handler.send(&response).is_ok());
+        assert!(handler.has_controller());
+
+        drop(handler);
+        assert!(reader.join().unwrap() > 0);
+        drop(observer);
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{memory::Memory, mmu::MemoryAccess, processor::DataAccess, Address, Size, Word};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WatchKind {
+    Read,
+    Write,
+    /// Reads and writes.
+    Access,
+}
+
+/// Breaks after an instruction accessed a byte within the (physical) memory range.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Watchpoint {
+    pub address: Address,
+    pub length: Word,
+    pub kind: WatchKind,
+    /// Only break if the accessed value (the written one for writes) is equal to this value.
+    #[serde(default)]
+    pub value: Option<Word>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchpointHit {
+    /// The first accessed address within the watched range.
+    pub address: Address,
+    /// Either `WatchKind::Read` or `WatchKind::Write`.
+    pub kind: WatchKind,
+    pub old_value: Word,
+    pub new_value: Word,
+}
+
+impl Watchpoint {
+    /// Checks whether the access (of an already executed instruction) triggers the
+    /// watchpoint. The values consist of the accessed bytes within the watched range (at most
+    /// the first four of them, big-endian).
+    pub fn check(&self, access: &DataAccess, memory: &Memory) -> Option<WatchpointHit> {
+        let kind = match access.access {
+            MemoryAccess::Read => WatchKind::Read,
+            MemoryAccess::Write => WatchKind::Write,
+            MemoryAccess::Execute => return None,
+        };
+        if self.kind != WatchKind::Access && self.kind != kind {
+            return None;
+        }
+        let watch_start = self.address as usize;
+        let start = access.range.start.max(watch_start);
+        let end = access
+            .range
+            .end
+            .min(watch_start + self.length as usize)
+            .min(start + Word::SIZE);
+        if start >= end {
+            return None;
+        }
+        let offset = start - access.range.start;
+        let old_value = to_word(&access.old_bytes[offset..][..end - start]);
+        let new_value = match kind {
+            WatchKind::Write => to_word(&memory.data()[start..end]),
+            _ => old_value,
+        };
+        if self.value.is_some_and(|value| value != new_value) {
+            return None;
+        }
+        Some(WatchpointHit {
+            address: start as Address,
+            kind,
+            old_value,
+            new_value,
+        })
+    }
+}
+
+fn to_word(bytes: &[u8]) -> Word {
+    bytes
+        .iter()
+        .fold(0, |word, &byte| (word << u8::BITS) | byte as Word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_access(memory: &mut Memory, address: Address, old: Word, new: Word) -> DataAccess {
+        memory.write_data(address, new);
+        DataAccess {
+            range: address as usize..address as usize + Word::SIZE,
+            access: MemoryAccess::Write,
+            old_bytes: old.to_be_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn write_watchpoint_reports_old_and_new_value() {
+        let mut memory = Memory::new();
+        let access = write_access(&mut memory, 0x100, 0x1122_3344, 0x5566_7788);
+        let watchpoint = Watchpoint {
+            address: 0x102,
+            length: 8,
+            kind: WatchKind::Write,
+            value: None,
+        };
+        assert_eq!(
+            watchpoint.check(&access, &memory),
+            Some(WatchpointHit {
+                address: 0x102,
+                kind: WatchKind::Write,
+                old_value: 0x3344,
+                new_value: 0x7788,
+            })
+        );
+    }
+
+    #[test]
+    fn kind_range_and_value_are_checked() {
+        let mut memory = Memory::new();
+        let access = write_access(&mut memory, 0x100, 0, 42);
+        let watchpoint = |address, kind, value| Watchpoint {
+            address,
+            length: 4,
+            kind,
+            value,
+        };
+        assert!(watchpoint(0x100, WatchKind::Read, None)
+            .check(&access, &memory)
+            .is_none());
+        assert!(watchpoint(0x104, WatchKind::Write, None)
+            .check(&access, &memory)
+            .is_none());
+        assert!(watchpoint(0x100, WatchKind::Access, Some(41))
+            .check(&access, &memory)
+            .is_none());
+        assert!(watchpoint(0x100, WatchKind::Access, Some(42))
+            .check(&access, &memory)
+            .is_some());
+    }
+}
@@ -12,6 +12,19 @@ use crate::{address_constants, memory::Memory, Address};
 pub const WIDTH: usize = 480;
 pub const HEIGHT: usize = WIDTH / 4 * 3;
 
+/// The factor by which the framebuffer is scaled when it is drawn into the window.
+#[cfg(feature = "graphics")]
+pub fn scale() -> f32 {
+    SCREEN_SIZE.height as f32 / HEIGHT as f32
+}
+
+/// Converts a position inside the window into display coordinates (clamped to the display).
+#[cfg(feature = "graphics")]
+pub fn window_to_display_position(x: f32, y: f32) -> (crate::Word, crate::Word) {
+    let to_display = |value: f32, size: usize| (value / scale()).clamp(0.0, (size - 1) as f32);
+    (to_display(x, WIDTH) as _, to_display(y, HEIGHT) as _)
+}
+
 pub trait Display {
     type Handle;
     type Thread;
@@ -95,7 +108,6 @@ impl Display for DisplayImplementation {
             b: 0xFF,
             a: 0xFF,
         };
-        let scale = SCREEN_SIZE.height as f32 / HEIGHT as f32;
         let framebuffer_start = match self.is_first_framebuffer_visible() {
             true => address_constants::FIRST_FRAMEBUFFER_START,
             false => address_constants::SECOND_FRAMEBUFFER_START,
@@ -107,7 +119,7 @@ impl Display for DisplayImplementation {
             &self.texture,
             raylib::ffi::Vector2 { x: 0.0, y: 0.0 },
             0.0,
-            scale,
+            scale(),
             tint_color,
         );
     }
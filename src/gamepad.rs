@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{numbering::define_numbering, Word};
+
+/// The maximum number of gamepads that can be connected at the same time.
+pub const NUM_GAMEPADS: usize = 4;
+
+/// Axis values are scaled into the range from `-AXIS_MAX` to `AXIS_MAX`.
+pub const AXIS_MAX: i32 = 32767;
+
+define_numbering!(
+    /// The buttons of a gamepad. Their numbers are the bit positions in the button mask.
+    GamepadButton {
+        DpadUp = 0 => "GAMEPAD_BUTTON_DPAD_UP",
+        DpadRight = 1 => "GAMEPAD_BUTTON_DPAD_RIGHT",
+        DpadDown = 2 => "GAMEPAD_BUTTON_DPAD_DOWN",
+        DpadLeft = 3 => "GAMEPAD_BUTTON_DPAD_LEFT",
+        FaceUp = 4 => "GAMEPAD_BUTTON_FACE_UP",
+        FaceRight = 5 => "GAMEPAD_BUTTON_FACE_RIGHT",
+        FaceDown = 6 => "GAMEPAD_BUTTON_FACE_DOWN",
+        FaceLeft = 7 => "GAMEPAD_BUTTON_FACE_LEFT",
+        LeftShoulder = 8 => "GAMEPAD_BUTTON_LEFT_SHOULDER",
+        LeftTrigger = 9 => "GAMEPAD_BUTTON_LEFT_TRIGGER",
+        RightShoulder = 10 => "GAMEPAD_BUTTON_RIGHT_SHOULDER",
+        RightTrigger = 11 => "GAMEPAD_BUTTON_RIGHT_TRIGGER",
+        Select = 12 => "GAMEPAD_BUTTON_SELECT",
+        Home = 13 => "GAMEPAD_BUTTON_HOME",
+        Start = 14 => "GAMEPAD_BUTTON_START",
+        LeftThumb = 15 => "GAMEPAD_BUTTON_LEFT_THUMB",
+        RightThumb = 16 => "GAMEPAD_BUTTON_RIGHT_THUMB",
+    }
+);
+
+define_numbering!(
+    /// The analog axes of a gamepad.
+    GamepadAxis {
+        LeftX = 0 => "GAMEPAD_AXIS_LEFT_X",
+        LeftY = 1 => "GAMEPAD_AXIS_LEFT_Y",
+        RightX = 2 => "GAMEPAD_AXIS_RIGHT_X",
+        RightY = 3 => "GAMEPAD_AXIS_RIGHT_Y",
+        LeftTrigger = 4 => "GAMEPAD_AXIS_LEFT_TRIGGER",
+        RightTrigger = 5 => "GAMEPAD_AXIS_RIGHT_TRIGGER",
+    }
+);
+
+/// The state of a connected gamepad. Axis values range from -1.0 to 1.0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadState {
+    pub buttons: Word,
+    pub axes: HashMap<GamepadAxis, f32>,
+}
+
+impl GamepadState {
+    pub fn set_pressed(&mut self, button: GamepadButton, pressed: bool) {
+        match pressed {
+            true => self.buttons |= 1 << button as Word,
+            false => self.buttons &= !(1 << button as Word),
+        }
+    }
+
+    /// Returns the axis value scaled into the range from `-AXIS_MAX` to `AXIS_MAX`.
+    pub fn scaled_axis(&self, axis: GamepadAxis) -> i32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0.0);
+        (value.clamp(-1.0, 1.0) * AXIS_MAX as f32).round() as i32
+    }
+}
+
+pub struct Gamepads {
+    get_state_callback: Box<dyn FnMut(Word, u64) -> Option<GamepadState>>,
+}
+
+impl Gamepads {
+    /// The callback returns the state of the gamepad with the given number at the given cycle
+    /// count or `None` if it is not connected.
+    pub fn new(get_state_callback: Box<dyn FnMut(Word, u64) -> Option<GamepadState>>) -> Self {
+        Self { get_state_callback }
+    }
+
+    /// The cycle count is only used by scripted gamepads.
+    pub fn get_state(&mut self, gamepad: Word, cycle_count: u64) -> Option<GamepadState> {
+        match (gamepad as usize) < NUM_GAMEPADS {
+            true => (self.get_state_callback)(gamepad, cycle_count),
+            false => None,
+        }
+    }
+}
+
+/// Reads the state of the gamepad with the given number from raylib.
+#[cfg(feature = "graphics")]
+pub fn get_raylib_gamepad_state(
+    handle: &raylib::RaylibHandle,
+    gamepad: Word,
+) -> Option<GamepadState> {
+    use raylib::consts::{GamepadAxis as RaylibAxis, GamepadButton as RaylibButton};
+
+    let gamepad = gamepad as i32;
+    if !handle.is_gamepad_available(gamepad) {
+        return None;
+    }
+    let buttons = [
+        (
+            GamepadButton::DpadUp,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_FACE_UP,
+        ),
+        (
+            GamepadButton::DpadRight,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT,
+        ),
+        (
+            GamepadButton::DpadDown,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN,
+        ),
+        (
+            GamepadButton::DpadLeft,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT,
+        ),
+        (
+            GamepadButton::FaceUp,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_FACE_UP,
+        ),
+        (
+            GamepadButton::FaceRight,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT,
+        ),
+        (
+            GamepadButton::FaceDown,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN,
+        ),
+        (
+            GamepadButton::FaceLeft,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT,
+        ),
+        (
+            GamepadButton::LeftShoulder,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1,
+        ),
+        (
+            GamepadButton::LeftTrigger,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2,
+        ),
+        (
+            GamepadButton::RightShoulder,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1,
+        ),
+        (
+            GamepadButton::RightTrigger,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2,
+        ),
+        (
+            GamepadButton::Select,
+            RaylibButton::GAMEPAD_BUTTON_MIDDLE_LEFT,
+        ),
+        (GamepadButton::Home, RaylibButton::GAMEPAD_BUTTON_MIDDLE),
+        (
+            GamepadButton::Start,
+            RaylibButton::GAMEPAD_BUTTON_MIDDLE_RIGHT,
+        ),
+        (
+            GamepadButton::LeftThumb,
+            RaylibButton::GAMEPAD_BUTTON_LEFT_THUMB,
+        ),
+        (
+            GamepadButton::RightThumb,
+            RaylibButton::GAMEPAD_BUTTON_RIGHT_THUMB,
+        ),
+    ];
+    let axes = [
+        (GamepadAxis::LeftX, RaylibAxis::GAMEPAD_AXIS_LEFT_X),
+        (GamepadAxis::LeftY, RaylibAxis::GAMEPAD_AXIS_LEFT_Y),
+        (GamepadAxis::RightX, RaylibAxis::GAMEPAD_AXIS_RIGHT_X),
+        (GamepadAxis::RightY, RaylibAxis::GAMEPAD_AXIS_RIGHT_Y),
+        (
+            GamepadAxis::LeftTrigger,
+            RaylibAxis::GAMEPAD_AXIS_LEFT_TRIGGER,
+        ),
+        (
+            GamepadAxis::RightTrigger,
+            RaylibAxis::GAMEPAD_AXIS_RIGHT_TRIGGER,
+        ),
+    ];
+    let mut state = GamepadState::default();
+    for (button, raylib_button) in buttons {
+        state.set_pressed(
+            button,
+            handle.is_gamepad_button_down(gamepad, raylib_button),
+        );
+    }
+    for (axis, raylib_axis) in axes {
+        state
+            .axes
+            .insert(axis, handle.get_gamepad_axis_movement(gamepad, raylib_axis));
+    }
+    Some(state)
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadAction {
+    Press(GamepadButton),
+    Release(GamepadButton),
+    Axis { axis: GamepadAxis, value: f32 },
+}
+
+/// An input of a gamepad script that happens the given number of milliseconds after the start.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct GamepadEvent {
+    pub time_ms: u64,
+    pub gamepad: Word,
+    #[serde(flatten)]
+    pub action: GamepadAction,
+}
+
+/// Replays gamepad inputs from a list of events (e.g. for headless runs). Every gamepad that
+/// occurs in the script is connected from the start. The time of the script advances with the
+/// executed cycles (like the virtual time of the RTC), so that replays are deterministic.
+///
+/// Example: `[{ "time_ms": 500, "gamepad": 0, "press": "start" },
+/// { "time_ms": 800, "gamepad": 0, "axis": { "axis": "left_x", "value": -1.0 } }]`
+pub struct GamepadScript {
+    events: Vec<GamepadEvent>,
+    cycles_per_second: u64,
+}
+
+impl GamepadScript {
+    /// The clock frequency that the script is timed with if the machine is not clocked.
+    pub const DEFAULT_CYCLES_PER_SECOND: u64 = 1_000_000;
+
+    pub fn new(mut events: Vec<GamepadEvent>, cycles_per_second: u64) -> Self {
+        events.sort_by_key(|event| event.time_ms);
+        Self {
+            events,
+            cycles_per_second,
+        }
+    }
+
+    pub fn from_json(json: &str, cycles_per_second: u64) -> serde_json::Result<Self> {
+        Ok(Self::new(serde_json::from_str(json)?, cycles_per_second))
+    }
+
+    /// Returns the state of the gamepad after applying all events up to the time that
+    /// corresponds to the given cycle count.
+    pub fn state_at(&self, gamepad: Word, cycle_count: u64) -> Option<GamepadState> {
+        let elapsed_ms = cycle_count as u128 * 1000 / self.cycles_per_second.max(1) as u128;
+        let mut events = self
+            .events
+            .iter()
+            .filter(|event| event.gamepad == gamepad)
+            .peekable();
+        events.peek()?;
+        let mut state = GamepadState::default();
+        for event in events.take_while(|event| event.time_ms as u128 <= elapsed_ms) {
+            match event.action {
+                GamepadAction::Press(button) => state.set_pressed(button, true),
+                GamepadAction::Release(button) => state.set_pressed(button, false),
+                GamepadAction::Axis { axis, value } => {
+                    state.axes.insert(axis, value);
+                }
+            }
+        }
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_is_replayed_in_order() {
+        let script = GamepadScript::from_json(
+            r#"[
+                { "time_ms": 200, "gamepad": 1, "release": "face_down" },
+                { "time_ms": 100, "gamepad": 1, "press": "face_down" },
+                { "time_ms": 100, "gamepad": 1, "axis": { "axis": "left_x", "value": -0.5 } }
+            ]"#,
+            2000,
+        )
+        .unwrap();
+        assert_eq!(script.state_at(0, 300), None);
+        assert_eq!(script.state_at(1, 100), Some(GamepadState::default()));
+        let state = script.state_at(1, 300).unwrap();
+        assert_eq!(state.buttons, 1 << GamepadButton::FaceDown as Word);
+        assert_eq!(state.scaled_axis(GamepadAxis::LeftX), -16384);
+        assert_eq!(script.state_at(1, 399).unwrap().buttons, state.buttons);
+        assert_eq!(script.state_at(1, 400).unwrap().buttons, 0);
+    }
+
+    #[test]
+    fn numbering_is_stable() {
+        for (index, &button) in GamepadButton::ALL.iter().enumerate() {
+            assert_eq!(button as usize, index);
+            assert_eq!(GamepadButton::try_from(index as Word), Ok(button));
+        }
+        for (index, &axis) in GamepadAxis::ALL.iter().enumerate() {
+            assert_eq!(axis as usize, index);
+        }
+    }
+}
@@ -1,22 +1,268 @@
-use crate::Word;
+use crate::{numbering::define_numbering, Word};
 
 pub enum KeyState {
     Down,
     Up,
 }
 
+define_numbering!(
+    /// The keys of the keyboard. The numbers are part of the ABI of the machine and do not
+    /// depend on the backend.
+    Key {
+        Space = 32 => "KEY_SPACE",
+        Apostrophe = 39 => "KEY_APOSTROPHE",
+        Comma = 44 => "KEY_COMMA",
+        Minus = 45 => "KEY_MINUS",
+        Period = 46 => "KEY_PERIOD",
+        Slash = 47 => "KEY_SLASH",
+        Digit0 = 48 => "KEY_DIGIT_0",
+        Digit1 = 49 => "KEY_DIGIT_1",
+        Digit2 = 50 => "KEY_DIGIT_2",
+        Digit3 = 51 => "KEY_DIGIT_3",
+        Digit4 = 52 => "KEY_DIGIT_4",
+        Digit5 = 53 => "KEY_DIGIT_5",
+        Digit6 = 54 => "KEY_DIGIT_6",
+        Digit7 = 55 => "KEY_DIGIT_7",
+        Digit8 = 56 => "KEY_DIGIT_8",
+        Digit9 = 57 => "KEY_DIGIT_9",
+        Semicolon = 59 => "KEY_SEMICOLON",
+        Equal = 61 => "KEY_EQUAL",
+        A = 65 => "KEY_A",
+        B = 66 => "KEY_B",
+        C = 67 => "KEY_C",
+        D = 68 => "KEY_D",
+        E = 69 => "KEY_E",
+        F = 70 => "KEY_F",
+        G = 71 => "KEY_G",
+        H = 72 => "KEY_H",
+        I = 73 => "KEY_I",
+        J = 74 => "KEY_J",
+        K = 75 => "KEY_K",
+        L = 76 => "KEY_L",
+        M = 77 => "KEY_M",
+        N = 78 => "KEY_N",
+        O = 79 => "KEY_O",
+        P = 80 => "KEY_P",
+        Q = 81 => "KEY_Q",
+        R = 82 => "KEY_R",
+        S = 83 => "KEY_S",
+        T = 84 => "KEY_T",
+        U = 85 => "KEY_U",
+        V = 86 => "KEY_V",
+        W = 87 => "KEY_W",
+        X = 88 => "KEY_X",
+        Y = 89 => "KEY_Y",
+        Z = 90 => "KEY_Z",
+        LeftBracket = 91 => "KEY_LEFT_BRACKET",
+        Backslash = 92 => "KEY_BACKSLASH",
+        RightBracket = 93 => "KEY_RIGHT_BRACKET",
+        Grave = 96 => "KEY_GRAVE",
+        Escape = 256 => "KEY_ESCAPE",
+        Enter = 257 => "KEY_ENTER",
+        Tab = 258 => "KEY_TAB",
+        Backspace = 259 => "KEY_BACKSPACE",
+        Insert = 260 => "KEY_INSERT",
+        Delete = 261 => "KEY_DELETE",
+        Right = 262 => "KEY_RIGHT",
+        Left = 263 => "KEY_LEFT",
+        Down = 264 => "KEY_DOWN",
+        Up = 265 => "KEY_UP",
+        PageUp = 266 => "KEY_PAGE_UP",
+        PageDown = 267 => "KEY_PAGE_DOWN",
+        Home = 268 => "KEY_HOME",
+        End = 269 => "KEY_END",
+        CapsLock = 280 => "KEY_CAPS_LOCK",
+        ScrollLock = 281 => "KEY_SCROLL_LOCK",
+        NumLock = 282 => "KEY_NUM_LOCK",
+        PrintScreen = 283 => "KEY_PRINT_SCREEN",
+        Pause = 284 => "KEY_PAUSE",
+        F1 = 290 => "KEY_F1",
+        F2 = 291 => "KEY_F2",
+        F3 = 292 => "KEY_F3",
+        F4 = 293 => "KEY_F4",
+        F5 = 294 => "KEY_F5",
+        F6 = 295 => "KEY_F6",
+        F7 = 296 => "KEY_F7",
+        F8 = 297 => "KEY_F8",
+        F9 = 298 => "KEY_F9",
+        F10 = 299 => "KEY_F10",
+        F11 = 300 => "KEY_F11",
+        F12 = 301 => "KEY_F12",
+        Keypad0 = 320 => "KEY_KEYPAD_0",
+        Keypad1 = 321 => "KEY_KEYPAD_1",
+        Keypad2 = 322 => "KEY_KEYPAD_2",
+        Keypad3 = 323 => "KEY_KEYPAD_3",
+        Keypad4 = 324 => "KEY_KEYPAD_4",
+        Keypad5 = 325 => "KEY_KEYPAD_5",
+        Keypad6 = 326 => "KEY_KEYPAD_6",
+        Keypad7 = 327 => "KEY_KEYPAD_7",
+        Keypad8 = 328 => "KEY_KEYPAD_8",
+        Keypad9 = 329 => "KEY_KEYPAD_9",
+        KeypadDecimal = 330 => "KEY_KEYPAD_DECIMAL",
+        KeypadDivide = 331 => "KEY_KEYPAD_DIVIDE",
+        KeypadMultiply = 332 => "KEY_KEYPAD_MULTIPLY",
+        KeypadSubtract = 333 => "KEY_KEYPAD_SUBTRACT",
+        KeypadAdd = 334 => "KEY_KEYPAD_ADD",
+        KeypadEnter = 335 => "KEY_KEYPAD_ENTER",
+        KeypadEqual = 336 => "KEY_KEYPAD_EQUAL",
+        LeftShift = 340 => "KEY_LEFT_SHIFT",
+        LeftControl = 341 => "KEY_LEFT_CONTROL",
+        LeftAlt = 342 => "KEY_LEFT_ALT",
+        LeftSuper = 343 => "KEY_LEFT_SUPER",
+        RightShift = 344 => "KEY_RIGHT_SHIFT",
+        RightControl = 345 => "KEY_RIGHT_CONTROL",
+        RightAlt = 346 => "KEY_RIGHT_ALT",
+        RightSuper = 347 => "KEY_RIGHT_SUPER",
+        Menu = 348 => "KEY_MENU",
+    }
+);
+
+/// Returns the raylib key that corresponds to the given key.
+#[cfg(feature = "graphics")]
+pub fn get_raylib_key(key: Key) -> raylib::consts::KeyboardKey {
+    use raylib::consts::KeyboardKey;
+
+    match key {
+        Key::Space => KeyboardKey::KEY_SPACE,
+        Key::Apostrophe => KeyboardKey::KEY_APOSTROPHE,
+        Key::Comma => KeyboardKey::KEY_COMMA,
+        Key::Minus => KeyboardKey::KEY_MINUS,
+        Key::Period => KeyboardKey::KEY_PERIOD,
+        Key::Slash => KeyboardKey::KEY_SLASH,
+        Key::Digit0 => KeyboardKey::KEY_ZERO,
+        Key::Digit1 => KeyboardKey::KEY_ONE,
+        Key::Digit2 => KeyboardKey::KEY_TWO,
+        Key::Digit3 => KeyboardKey::KEY_THREE,
+        Key::Digit4 => KeyboardKey::KEY_FOUR,
+        Key::Digit5 => KeyboardKey::KEY_FIVE,
+        Key::Digit6 => KeyboardKey::KEY_SIX,
+        Key::Digit7 => KeyboardKey::KEY_SEVEN,
+        Key::Digit8 => KeyboardKey::KEY_EIGHT,
+        Key::Digit9 => KeyboardKey::KEY_NINE,
+        Key::Semicolon => KeyboardKey::KEY_SEMICOLON,
+        Key::Equal => KeyboardKey::KEY_EQUAL,
+        Key::A => KeyboardKey::KEY_A,
+        Key::B => KeyboardKey::KEY_B,
+        Key::C => KeyboardKey::KEY_C,
+        Key::D => KeyboardKey::KEY_D,
+        Key::E => KeyboardKey::KEY_E,
+        Key::F => KeyboardKey::KEY_F,
+        Key::G => KeyboardKey::KEY_G,
+        Key::H => KeyboardKey::KEY_H,
+        Key::I => KeyboardKey::KEY_I,
+        Key::J => KeyboardKey::KEY_J,
+        Key::K => KeyboardKey::KEY_K,
+        Key::L => KeyboardKey::KEY_L,
+        Key::M => KeyboardKey::KEY_M,
+        Key::N => KeyboardKey::KEY_N,
+        Key::O => KeyboardKey::KEY_O,
+        Key::P => KeyboardKey::KEY_P,
+        Key::Q => KeyboardKey::KEY_Q,
+        Key::R => KeyboardKey::KEY_R,
+        Key::S => KeyboardKey::KEY_S,
+        Key::T => KeyboardKey::KEY_T,
+        Key::U => KeyboardKey::KEY_U,
+        Key::V => KeyboardKey::KEY_V,
+        Key::W => KeyboardKey::KEY_W,
+        Key::X => KeyboardKey::KEY_X,
+        Key::Y => KeyboardKey::KEY_Y,
+        Key::Z => KeyboardKey::KEY_Z,
+        Key::LeftBracket => KeyboardKey::KEY_LEFT_BRACKET,
+        Key::Backslash => KeyboardKey::KEY_BACKSLASH,
+        Key::RightBracket => KeyboardKey::KEY_RIGHT_BRACKET,
+        Key::Grave => KeyboardKey::KEY_GRAVE,
+        Key::Escape => KeyboardKey::KEY_ESCAPE,
+        Key::Enter => KeyboardKey::KEY_ENTER,
+        Key::Tab => KeyboardKey::KEY_TAB,
+        Key::Backspace => KeyboardKey::KEY_BACKSPACE,
+        Key::Insert => KeyboardKey::KEY_INSERT,
+        Key::Delete => KeyboardKey::KEY_DELETE,
+        Key::Right => KeyboardKey::KEY_RIGHT,
+        Key::Left => KeyboardKey::KEY_LEFT,
+        Key::Down => KeyboardKey::KEY_DOWN,
+        Key::Up => KeyboardKey::KEY_UP,
+        Key::PageUp => KeyboardKey::KEY_PAGE_UP,
+        Key::PageDown => KeyboardKey::KEY_PAGE_DOWN,
+        Key::Home => KeyboardKey::KEY_HOME,
+        Key::End => KeyboardKey::KEY_END,
+        Key::CapsLock => KeyboardKey::KEY_CAPS_LOCK,
+        Key::ScrollLock => KeyboardKey::KEY_SCROLL_LOCK,
+        Key::NumLock => KeyboardKey::KEY_NUM_LOCK,
+        Key::PrintScreen => KeyboardKey::KEY_PRINT_SCREEN,
+        Key::Pause => KeyboardKey::KEY_PAUSE,
+        Key::F1 => KeyboardKey::KEY_F1,
+        Key::F2 => KeyboardKey::KEY_F2,
+        Key::F3 => KeyboardKey::KEY_F3,
+        Key::F4 => KeyboardKey::KEY_F4,
+        Key::F5 => KeyboardKey::KEY_F5,
+        Key::F6 => KeyboardKey::KEY_F6,
+        Key::F7 => KeyboardKey::KEY_F7,
+        Key::F8 => KeyboardKey::KEY_F8,
+        Key::F9 => KeyboardKey::KEY_F9,
+        Key::F10 => KeyboardKey::KEY_F10,
+        Key::F11 => KeyboardKey::KEY_F11,
+        Key::F12 => KeyboardKey::KEY_F12,
+        Key::Keypad0 => KeyboardKey::KEY_KP_0,
+        Key::Keypad1 => KeyboardKey::KEY_KP_1,
+        Key::Keypad2 => KeyboardKey::KEY_KP_2,
+        Key::Keypad3 => KeyboardKey::KEY_KP_3,
+        Key::Keypad4 => KeyboardKey::KEY_KP_4,
+        Key::Keypad5 => KeyboardKey::KEY_KP_5,
+        Key::Keypad6 => KeyboardKey::KEY_KP_6,
+        Key::Keypad7 => KeyboardKey::KEY_KP_7,
+        Key::Keypad8 => KeyboardKey::KEY_KP_8,
+        Key::Keypad9 => KeyboardKey::KEY_KP_9,
+        Key::KeypadDecimal => KeyboardKey::KEY_KP_DECIMAL,
+        Key::KeypadDivide => KeyboardKey::KEY_KP_DIVIDE,
+        Key::KeypadMultiply => KeyboardKey::KEY_KP_MULTIPLY,
+        Key::KeypadSubtract => KeyboardKey::KEY_KP_SUBTRACT,
+        Key::KeypadAdd => KeyboardKey::KEY_KP_ADD,
+        Key::KeypadEnter => KeyboardKey::KEY_KP_ENTER,
+        Key::KeypadEqual => KeyboardKey::KEY_KP_EQUAL,
+        Key::LeftShift => KeyboardKey::KEY_LEFT_SHIFT,
+        Key::LeftControl => KeyboardKey::KEY_LEFT_CONTROL,
+        Key::LeftAlt => KeyboardKey::KEY_LEFT_ALT,
+        Key::LeftSuper => KeyboardKey::KEY_LEFT_SUPER,
+        Key::RightShift => KeyboardKey::KEY_RIGHT_SHIFT,
+        Key::RightControl => KeyboardKey::KEY_RIGHT_CONTROL,
+        Key::RightAlt => KeyboardKey::KEY_RIGHT_ALT,
+        Key::RightSuper => KeyboardKey::KEY_RIGHT_SUPER,
+        Key::Menu => KeyboardKey::KEY_KB_MENU,
+    }
+}
+
 pub struct Keyboard {
-    get_keystate_callback: Box<dyn FnMut(Word) -> KeyState>,
+    get_keystate_callback: Box<dyn FnMut(Key) -> KeyState>,
 }
 
 impl Keyboard {
-    pub fn new(get_keystate_callback: Box<dyn FnMut(Word) -> KeyState>) -> Self {
+    pub fn new(get_keystate_callback: Box<dyn FnMut(Key) -> KeyState>) -> Self {
         Keyboard {
             get_keystate_callback,
         }
     }
 
-    pub fn get_keystate(&mut self, key: Word) -> KeyState {
-        (self.get_keystate_callback)(key)
+    /// Unknown keycodes are never held down.
+    pub fn get_keystate(&mut self, keycode: Word) -> KeyState {
+        match Key::try_from(keycode) {
+            Ok(key) => (self.get_keystate_callback)(key),
+            Err(()) => KeyState::Up,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keycodes_are_released() {
+        let mut keyboard = Keyboard::new(Box::new(|_| KeyState::Down));
+        assert!(matches!(
+            keyboard.get_keystate(Key::A as Word),
+            KeyState::Down
+        ));
+        assert!(matches!(keyboard.get_keystate(0), KeyState::Up));
+        assert!(matches!(keyboard.get_keystate(Word::MAX), KeyState::Up));
     }
 }
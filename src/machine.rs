@@ -4,10 +4,11 @@ use crate::{
     address_constants,
     cursor::{Cursor, CursorMode},
     display,
+    machine_profile::MachineProfile,
     memory::Memory,
     periphery::PeripheryImplementation,
     processor::{CachedInstruction, ExecutionResult, InstructionCache, Processor},
-    terminal, Address, Instruction, Size,
+    terminal, Instruction, Size, Word,
 };
 
 #[cfg(feature = "debugger")]
@@ -21,10 +22,20 @@ where
     Display: display::Display,
 {
     pub memory: Memory,
-    pub processor: Processor,
+    /// The cores sharing the memory, the first one is the boot core.
+    pub processors: Vec<Processor>,
     pub periphery: PeripheryImplementation<Display>,
     is_halted: bool,
+    current_core: usize,
+    /// The cycles that elapsed on the machine clock, i.e. the sum of the longest instruction of
+    /// every scheduling round.
+    cycle_count: u64,
+    /// The cycles of the longest instruction in the current scheduling round.
+    round_cycles: u64,
+    /// The cores that waited for a wake-up in the current scheduling round.
+    waiting_cores: Vec<usize>,
     instruction_cache: InstructionCache<PeripheryImplementation<Display>>,
+    profile: MachineProfile,
     #[cfg(feature = "debugger")]
     debug_handle: DebugHandle,
 }
@@ -33,7 +44,12 @@ impl<Display> Machine<Display>
 where
     Display: display::Display + 'static,
 {
-    pub fn new(periphery: PeripheryImplementation<Display>, exit_on_halt: bool) -> Self {
+    pub fn new(
+        periphery: PeripheryImplementation<Display>,
+        exit_on_halt: bool,
+        profile: MachineProfile,
+        num_cores: Word,
+    ) -> Self {
         const MAX_NUM_INSTRUCTIONS: usize = Memory::SIZE / Instruction::SIZE;
         let cache: Vec<_> = (0..MAX_NUM_INSTRUCTIONS)
             .map(|_| {
@@ -52,62 +68,46 @@ where
                 .try_into()
                 .unwrap_or_else(|_| unreachable!()),
         };
+        let processors = (0..num_cores)
+            .map(|core_id| Processor::new(exit_on_halt, core_id, num_cores))
+            .collect();
 
         #[cfg(not(feature = "debugger"))]
         {
             Self {
                 memory: Memory::new(),
-                processor: Processor::new(exit_on_halt),
+                processors,
                 periphery,
                 is_halted: false,
+                current_core: 0,
+                cycle_count: 0,
+                round_cycles: 0,
+                waiting_cores: Vec::new(),
                 instruction_cache,
+                profile,
             }
         }
         #[cfg(feature = "debugger")]
         {
             Self {
                 memory: Memory::new(),
-                processor: Processor::new(exit_on_halt),
+                processors,
                 periphery,
                 is_halted: false,
+                current_core: 0,
+                cycle_count: 0,
+                round_cycles: 0,
+                waiting_cores: Vec::new(),
                 instruction_cache,
+                profile,
                 debug_handle: DebugHandle::dummy(),
             }
         }
     }
 
     pub fn generate_instruction_cache(&mut self) {
-        const MAX_NUM_INSTRUCTIONS: usize = Memory::SIZE / Instruction::SIZE;
-        let cache: Vec<CachedInstruction<PeripheryImplementation<Display>>> = (0
-            ..MAX_NUM_INSTRUCTIONS)
-            .map(|i| {
-                let address = (i * Instruction::SIZE) as Address;
-                match address >= address_constants::ENTRY_POINT {
-                    true => match self.memory.read_opcode(address) {
-                        Ok(opcode) => Processor::generate_cached_instruction(opcode),
-                        Err(_) => Box::new(
-                            |_: &mut Processor,
-                             _: &mut Memory,
-                             _: &mut PeripheryImplementation<Display>| {
-                                ExecutionResult::Error
-                            },
-                        ),
-                    },
-                    false => Box::new(
-                        |_: &mut Processor,
-                         _: &mut Memory,
-                         _: &mut PeripheryImplementation<Display>| {
-                            ExecutionResult::Error
-                        },
-                    ),
-                }
-            })
-            .collect();
-
-        self.instruction_cache.cache = cache
-            .into_boxed_slice()
-            .try_into()
-            .unwrap_or_else(|_| unreachable!());
+        self.instruction_cache
+            .regenerate(&self.memory, 0..Memory::SIZE, self.profile);
     }
 
     fn update_cursor(&mut self) {
@@ -143,30 +143,89 @@ where
         );
     }
 
+    /// Executes the next instruction of the current core and then switches to the next core
+    /// (round-robin), so that the interleaving of the cores is deterministic. Every core executes
+    /// one instruction per round and the round takes as long as its longest instruction.
     pub fn execute_next_instruction(&mut self) {
+        let core = self.current_core;
+        self.current_core = (core + 1) % self.processors.len();
+        if self.processors[core].is_waiting_for_wake_up() {
+            self.waiting_cores.push(core);
+        } else {
+            self.execute_on_core(core);
+        }
+        if self.current_core == 0 {
+            self.finish_round();
+        }
+    }
+
+    fn execute_on_core(&mut self, core: usize) {
         use crate::processor::ExecutionResult::*;
 
+        let processor = &mut self.processors[core];
+        let cycles_before = processor.get_cycle_count();
+
         #[cfg(feature = "debugger")]
         {
             let result = self
                 .debug_handle
-                .before_instruction_execution(&mut self.processor, &mut self.memory);
+                .before_instruction_execution(processor, &mut self.memory);
+            // memory writes of the debugger may have modified code
+            for range in self.debug_handle.take_modified_memory() {
+                self.instruction_cache
+                    .regenerate(&self.memory, range, self.profile);
+            }
             if let ShouldExecuteInstruction::No = result {
                 return;
             }
         }
 
-        match self.processor.execute_next_instruction(
+        let result = processor.execute_next_instruction(
             &mut self.memory,
             &mut self.periphery,
             &mut self.instruction_cache,
-        ) {
+        );
+
+        #[cfg(feature = "debugger")]
+        self.debug_handle
+            .after_instruction_execution(processor, &self.memory);
+
+        let consumed_cycles = processor.get_cycle_count().saturating_sub(cycles_before);
+        if consumed_cycles > self.round_cycles {
+            self.cycle_count += consumed_cycles - self.round_cycles;
+            self.round_cycles = consumed_cycles;
+        }
+
+        match result {
             Error => panic!(),
             Normal => {}
             Halted => {
                 self.is_halted = true;
             }
+            WakeUpCore(core_id) => {
+                if let Some(processor) = self.processors.get_mut(core_id as usize) {
+                    processor.wake_up();
+                }
+            }
+        }
+    }
+
+    /// Charges the waiting cores with the duration of the round, so that they stay in step with
+    /// the running cores. A round in which no instruction was executed still takes one cycle.
+    fn finish_round(&mut self) {
+        if self.round_cycles == 0 && !self.waiting_cores.is_empty() {
+            self.round_cycles = 1;
+            self.cycle_count += 1;
+        }
+        for core in self.waiting_cores.drain(..) {
+            self.processors[core].increase_cycle_count(self.round_cycles);
         }
+        self.round_cycles = 0;
+    }
+
+    /// Returns the number of cycles that elapsed on the machine clock shared by all cores.
+    pub fn get_cycle_count(&self) -> u64 {
+        self.cycle_count
     }
 
     #[must_use = "Am I a joke to you?"]
@@ -175,8 +234,13 @@ where
     }
 
     #[cfg(feature = "debugger")]
-    pub fn start_debugger(&mut self) {
-        self.debug_handle = crate::debugger::start_debugger();
+    pub fn start_debugger(&mut self, options: crate::debugger::DebuggerOptions) {
+        self.attach_debug_handle(crate::debugger::start_debugger(options));
+    }
+
+    #[cfg(feature = "debugger")]
+    pub fn attach_debug_handle(&mut self, debug_handle: DebugHandle) {
+        self.debug_handle = debug_handle;
     }
 
     #[cfg(feature = "debugger")]
@@ -186,19 +250,27 @@ where
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::time::Instant;
 
     use crate::cursor::Cursor;
     use crate::display::MockDisplay;
-    use crate::keyboard::{KeyState, Keyboard};
+    use crate::gamepad::{GamepadAxis, GamepadButton, GamepadState, Gamepads};
+    use crate::keyboard::{Key, KeyState, Keyboard};
+    use crate::machine_profile::CostClass;
+    use crate::mmu::{PageFlags, PAGE_SIZE};
+    use crate::mouse::{Mouse, MouseButtons, MouseState};
     use crate::processor::Flag;
+    use crate::random::Random;
+    use crate::rtc::{Rtc, TimeSource};
     use crate::timer::Timer;
+    use crate::trap::TrapCause;
     use crate::{address_constants, Address, Instruction, Size, Word};
     use crate::{
         opcodes::Opcode::{self, *},
         Register,
     };
+    use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
 
     use super::*;
 
@@ -232,12 +304,12 @@ mod tests {
                 let mut machine = opcodes_to_machine!($( $opcodes )?);
                 $(
                     $(
-                        machine.processor.registers[$register_pre.into()] = $register_pre_value;
+                        machine.processors[0].registers[$register_pre.into()] = $register_pre_value;
                     )+
                 )?
                 $(
                     $(
-                        machine.processor.set_flag(Flag::$flag_pre, $flag_pre_value);
+                        machine.processors[0].set_flag(Flag::$flag_pre, $flag_pre_value);
                     )+
                 )?
                 $(
@@ -252,7 +324,7 @@ mod tests {
                 )?
                 $(
                     $(
-                        assert_eq!(machine.processor.registers[$register_post], $register_post_value);
+                        assert_eq!(machine.processors[0].registers[$register_post], $register_post_value);
                     )+
                 )?
                 $(
@@ -262,7 +334,7 @@ mod tests {
                 )?
                 $(
                     $(
-                        assert_eq!(machine.processor.get_flag(Flag::$flag_post), $flag_post_value);
+                        assert_eq!(machine.processors[0].get_flag(Flag::$flag_post), $flag_post_value);
                     )+
                 )?
                 $(
@@ -274,8 +346,15 @@ mod tests {
         };
     }
 
-    fn create_machine_with_opcodes(opcodes: &[Opcode]) -> Machine<MockDisplay> {
-        let mut machine = Machine::new(create_mock_periphery(), false);
+    pub(crate) fn create_machine_with_opcodes(opcodes: &[Opcode]) -> Machine<MockDisplay> {
+        create_machine_with_opcodes_and_profile(opcodes, MachineProfile::Uniform)
+    }
+
+    fn create_machine_with_opcodes_and_profile(
+        opcodes: &[Opcode],
+        profile: MachineProfile,
+    ) -> Machine<MockDisplay> {
+        let mut machine = Machine::new(create_mock_periphery(), false, profile, 1);
         for (&opcode, address) in opcodes
             .iter()
             .zip((address_constants::ENTRY_POINT..).step_by(Instruction::SIZE))
@@ -290,16 +369,16 @@ mod tests {
         mut machine: Machine<MockDisplay>,
         opcode: Opcode,
     ) -> Machine<MockDisplay> {
-        let instruction_pointer = machine.processor.registers[Processor::INSTRUCTION_POINTER];
+        let instruction_pointer = machine.processors[0].registers[Processor::INSTRUCTION_POINTER];
         machine.memory.write_opcode(instruction_pointer, opcode);
         machine.generate_instruction_cache();
-        machine.processor.execute_next_instruction(
+        machine.processors[0].execute_next_instruction(
             &mut machine.memory,
             &mut machine.periphery,
             &mut machine.instruction_cache,
         );
         assert_eq!(
-            machine.processor.registers[Processor::INSTRUCTION_POINTER],
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
             instruction_pointer + Instruction::SIZE as u32
         );
         machine
@@ -314,14 +393,42 @@ mod tests {
                 old_value
             }),
             keyboard: Keyboard::new(Box::new(|_| KeyState::Up)),
+            mouse: Mouse::new(Box::new(|| MouseState {
+                x: 120,
+                y: 340,
+                buttons: MouseButtons::LEFT | MouseButtons::MIDDLE,
+            })),
+            gamepads: Gamepads::new(Box::new(|gamepad, _| {
+                (gamepad == 0).then(|| {
+                    let mut state = GamepadState::default();
+                    state.set_pressed(GamepadButton::FaceDown, true);
+                    state.set_pressed(GamepadButton::Start, true);
+                    state.axes.insert(GamepadAxis::LeftX, -1.0);
+                    state.axes.insert(GamepadAxis::RightTrigger, 0.5);
+                    state
+                })
+            })),
             display: MockDisplay::new(&mut (), &()),
             cursor: Cursor {
                 visible: false,
                 time_of_next_toggle: Instant::now() + Cursor::TOGGLE_INTERVAL,
             },
+            random: Random::new(RANDOM_SEED),
+            rtc: Rtc::new(
+                TimeSource::Fixed(
+                    Utc.from_utc_datetime(
+                        &NaiveDate::from_ymd_opt(2024, 2, 29)
+                            .and_then(|date| date.and_hms_opt(23, 30, 15))
+                            .unwrap(),
+                    ),
+                ),
+                FixedOffset::east_opt(3600).unwrap(),
+            ),
         }
     }
 
+    const RANDOM_SEED: u64 = 42;
+
     create_test!(
         make_tick_increases_instruction_pointer,
         opcodes = &[Opcode::MoveRegisterImmediate {
@@ -385,14 +492,14 @@ mod tests {
 
     #[test]
     fn move_from_one_register_to_another() {
-        let mut machine = Machine::new(create_mock_periphery(), false);
+        let mut machine = Machine::new(create_mock_periphery(), false, MachineProfile::Uniform, 1);
         let source = 0x5.into();
         let target = 0x0A.into();
         let data = 0xCAFE;
-        machine.processor.registers[source] = data;
+        machine.processors[0].registers[source] = data;
         let machine =
             execute_instruction_with_machine(machine, MoveTargetSource { target, source });
-        assert_eq!(machine.processor.registers[target], data);
+        assert_eq!(machine.processors[0].registers[target], data);
     }
 
     create_test!(
@@ -768,6 +875,54 @@ mod tests {
         carry = true
     );
 
+    create_test!(
+        add_two_positive_values_with_signed_overflow,
+        opcodes = &[AddTargetLhsRhs {
+            target: 2.into(),
+            lhs: 0.into(),
+            rhs: 1.into(),
+        }],
+        registers_pre = [i32::MAX as Word => 0, 1 => 1],
+        registers_post = [(2.into(), i32::MIN as Word)],
+        flags_post = [(Zero, false), (Carry, false), (Overflow, true)],
+    );
+
+    create_test!(
+        add_negative_and_positive_value_without_signed_overflow,
+        opcodes = &[AddTargetLhsRhs {
+            target: 2.into(),
+            lhs: 0.into(),
+            rhs: 1.into(),
+        }],
+        registers_pre = [-1i32 as Word => 0, 1 => 1],
+        registers_post = [(2.into(), 0)],
+        flags_post = [(Zero, true), (Carry, true), (Overflow, false)],
+    );
+
+    create_test!(
+        subtract_positive_value_from_negative_value_with_signed_overflow,
+        opcodes = &[SubtractTargetLhsRhs {
+            target: 2.into(),
+            lhs: 0.into(),
+            rhs: 1.into(),
+        }],
+        registers_pre = [i32::MIN as Word => 0, 1 => 1],
+        registers_post = [(2.into(), i32::MAX as Word)],
+        flags_post = [(Carry, false), (Overflow, true)],
+    );
+
+    create_test!(
+        add_immediate_with_signed_overflow,
+        opcodes = &[AddTargetSourceImmediate {
+            target: 2.into(),
+            source: 0.into(),
+            immediate: 1,
+        }],
+        registers_pre = [i32::MAX as Word => 0],
+        registers_post = [(2.into(), i32::MIN as Word)],
+        flags_post = [(Carry, false), (Overflow, true)],
+    );
+
     create_test!(
         add_two_values_with_carry_with_no_flags_set,
         opcodes = &[Opcode::AddWithCarryTargetLhsRhs {
@@ -1137,365 +1292,482 @@ mod tests {
         zero = true
     );
 
-    macro_rules! create_bitwise_test{
+    macro_rules! create_signed_divmod_test{
         (
             $test_name:ident,
-            $bitwise_instruction:ident,
             $lhs:expr,
             $rhs:expr,
-            $expected:expr,
-            zero = $zero:literal
+            $quotient:expr,
+            $remainder:expr,
+            divide_by_zero = $divide_by_zero:literal,
+            zero = $zero:literal,
+            overflow = $overflow:literal
         ) => {
             create_test!(
                 $test_name,
                 setup = {
                     let lhs_register = 0x42.into();
                     let rhs_register = 0x43.into();
-                    let target_register = 0x0A.into();
-                    let lhs: Word = $lhs;
-                    let rhs = $rhs;
-                    let expected = $expected;
+                    let target_quotient = 0x09.into();
+                    let target_remainder = 0x0A.into();
+                    let lhs: i32 = $lhs;
+                    let rhs: i32 = $rhs;
+                    let expected_quotient: i32 = $quotient;
+                    let expected_remainder: i32 = $remainder;
                 },
-                opcodes = &[$bitwise_instruction {
-                    target: target_register,
+                opcodes = &[DivmodSignedTargetModLhsRhs {
+                    result: target_quotient,
+                    remainder: target_remainder,
                     lhs: lhs_register,
                     rhs: rhs_register,
                 }],
-                registers_pre = [lhs => lhs_register, rhs => rhs_register],
-                registers_post = [(lhs_register, lhs), (rhs_register, rhs), (target_register, expected)],
-                flags_post = [(Zero, $zero)],
+                registers_pre = [lhs as Word => lhs_register, rhs as Word => rhs_register],
+                registers_post = [
+                    (target_quotient, expected_quotient as Word),
+                    (target_remainder, expected_remainder as Word)],
+                flags_post = [(DivideByZero, $divide_by_zero), (Zero, $zero), (Overflow, $overflow)],
             );
         }
     }
 
-    create_bitwise_test!(
-        bitwise_and_two_values_with_no_flags_set,
-        AndTargetLhsRhs,
-        0b0110_1110_1001_1010_0110_1110_1001_1010,
-        0b1011_1010_0101_1001_1011_1010_0101_1001,
-        0b0010_1010_0001_1000_0010_1010_0001_1000,
-        zero = false
+    create_signed_divmod_test!(
+        signed_divmod_two_positive_values,
+        15,
+        6,
+        2,
+        3,
+        divide_by_zero = false,
+        zero = false,
+        overflow = false
     );
 
-    create_bitwise_test!(
-        bitwise_and_two_values_with_zero_flag_set,
-        AndTargetLhsRhs,
-        0b0100_0100_1000_0110_0100_0100_1000_0010,
-        0b1011_1010_0101_1001_1011_1010_0101_1001,
-        0,
-        zero = true
+    create_signed_divmod_test!(
+        signed_divmod_negative_dividend_rounds_towards_zero,
+        -15,
+        6,
+        -2,
+        -3,
+        divide_by_zero = false,
+        zero = false,
+        overflow = false
     );
 
-    create_bitwise_test!(
-        bitwise_or_two_values_with_no_flags_set,
-        OrTargetLhsRhs,
-        0b0110_1110_1001_1010_0110_1110_1001_1010,
-        0b1011_1010_0101_1001_1011_1010_0101_1001,
-        0b1111_1110_1101_1011_1111_1110_1101_1011,
-        zero = false
+    create_signed_divmod_test!(
+        signed_divmod_negative_divisor_rounds_towards_zero,
+        15,
+        -6,
+        -2,
+        3,
+        divide_by_zero = false,
+        zero = false,
+        overflow = false
     );
 
-    create_bitwise_test!(
-        bitwise_or_two_values_with_zero_flag_set,
-        OrTargetLhsRhs,
-        0,
+    create_signed_divmod_test!(
+        signed_divmod_two_negative_values,
+        -15,
+        -6,
+        2,
+        -3,
+        divide_by_zero = false,
+        zero = false,
+        overflow = false
+    );
+
+    create_signed_divmod_test!(
+        signed_divmod_divide_by_zero,
+        -15,
         0,
         0,
-        zero = true
+        -15,
+        divide_by_zero = true,
+        zero = true,
+        overflow = false
     );
 
-    create_bitwise_test!(
-        bitwise_xor_two_values_with_no_flags_set,
-        XorTargetLhsRhs,
-        0b0110_1110_1001_1010_0110_1110_1001_1010,
-        0b1011_1010_0101_1001_1011_1010_0101_1001,
-        0b1101_0100_1100_0011_1101_0100_1100_0011,
-        zero = false
+    create_signed_divmod_test!(
+        signed_divmod_minimum_by_minus_one_overflows,
+        i32::MIN,
+        -1,
+        i32::MIN,
+        0,
+        divide_by_zero = false,
+        zero = false,
+        overflow = true
     );
 
-    create_bitwise_test!(
-        bitwise_xor_two_values_with_zero_flag_set,
-        XorTargetLhsRhs,
-        0b1011_1010_1001_0010_0100_0100_1001_0010,
-        0b1011_1010_1001_0010_0100_0100_1001_0010,
-        0,
-        zero = true
+    create_test!(
+        signed_multiply_two_negative_values,
+        opcodes = &[MultiplySignedHighLowLhsRhs {
+            high: 0x09.into(),
+            low: 0x0A.into(),
+            lhs: 0x42.into(),
+            rhs: 0x43.into(),
+        }],
+        registers_pre = [-3i32 as Word => 0x42, -4i32 as Word => 0x43],
+        registers_post = [(0x09.into(), 0), (0x0A.into(), 12)],
+        flags_post = [(Zero, false), (Overflow, false)],
     );
 
     create_test!(
-        bitwise_not_value_with_no_flags_set,
-        setup = {
-            let source = 0x5.into();
-            let target = 0x0A.into();
-            let data = 0b0010_1010_0001_1000_0010_1010_0001_1000;
-            let expected = 0b1101_0101_1110_0111_1101_0101_1110_0111;
-        },
-        opcodes = &[NotTargetSource { target, source }],
-        registers_pre = [data => source],
-        registers_post = [(target, expected)],
-        flags_post = [(Zero, false)],
+        signed_multiply_sign_extends_into_high_part,
+        opcodes = &[MultiplySignedHighLowLhsRhs {
+            high: 0x09.into(),
+            low: 0x0A.into(),
+            lhs: 0x42.into(),
+            rhs: 0x43.into(),
+        }],
+        registers_pre = [-3i32 as Word => 0x42, 4 => 0x43],
+        registers_post = [(0x09.into(), Word::MAX), (0x0A.into(), -12i32 as Word)],
+        flags_post = [(Zero, false), (Overflow, false)],
     );
 
     create_test!(
-        bitwise_not_value_with_zero_flag_set,
-        setup = {
-            let source = 0x5.into();
-            let target = 0x0A.into();
-            let data = 0xFFFFFFFF;
-            let expected = 0;
-        },
-        opcodes = &[NotTargetSource { target, source }],
-        registers_pre = [data => source],
-        registers_post = [(target, expected)],
-        flags_post = [(Zero, true)],
+        signed_multiply_with_overflow,
+        opcodes = &[MultiplySignedHighLowLhsRhs {
+            high: 0x09.into(),
+            low: 0x0A.into(),
+            lhs: 0x42.into(),
+            rhs: 0x43.into(),
+        }],
+        registers_pre = [i32::MIN as Word => 0x42, -1i32 as Word => 0x43],
+        registers_post = [(0x09.into(), 0), (0x0A.into(), i32::MIN as Word)],
+        flags_post = [(Zero, false), (Overflow, true)],
     );
 
-    macro_rules! create_shift_test{
+    macro_rules! create_float_test{
         (
             $test_name:ident,
-            $shift_instruction:ident,
+            $float_instruction:ident,
             $lhs:expr,
             $rhs:expr,
             $expected:expr,
             zero = $zero:literal,
-            carry = $carry:literal
+            nan = $nan:literal,
+            infinity = $infinity:literal,
+            inexact = $inexact:literal
         ) => {
             create_test!(
                 $test_name,
-                opcodes = &[$shift_instruction {
+                opcodes = &[$float_instruction {
                     target: 0x0A.into(),
-                    lhs: 0x5.into(),
-                    rhs: 0x6.into(),
+                    lhs: 0x42.into(),
+                    rhs: 0x43.into(),
                 }],
-                registers_pre = [$lhs => Register(0x5), $rhs => Register(0x6)],
-                registers_post = [(0x5.into(), $lhs), (0x6.into(), $rhs), (0x0A.into(), $expected)],
-                flags_post = [(Zero, $zero), (Carry, $carry)],
+                registers_pre = [f32::to_bits($lhs) => 0x42, f32::to_bits($rhs) => 0x43],
+                registers_post = [(0x0A.into(), f32::to_bits($expected))],
+                flags_post = [(Zero, $zero), (NaN, $nan), (Infinity, $infinity), (Inexact, $inexact)],
             );
-        }
+        };
     }
 
-    create_shift_test!(
-        left_shift_without_any_flags_set,
-        LeftShiftTargetLhsRhs,
-        0b1,
-        2,
-        0b100,
+    create_float_test!(
+        float_add_exact_values,
+        FloatAddTargetLhsRhs,
+        1.5,
+        2.25,
+        3.75,
         zero = false,
-        carry = false
+        nan = false,
+        infinity = false,
+        inexact = false
     );
 
-    create_shift_test!(
-        left_shift_with_carry_flag_set,
-        LeftShiftTargetLhsRhs,
-        0b11 << 30,
-        1,
-        0b1 << 31,
+    create_float_test!(
+        float_add_rounds_small_value_away,
+        FloatAddTargetLhsRhs,
+        16_777_216.0,
+        1.0,
+        16_777_216.0,
         zero = false,
-        carry = true
-    );
-
-    create_shift_test!(
-        left_shift_with_carry_and_zero_flags_set,
-        LeftShiftTargetLhsRhs,
-        0b1 << 31,
-        1,
-        0,
-        zero = true,
-        carry = true
+        nan = false,
+        infinity = false,
+        inexact = true
     );
 
-    create_shift_test!(
-        left_shift_way_too_far,
-        LeftShiftTargetLhsRhs,
-        0xFFFF_FFFF,
-        123,
-        0,
+    create_float_test!(
+        float_subtract_equal_values,
+        FloatSubtractTargetLhsRhs,
+        42.5,
+        42.5,
+        0.0,
         zero = true,
-        carry = true
+        nan = false,
+        infinity = false,
+        inexact = false
     );
 
-    create_shift_test!(
-        left_shift_zero_way_too_far,
-        LeftShiftTargetLhsRhs,
-        0,
-        123,
-        0,
-        zero = true,
-        carry = false
+    create_float_test!(
+        float_multiply_with_overflow_to_infinity,
+        FloatMultiplyTargetLhsRhs,
+        f32::MAX,
+        2.0,
+        f32::INFINITY,
+        zero = false,
+        nan = false,
+        infinity = true,
+        inexact = true
     );
 
-    create_shift_test!(
-        right_shift_without_any_flags_set,
-        RightShiftTargetLhsRhs,
-        0b10,
-        1,
-        0b1,
+    create_float_test!(
+        float_divide_inexact,
+        FloatDivideTargetLhsRhs,
+        1.0,
+        3.0,
+        1.0 / 3.0,
         zero = false,
-        carry = false
+        nan = false,
+        infinity = false,
+        inexact = true
     );
 
-    create_shift_test!(
-        right_shift_with_carry_flag_set,
-        RightShiftTargetLhsRhs,
-        0b11,
-        1,
-        0b1,
+    create_float_test!(
+        float_add_to_infinity_is_exact,
+        FloatAddTargetLhsRhs,
+        f32::INFINITY,
+        1.0,
+        f32::INFINITY,
         zero = false,
-        carry = true
+        nan = false,
+        infinity = true,
+        inexact = false
     );
 
-    create_shift_test!(
-        right_shift_with_zero_flag_set,
-        RightShiftTargetLhsRhs,
-        0b0,
-        1,
-        0,
-        zero = true,
-        carry = false
+    create_test!(
+        float_divide_by_zero,
+        opcodes = &[FloatDivideTargetLhsRhs {
+            target: 0x0A.into(),
+            lhs: 0x42.into(),
+            rhs: 0x43.into(),
+        }],
+        registers_pre = [f32::to_bits(-1.0) => 0x42, f32::to_bits(0.0) => 0x43],
+        registers_post = [(0x0A.into(), f32::to_bits(f32::NEG_INFINITY))],
+        flags_post = [(DivideByZero, true), (Infinity, true), (Inexact, false)],
     );
 
-    create_shift_test!(
-        right_shift_with_carry_and_zero_flags_set,
-        RightShiftTargetLhsRhs,
-        0b1,
-        1,
-        0,
-        zero = true,
-        carry = true
-    );
-
-    create_shift_test!(
-        right_shift_way_too_far,
-        RightShiftTargetLhsRhs,
-        0xFFFF_FFFF,
-        123,
-        0b0,
-        zero = true,
-        carry = true
-    );
-
-    create_shift_test!(
-        right_shift_zero_way_too_far,
-        RightShiftTargetLhsRhs,
-        0,
-        123,
-        0b0,
-        zero = true,
-        carry = false
-    );
-
-    macro_rules! create_add_immediate_test{
+    macro_rules! create_float_comparison_test{
         (
             $test_name:ident,
-            $immediate:expr,
-            $source_value:expr,
-            $expected_value:expr,
-            zero = $zero:literal,
-            carry = $carry:literal
+            $lhs:expr,
+            $rhs:expr,
+            $expected:expr,
+            nan = $nan:literal
         ) => {
             create_test!(
                 $test_name,
-                opcodes = &[AddTargetSourceImmediate {
-                    target: Register(0xAB),
-                    source: Register(0x07),
-                    immediate: $immediate,
+                opcodes = &[FloatCompareTargetLhsRhs {
+                    target: 0x0A.into(),
+                    lhs: 0x42.into(),
+                    rhs: 0x43.into(),
                 }],
-                registers_pre = [$source_value => Register(0x07)],
-                registers_post = [(Register(0x07), $source_value), (Register(0xAB), $expected_value)],
-                flags_post = [(Zero, $zero), (Carry, $carry)],
+                registers_pre = [f32::to_bits($lhs) => 0x42, f32::to_bits($rhs) => 0x43],
+                registers_post = [(0x0A.into(), $expected)],
+                flags_post = [(Zero, $expected == 0), (NaN, $nan)],
             );
-        }
+        };
     }
 
-    create_add_immediate_test!(
-        add_immediate_with_no_flags_set,
-        2,
-        40,
-        42,
-        zero = false,
-        carry = false
+    create_float_comparison_test!(float_compare_less, -2.5, 1.0, Word::MAX, nan = false);
+    create_float_comparison_test!(float_compare_equal, 0.0, -0.0, 0, nan = false);
+    create_float_comparison_test!(float_compare_greater, 3.0, 2.0, 1, nan = false);
+    create_float_comparison_test!(float_compare_unordered, f32::NAN, 2.0, 2, nan = true);
+
+    create_test!(
+        float_square_root_of_perfect_square,
+        opcodes = &[FloatSquareRootTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [f32::to_bits(6.25) => 0x42],
+        registers_post = [(0x0A.into(), f32::to_bits(2.5))],
+        flags_post = [(NaN, false), (Inexact, false)],
     );
 
-    create_add_immediate_test!(
-        add_immediate_with_zero_flag_set,
-        0,
-        0,
-        0,
-        zero = true,
-        carry = false
+    create_test!(
+        float_square_root_of_two_is_inexact,
+        opcodes = &[FloatSquareRootTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [f32::to_bits(2.0) => 0x42],
+        registers_post = [(0x0A.into(), f32::to_bits(2.0f32.sqrt()))],
+        flags_post = [(NaN, false), (Inexact, true)],
     );
 
-    create_add_immediate_test!(
-        add_immediate_with_carry_flag_set,
-        5,
-        Word::MAX,
-        4,
-        zero = false,
-        carry = true
+    #[test]
+    fn float_square_root_of_negative_value() {
+        let mut machine = create_machine_with_opcodes(&[FloatSquareRootTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }]);
+        machine.processors[0].registers[0x42.into()] = f32::to_bits(-1.0);
+        machine.execute_next_instruction();
+        assert!(f32::from_bits(machine.processors[0].registers[0x0A.into()]).is_nan());
+        assert!(machine.processors[0].get_flag(Flag::NaN));
+        assert!(!machine.processors[0].get_flag(Flag::Inexact));
+    }
+
+    create_test!(
+        float_floor_and_ceil,
+        opcodes = &[
+            FloatFloorTargetSource {
+                target: 0x0A.into(),
+                source: 0x42.into(),
+            },
+            FloatCeilTargetSource {
+                target: 0x0B.into(),
+                source: 0x42.into(),
+            },
+        ],
+        registers_pre = [f32::to_bits(-1.5) => 0x42],
+        registers_post = [(0x0A.into(), f32::to_bits(-2.0)), (0x0B.into(), f32::to_bits(-1.0))],
     );
 
-    create_add_immediate_test!(
-        add_immediate_with_both_flags_set,
-        1,
-        Word::MAX,
-        0,
-        zero = true,
-        carry = true
+    create_test!(
+        integer_to_float_conversion_is_signed,
+        opcodes = &[IntegerToFloatTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [-7i32 as Word => 0x42],
+        registers_post = [(0x0A.into(), f32::to_bits(-7.0))],
+        flags_post = [(Inexact, false)],
     );
 
-    macro_rules! create_subtract_immediate_test{
+    create_test!(
+        integer_to_float_conversion_rounds_large_values,
+        opcodes = &[IntegerToFloatTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [16_777_217 => 0x42],
+        registers_post = [(0x0A.into(), f32::to_bits(16_777_216.0))],
+        flags_post = [(Inexact, true)],
+    );
+
+    create_test!(
+        float_to_integer_conversion_truncates,
+        opcodes = &[FloatToIntegerTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [f32::to_bits(-3.75) => 0x42],
+        registers_post = [(0x0A.into(), -3i32 as Word)],
+        flags_post = [(Zero, false), (Inexact, true), (Overflow, false)],
+    );
+
+    create_test!(
+        float_to_integer_conversion_saturates,
+        opcodes = &[FloatToIntegerTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [f32::to_bits(1e10) => 0x42],
+        registers_post = [(0x0A.into(), i32::MAX as Word)],
+        flags_post = [(Inexact, false), (Overflow, true)],
+    );
+
+    create_test!(
+        float_to_integer_conversion_of_nan,
+        opcodes = &[FloatToIntegerTargetSource {
+            target: 0x0A.into(),
+            source: 0x42.into(),
+        }],
+        registers_pre = [f32::to_bits(f32::NAN) => 0x42],
+        registers_post = [(0x0A.into(), 0)],
+        flags_post = [(Zero, true), (NaN, true), (Overflow, false)],
+    );
+
+    create_test!(
+        memory_copy_copies_bytes,
+        opcodes = &[MemoryCopyDestinationSourceLength {
+            destination: 0x01.into(),
+            source: 0x02.into(),
+            length: 0x03.into(),
+        }],
+        registers_pre = [0x200 => 0x01, 0x100 => 0x02, 6 => 0x03],
+        memory_pre = [0x1122_3344 => 0x100, 0x5566_7788 => 0x104],
+        memory_post = [(0x200, 0x1122_3344), (0x204, 0x5566_0000)],
+    );
+
+    create_test!(
+        memory_copy_with_overlapping_blocks,
+        opcodes = &[MemoryCopyDestinationSourceLength {
+            destination: 0x01.into(),
+            source: 0x02.into(),
+            length: 0x03.into(),
+        }],
+        registers_pre = [0x102 => 0x01, 0x100 => 0x02, 4 => 0x03],
+        memory_pre = [0x1122_3344 => 0x100],
+        memory_post = [(0x100, 0x1122_1122), (0x104, 0x3344_0000)],
+    );
+
+    create_test!(
+        memory_fill_uses_lowest_byte,
+        opcodes = &[MemoryFillDestinationValueLength {
+            destination: 0x01.into(),
+            value: 0x02.into(),
+            length: 0x03.into(),
+        }],
+        registers_pre = [0x101 => 0x01, 0xCAFE => 0x02, 5 => 0x03],
+        memory_post = [(0x100, 0x00FE_FEFE), (0x104, 0xFEFE_0000)],
+    );
+
+    macro_rules! create_memory_compare_test{
         (
             $test_name:ident,
-            $immediate:expr,
-            $source_value:expr,
-            $expected_value:expr,
-            zero = $zero:literal,
-            carry = $carry:literal
+            $lhs:expr,
+            $rhs:expr,
+            $length:expr,
+            $expected:expr
         ) => {
             create_test!(
                 $test_name,
-                opcodes = &[SubtractTargetSourceImmediate {
-                    target: Register(0xAB),
-                    source: Register(0x07),
-                    immediate: $immediate,
+                opcodes = &[MemoryCompareTargetLhsRhsLength {
+                    target: 0x0A.into(),
+                    lhs: 0x01.into(),
+                    rhs: 0x02.into(),
+                    length: 0x03.into(),
                 }],
-                registers_pre = [$source_value => Register(0x07)],
-                registers_post = [(Register(0x07), $source_value), (Register(0xAB), $expected_value)],
-                flags_post = [(Zero, $zero), (Carry, $carry)],
+                registers_pre = [0x100 => 0x01, 0x200 => 0x02, $length => 0x03],
+                memory_pre = [$lhs => 0x100, $rhs => 0x200],
+                registers_post = [(0x0A.into(), $expected)],
+                flags_post = [(Zero, $expected == 0)],
             );
         }
     }
 
-    create_subtract_immediate_test!(
-        subtract_immediate_with_no_flags_set,
-        2,
-        44,
-        42,
-        zero = false,
-        carry = false
-    );
+    create_memory_compare_test!(memory_compare_equal_blocks, 0xAABB_CCDD, 0xAABB_CCDD, 4, 0);
 
-    create_subtract_immediate_test!(
-        subtract_immediate_with_zero_flag_set,
-        42,
-        42,
-        0,
-        zero = true,
-        carry = false
-    );
+    create_memory_compare_test!(memory_compare_less, 0xAABB_CCDD, 0xAABB_CDDD, 4, Word::MAX);
 
-    create_subtract_immediate_test!(
-        subtract_immediate_with_carry_flag_set,
+    create_memory_compare_test!(memory_compare_greater, 0xABBB_CCDD, 0xAABB_CCDD, 4, 1);
+
+    create_memory_compare_test!(
+        memory_compare_ignores_bytes_after_length,
+        0xAABB_0000,
+        0xAABB_FFFF,
         2,
-        1,
-        Word::MAX,
-        zero = false,
-        carry = true
+        0
     );
 
-    macro_rules! create_comparison_test{
+    #[test]
+    #[should_panic]
+    fn memory_copy_out_of_bounds() {
+        let mut machine = create_machine_with_opcodes(&[MemoryCopyDestinationSourceLength {
+            destination: 0x01.into(),
+            source: 0x02.into(),
+            length: 0x03.into(),
+        }]);
+        machine.processors[0].registers[0x01.into()] = Memory::SIZE as Address - 4;
+        machine.processors[0].registers[0x03.into()] = 8;
+        machine.execute_next_instruction();
+    }
+
+    macro_rules! create_bitwise_test{
         (
             $test_name:ident,
+            $bitwise_instruction:ident,
             $lhs:expr,
             $rhs:expr,
             $expected:expr,
@@ -1503,142 +1775,2506 @@ mod tests {
         ) => {
             create_test!(
                 $test_name,
-                opcodes = &[CompareTargetLhsRhs {
-                    target: Register(0x0A),
-                    lhs: Register(0x42),
-                    rhs: Register(0x43),
+                setup = {
+                    let lhs_register = 0x42.into();
+                    let rhs_register = 0x43.into();
+                    let target_register = 0x0A.into();
+                    let lhs: Word = $lhs;
+                    let rhs = $rhs;
+                    let expected = $expected;
+                },
+                opcodes = &[$bitwise_instruction {
+                    target: target_register,
+                    lhs: lhs_register,
+                    rhs: rhs_register,
                 }],
-                registers_pre = [$lhs => Register(0x42), $rhs => Register(0x43)],
-                registers_post = [
-                    (Register(0x42), $lhs),
-                    (Register(0x43), $rhs),
-                    (Register(0x0A), $expected)
-                ],
+                registers_pre = [lhs => lhs_register, rhs => rhs_register],
+                registers_post = [(lhs_register, lhs), (rhs_register, rhs), (target_register, expected)],
                 flags_post = [(Zero, $zero)],
             );
         }
     }
 
-    create_comparison_test!(
-        compare_lower_value_against_higher_value,
-        10,
-        12,
-        Word::MAX,
+    create_bitwise_test!(
+        bitwise_and_two_values_with_no_flags_set,
+        AndTargetLhsRhs,
+        0b0110_1110_1001_1010_0110_1110_1001_1010,
+        0b1011_1010_0101_1001_1011_1010_0101_1001,
+        0b0010_1010_0001_1000_0010_1010_0001_1000,
+        zero = false
+    );
+
+    create_bitwise_test!(
+        bitwise_and_two_values_with_zero_flag_set,
+        AndTargetLhsRhs,
+        0b0100_0100_1000_0110_0100_0100_1000_0010,
+        0b1011_1010_0101_1001_1011_1010_0101_1001,
+        0,
+        zero = true
+    );
+
+    create_bitwise_test!(
+        bitwise_or_two_values_with_no_flags_set,
+        OrTargetLhsRhs,
+        0b0110_1110_1001_1010_0110_1110_1001_1010,
+        0b1011_1010_0101_1001_1011_1010_0101_1001,
+        0b1111_1110_1101_1011_1111_1110_1101_1011,
+        zero = false
+    );
+
+    create_bitwise_test!(
+        bitwise_or_two_values_with_zero_flag_set,
+        OrTargetLhsRhs,
+        0,
+        0,
+        0,
+        zero = true
+    );
+
+    create_bitwise_test!(
+        bitwise_xor_two_values_with_no_flags_set,
+        XorTargetLhsRhs,
+        0b0110_1110_1001_1010_0110_1110_1001_1010,
+        0b1011_1010_0101_1001_1011_1010_0101_1001,
+        0b1101_0100_1100_0011_1101_0100_1100_0011,
         zero = false
     );
 
-    create_comparison_test!(
-        compare_higher_value_against_lower_value,
-        14,
-        12,
-        1,
-        zero = false
+    create_bitwise_test!(
+        bitwise_xor_two_values_with_zero_flag_set,
+        XorTargetLhsRhs,
+        0b1011_1010_1001_0010_0100_0100_1001_0010,
+        0b1011_1010_1001_0010_0100_0100_1001_0010,
+        0,
+        zero = true
+    );
+
+    create_test!(
+        bitwise_not_value_with_no_flags_set,
+        setup = {
+            let source = 0x5.into();
+            let target = 0x0A.into();
+            let data = 0b0010_1010_0001_1000_0010_1010_0001_1000;
+            let expected = 0b1101_0101_1110_0111_1101_0101_1110_0111;
+        },
+        opcodes = &[NotTargetSource { target, source }],
+        registers_pre = [data => source],
+        registers_post = [(target, expected)],
+        flags_post = [(Zero, false)],
+    );
+
+    create_test!(
+        bitwise_not_value_with_zero_flag_set,
+        setup = {
+            let source = 0x5.into();
+            let target = 0x0A.into();
+            let data = 0xFFFFFFFF;
+            let expected = 0;
+        },
+        opcodes = &[NotTargetSource { target, source }],
+        registers_pre = [data => source],
+        registers_post = [(target, expected)],
+        flags_post = [(Zero, true)],
+    );
+
+    macro_rules! create_shift_test{
+        (
+            $test_name:ident,
+            $shift_instruction:ident,
+            $lhs:expr,
+            $rhs:expr,
+            $expected:expr,
+            zero = $zero:literal,
+            carry = $carry:literal
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$shift_instruction {
+                    target: 0x0A.into(),
+                    lhs: 0x5.into(),
+                    rhs: 0x6.into(),
+                }],
+                registers_pre = [$lhs => Register(0x5), $rhs => Register(0x6)],
+                registers_post = [(0x5.into(), $lhs), (0x6.into(), $rhs), (0x0A.into(), $expected)],
+                flags_post = [(Zero, $zero), (Carry, $carry)],
+            );
+        }
+    }
+
+    create_shift_test!(
+        left_shift_without_any_flags_set,
+        LeftShiftTargetLhsRhs,
+        0b1,
+        2,
+        0b100,
+        zero = false,
+        carry = false
+    );
+
+    create_shift_test!(
+        left_shift_with_carry_flag_set,
+        LeftShiftTargetLhsRhs,
+        0b11 << 30,
+        1,
+        0b1 << 31,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        left_shift_with_carry_and_zero_flags_set,
+        LeftShiftTargetLhsRhs,
+        0b1 << 31,
+        1,
+        0,
+        zero = true,
+        carry = true
+    );
+
+    create_shift_test!(
+        left_shift_way_too_far,
+        LeftShiftTargetLhsRhs,
+        0xFFFF_FFFF,
+        123,
+        0,
+        zero = true,
+        carry = true
+    );
+
+    create_shift_test!(
+        left_shift_zero_way_too_far,
+        LeftShiftTargetLhsRhs,
+        0,
+        123,
+        0,
+        zero = true,
+        carry = false
+    );
+
+    create_shift_test!(
+        right_shift_without_any_flags_set,
+        RightShiftTargetLhsRhs,
+        0b10,
+        1,
+        0b1,
+        zero = false,
+        carry = false
+    );
+
+    create_shift_test!(
+        right_shift_with_carry_flag_set,
+        RightShiftTargetLhsRhs,
+        0b11,
+        1,
+        0b1,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        right_shift_with_zero_flag_set,
+        RightShiftTargetLhsRhs,
+        0b0,
+        1,
+        0,
+        zero = true,
+        carry = false
+    );
+
+    create_shift_test!(
+        right_shift_with_carry_and_zero_flags_set,
+        RightShiftTargetLhsRhs,
+        0b1,
+        1,
+        0,
+        zero = true,
+        carry = true
+    );
+
+    create_shift_test!(
+        right_shift_way_too_far,
+        RightShiftTargetLhsRhs,
+        0xFFFF_FFFF,
+        123,
+        0b0,
+        zero = true,
+        carry = true
+    );
+
+    create_shift_test!(
+        right_shift_zero_way_too_far,
+        RightShiftTargetLhsRhs,
+        0,
+        123,
+        0b0,
+        zero = true,
+        carry = false
+    );
+
+    create_shift_test!(
+        arithmetic_right_shift_of_positive_value,
+        ArithmeticRightShiftTargetLhsRhs,
+        0b1100,
+        2,
+        0b11,
+        zero = false,
+        carry = false
+    );
+
+    create_shift_test!(
+        arithmetic_right_shift_of_negative_value_preserves_sign,
+        ArithmeticRightShiftTargetLhsRhs,
+        -16i32 as Word,
+        2,
+        -4i32 as Word,
+        zero = false,
+        carry = false
+    );
+
+    create_shift_test!(
+        arithmetic_right_shift_of_negative_value_with_carry_flag_set,
+        ArithmeticRightShiftTargetLhsRhs,
+        -3i32 as Word,
+        1,
+        -2i32 as Word,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        arithmetic_right_shift_of_negative_value_way_too_far,
+        ArithmeticRightShiftTargetLhsRhs,
+        -3i32 as Word,
+        123,
+        Word::MAX,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        arithmetic_right_shift_of_positive_value_way_too_far,
+        ArithmeticRightShiftTargetLhsRhs,
+        0x7FFF_FFFF,
+        123,
+        0,
+        zero = true,
+        carry = true
+    );
+
+    create_shift_test!(
+        rotate_left_with_carry_flag_set,
+        RotateLeftTargetLhsRhs,
+        0x8000_0001,
+        1,
+        0x0000_0003,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        rotate_left_by_multiple_of_word_size,
+        RotateLeftTargetLhsRhs,
+        0x8000_0001,
+        64,
+        0x8000_0001,
+        zero = false,
+        carry = false
+    );
+
+    create_shift_test!(
+        rotate_right_with_carry_flag_set,
+        RotateRightTargetLhsRhs,
+        0x0000_0001,
+        1,
+        0x8000_0000,
+        zero = false,
+        carry = true
+    );
+
+    create_shift_test!(
+        rotate_right_zero,
+        RotateRightTargetLhsRhs,
+        0,
+        13,
+        0,
+        zero = true,
+        carry = false
+    );
+
+    macro_rules! create_unary_bit_test{
+        (
+            $test_name:ident,
+            $instruction:ident,
+            $source:expr,
+            $expected:expr,
+            flags = [ $( ( $flag:ident, $flag_value:expr ) ),+ ]
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$instruction {
+                    target: 0x0A.into(),
+                    source: 0x05.into(),
+                }],
+                registers_pre = [$source => Register(0x05)],
+                registers_post = [(0x05.into(), $source), (0x0A.into(), $expected)],
+                flags_post = [ $( ($flag, $flag_value) ),+ ],
+            );
+        }
+    }
+
+    create_unary_bit_test!(
+        population_count,
+        PopulationCountTargetSource,
+        0xF0F0_0001,
+        9,
+        flags = [(Zero, false)]
+    );
+
+    create_unary_bit_test!(
+        population_count_of_zero,
+        PopulationCountTargetSource,
+        0,
+        0,
+        flags = [(Zero, true)]
+    );
+
+    create_unary_bit_test!(
+        count_leading_zeros,
+        CountLeadingZerosTargetSource,
+        0x0001_0000,
+        15,
+        flags = [(Zero, false), (Carry, false)]
+    );
+
+    create_unary_bit_test!(
+        count_leading_zeros_of_zero,
+        CountLeadingZerosTargetSource,
+        0,
+        32,
+        flags = [(Zero, false), (Carry, true)]
+    );
+
+    create_unary_bit_test!(
+        count_trailing_zeros,
+        CountTrailingZerosTargetSource,
+        0x0001_0000,
+        16,
+        flags = [(Zero, false), (Carry, false)]
+    );
+
+    create_unary_bit_test!(
+        count_trailing_zeros_with_lowest_bit_set,
+        CountTrailingZerosTargetSource,
+        0xFFFF_FFFF,
+        0,
+        flags = [(Zero, true), (Carry, false)]
+    );
+
+    create_unary_bit_test!(
+        byte_swap,
+        ByteSwapTargetSource,
+        0x1122_3344,
+        0x4433_2211,
+        flags = [(Zero, false)]
+    );
+
+    create_test!(
+        byte_swap_of_value_read_from_memory,
+        opcodes = &[
+            MoveRegisterAddress {
+                register: 0x05.into(),
+                source_address: 0x100,
+            },
+            ByteSwapTargetSource {
+                target: 0x05.into(),
+                source: 0x05.into(),
+            },
+        ],
+        memory_pre = [0xEFBE_ADDE => 0x100],
+        registers_post = [(0x05.into(), 0xDEAD_BEEF)],
+    );
+
+    macro_rules! create_bit_field_test{
+        (
+            $test_name:ident,
+            $instruction:ident,
+            $target:expr,
+            $source:expr,
+            $offset:expr,
+            $width:expr,
+            $expected:expr
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$instruction {
+                    target: 0x0A.into(),
+                    source: 0x05.into(),
+                    offset: 0x06.into(),
+                    width: 0x07.into(),
+                }],
+                registers_pre = [
+                    $target => Register(0x0A),
+                    $source => Register(0x05),
+                    $offset => Register(0x06),
+                    $width => Register(0x07)
+                ],
+                registers_post = [(0x0A.into(), $expected)],
+                flags_post = [(Zero, $expected == 0 as Word)],
+            );
+        }
+    }
+
+    create_bit_field_test!(
+        bit_field_extract,
+        BitFieldExtractTargetSourceOffsetWidth,
+        0,
+        0x1234_5678,
+        8,
+        12,
+        0x456
+    );
+
+    create_bit_field_test!(
+        bit_field_extract_beyond_word,
+        BitFieldExtractTargetSourceOffsetWidth,
+        0,
+        0xF234_5678,
+        28,
+        8,
+        0xF
+    );
+
+    create_bit_field_test!(
+        bit_field_extract_whole_word,
+        BitFieldExtractTargetSourceOffsetWidth,
+        0,
+        0xF234_5678,
+        0,
+        32,
+        0xF234_5678
+    );
+
+    create_bit_field_test!(
+        bit_field_extract_with_offset_too_far,
+        BitFieldExtractTargetSourceOffsetWidth,
+        0,
+        0xF234_5678,
+        32,
+        4,
+        0
+    );
+
+    create_bit_field_test!(
+        bit_field_insert,
+        BitFieldInsertTargetSourceOffsetWidth,
+        0x1234_5678,
+        0xFFFF_FABC,
+        8,
+        12,
+        0x123A_BC78
+    );
+
+    create_bit_field_test!(
+        bit_field_insert_beyond_word,
+        BitFieldInsertTargetSourceOffsetWidth,
+        0x1234_5678,
+        0xFF,
+        28,
+        8,
+        0xF234_5678
+    );
+
+    create_bit_field_test!(
+        bit_field_insert_with_zero_width,
+        BitFieldInsertTargetSourceOffsetWidth,
+        0x1234_5678,
+        0xFF,
+        4,
+        0,
+        0x1234_5678
+    );
+
+    macro_rules! create_add_immediate_test{
+        (
+            $test_name:ident,
+            $immediate:expr,
+            $source_value:expr,
+            $expected_value:expr,
+            zero = $zero:literal,
+            carry = $carry:literal
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[AddTargetSourceImmediate {
+                    target: Register(0xAB),
+                    source: Register(0x07),
+                    immediate: $immediate,
+                }],
+                registers_pre = [$source_value => Register(0x07)],
+                registers_post = [(Register(0x07), $source_value), (Register(0xAB), $expected_value)],
+                flags_post = [(Zero, $zero), (Carry, $carry)],
+            );
+        }
+    }
+
+    create_add_immediate_test!(
+        add_immediate_with_no_flags_set,
+        2,
+        40,
+        42,
+        zero = false,
+        carry = false
+    );
+
+    create_add_immediate_test!(
+        add_immediate_with_zero_flag_set,
+        0,
+        0,
+        0,
+        zero = true,
+        carry = false
+    );
+
+    create_add_immediate_test!(
+        add_immediate_with_carry_flag_set,
+        5,
+        Word::MAX,
+        4,
+        zero = false,
+        carry = true
+    );
+
+    create_add_immediate_test!(
+        add_immediate_with_both_flags_set,
+        1,
+        Word::MAX,
+        0,
+        zero = true,
+        carry = true
+    );
+
+    macro_rules! create_subtract_immediate_test{
+        (
+            $test_name:ident,
+            $immediate:expr,
+            $source_value:expr,
+            $expected_value:expr,
+            zero = $zero:literal,
+            carry = $carry:literal
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[SubtractTargetSourceImmediate {
+                    target: Register(0xAB),
+                    source: Register(0x07),
+                    immediate: $immediate,
+                }],
+                registers_pre = [$source_value => Register(0x07)],
+                registers_post = [(Register(0x07), $source_value), (Register(0xAB), $expected_value)],
+                flags_post = [(Zero, $zero), (Carry, $carry)],
+            );
+        }
+    }
+
+    create_subtract_immediate_test!(
+        subtract_immediate_with_no_flags_set,
+        2,
+        44,
+        42,
+        zero = false,
+        carry = false
+    );
+
+    create_subtract_immediate_test!(
+        subtract_immediate_with_zero_flag_set,
+        42,
+        42,
+        0,
+        zero = true,
+        carry = false
+    );
+
+    create_subtract_immediate_test!(
+        subtract_immediate_with_carry_flag_set,
+        2,
+        1,
+        Word::MAX,
+        zero = false,
+        carry = true
+    );
+
+    macro_rules! create_source_immediate_test{
+        (
+            $test_name:ident,
+            $instruction:ident,
+            $source_value:expr,
+            $immediate:expr,
+            $expected_value:expr,
+            $( flags_pre = [ $( $flag_pre_value:expr => $flag_pre:ident ),+ ], )?
+            flags = [ $( ( $flag:ident, $flag_value:expr ) ),+ ]
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$instruction {
+                    target: Register(0xAB),
+                    source: Register(0x07),
+                    immediate: $immediate as Word,
+                }],
+                registers_pre = [$source_value as Word => Register(0x07)],
+                $( flags_pre = [ $( $flag_pre_value => $flag_pre ),+ ], )?
+                registers_post = [
+                    (Register(0x07), $source_value as Word),
+                    (Register(0xAB), $expected_value as Word)
+                ],
+                flags_post = [ $( ($flag, $flag_value) ),+ ],
+            );
+        }
+    }
+
+    create_source_immediate_test!(
+        and_immediate,
+        AndTargetSourceImmediate,
+        0b1100,
+        0b1010,
+        0b1000,
+        flags = [(Zero, false)]
+    );
+
+    create_source_immediate_test!(
+        and_immediate_with_zero_flag_set,
+        AndTargetSourceImmediate,
+        0xFF00,
+        0x00FF,
+        0,
+        flags = [(Zero, true)]
+    );
+
+    create_source_immediate_test!(
+        or_immediate,
+        OrTargetSourceImmediate,
+        0b1100,
+        0b1010,
+        0b1110,
+        flags = [(Zero, false)]
+    );
+
+    create_source_immediate_test!(
+        xor_immediate_with_zero_flag_set,
+        XorTargetSourceImmediate,
+        0xCAFE,
+        0xCAFE,
+        0,
+        flags = [(Zero, true)]
+    );
+
+    create_source_immediate_test!(
+        left_shift_immediate_with_carry_flag_set,
+        LeftShiftTargetSourceImmediate,
+        0b11 << 30,
+        1,
+        0b1 << 31,
+        flags = [(Zero, false), (Carry, true)]
+    );
+
+    create_source_immediate_test!(
+        left_shift_immediate_by_whole_word,
+        LeftShiftTargetSourceImmediate,
+        1,
+        32,
+        0,
+        flags = [(Zero, true), (Carry, true)]
+    );
+
+    create_source_immediate_test!(
+        right_shift_immediate_without_any_flags_set,
+        RightShiftTargetSourceImmediate,
+        0b1000,
+        3,
+        1,
+        flags = [(Zero, false), (Carry, false)]
+    );
+
+    create_source_immediate_test!(
+        arithmetic_right_shift_immediate_preserves_sign,
+        ArithmeticRightShiftTargetSourceImmediate,
+        -16i32,
+        2,
+        -4i32,
+        flags = [(Zero, false), (Carry, false)]
+    );
+
+    create_source_immediate_test!(
+        multiply_immediate,
+        MultiplyTargetSourceImmediate,
+        6,
+        7,
+        42,
+        flags = [(Zero, false), (Carry, false)]
+    );
+
+    create_source_immediate_test!(
+        multiply_immediate_with_carry_flag_set,
+        MultiplyTargetSourceImmediate,
+        0x8000_0001u32,
+        2,
+        2,
+        flags = [(Zero, false), (Carry, true)]
+    );
+
+    create_source_immediate_test!(
+        divide_immediate,
+        DivideTargetSourceImmediate,
+        43,
+        5,
+        8,
+        flags = [(Zero, false), (DivideByZero, false)]
+    );
+
+    create_source_immediate_test!(
+        divide_immediate_by_zero,
+        DivideTargetSourceImmediate,
+        43,
+        0,
+        0,
+        flags = [(Zero, true), (DivideByZero, true)]
+    );
+
+    create_source_immediate_test!(
+        modulo_immediate,
+        ModuloTargetSourceImmediate,
+        43,
+        5,
+        3,
+        flags = [(Zero, false), (DivideByZero, false)]
+    );
+
+    create_source_immediate_test!(
+        modulo_immediate_by_zero,
+        ModuloTargetSourceImmediate,
+        43,
+        0,
+        43,
+        flags = [(Zero, false), (DivideByZero, true)]
+    );
+
+    create_source_immediate_test!(
+        multiply_signed_immediate,
+        MultiplySignedTargetSourceImmediate,
+        -6i32,
+        7,
+        -42i32,
+        flags = [(Zero, false), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        multiply_signed_immediate_with_overflow_flag_set,
+        MultiplySignedTargetSourceImmediate,
+        i32::MAX,
+        -2i32,
+        2,
+        flags = [(Zero, false), (Overflow, true)]
+    );
+
+    create_source_immediate_test!(
+        divide_signed_immediate_rounds_towards_zero,
+        DivideSignedTargetSourceImmediate,
+        -43i32,
+        5,
+        -8i32,
+        flags = [(Zero, false), (DivideByZero, false), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        divide_signed_immediate_with_overflow_flag_set,
+        DivideSignedTargetSourceImmediate,
+        i32::MIN,
+        -1i32,
+        i32::MIN,
+        flags = [(Zero, false), (DivideByZero, false), (Overflow, true)]
+    );
+
+    create_source_immediate_test!(
+        divide_signed_immediate_by_zero,
+        DivideSignedTargetSourceImmediate,
+        -43i32,
+        0,
+        0,
+        flags = [(Zero, true), (DivideByZero, true), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        modulo_signed_immediate_has_the_sign_of_the_dividend,
+        ModuloSignedTargetSourceImmediate,
+        -43i32,
+        -5i32,
+        -3i32,
+        flags = [(Zero, false), (DivideByZero, false)]
+    );
+
+    create_source_immediate_test!(
+        modulo_signed_immediate_by_zero,
+        ModuloSignedTargetSourceImmediate,
+        -43i32,
+        0,
+        -43i32,
+        flags = [(Zero, false), (DivideByZero, true)]
+    );
+
+    create_source_immediate_test!(
+        add_with_carry_immediate,
+        AddWithCarryTargetSourceImmediate,
+        Word::MAX,
+        1,
+        1,
+        flags_pre = [true => Carry],
+        flags = [(Zero, false), (Carry, true), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        add_with_carry_immediate_with_overflow_flag_set,
+        AddWithCarryTargetSourceImmediate,
+        i32::MAX,
+        0,
+        i32::MIN,
+        flags_pre = [true => Carry],
+        flags = [(Zero, false), (Carry, false), (Overflow, true)]
+    );
+
+    create_source_immediate_test!(
+        subtract_with_carry_immediate,
+        SubtractWithCarryTargetSourceImmediate,
+        1,
+        1,
+        Word::MAX,
+        flags_pre = [true => Carry],
+        flags = [(Zero, false), (Carry, true), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        subtract_with_carry_immediate_without_carry_set,
+        SubtractWithCarryTargetSourceImmediate,
+        43,
+        1,
+        42,
+        flags_pre = [false => Carry],
+        flags = [(Zero, false), (Carry, false), (Overflow, false)]
+    );
+
+    create_source_immediate_test!(
+        compare_immediate_is_unsigned,
+        CompareTargetSourceImmediate,
+        -1i32,
+        1,
+        1,
+        flags = [(Zero, false)]
+    );
+
+    create_source_immediate_test!(
+        compare_immediate_with_equal_values,
+        CompareTargetSourceImmediate,
+        42,
+        42,
+        0,
+        flags = [(Zero, true)]
+    );
+
+    create_source_immediate_test!(
+        compare_signed_immediate,
+        CompareSignedTargetSourceImmediate,
+        -1i32,
+        1,
+        Word::MAX,
+        flags = [(Zero, false)]
+    );
+
+    macro_rules! create_bool_comparison_immediate_test{
+        (
+            $test_name:ident,
+            $instruction:ident,
+            $lhs:expr,
+            $immediate:expr,
+            $expected:expr
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$instruction {
+                    target: Register(0x0A),
+                    lhs: Register(0x42),
+                    immediate: $immediate as Word,
+                }],
+                registers_pre = [$lhs as Word => Register(0x42)],
+                registers_post = [(Register(0x0A), $expected)],
+            );
+        }
+    }
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_equals_immediate,
+        BoolCompareEqualsImmediate,
+        5,
+        5,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_not_equals_immediate,
+        BoolCompareNotEqualsImmediate,
+        5,
+        5,
+        0
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_greater_immediate_is_unsigned,
+        BoolCompareGreaterImmediate,
+        -1i32,
+        1,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_greater_or_equals_immediate,
+        BoolCompareGreaterOrEqualsImmediate,
+        4,
+        5,
+        0
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_less_immediate,
+        BoolCompareLessImmediate,
+        4,
+        5,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_less_or_equals_immediate,
+        BoolCompareLessOrEqualsImmediate,
+        5,
+        5,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_signed_greater_immediate,
+        BoolCompareSignedGreaterImmediate,
+        -1i32,
+        1,
+        0
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_signed_greater_or_equals_immediate,
+        BoolCompareSignedGreaterOrEqualsImmediate,
+        -1i32,
+        -1i32,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_signed_less_immediate,
+        BoolCompareSignedLessImmediate,
+        -2i32,
+        -1i32,
+        1
+    );
+
+    create_bool_comparison_immediate_test!(
+        bool_compare_signed_less_or_equals_immediate,
+        BoolCompareSignedLessOrEqualsImmediate,
+        0,
+        -1i32,
+        0
+    );
+
+    macro_rules! create_comparison_test{
+        (
+            $test_name:ident,
+            $lhs:expr,
+            $rhs:expr,
+            $expected:expr,
+            zero = $zero:literal
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[CompareTargetLhsRhs {
+                    target: Register(0x0A),
+                    lhs: Register(0x42),
+                    rhs: Register(0x43),
+                }],
+                registers_pre = [$lhs => Register(0x42), $rhs => Register(0x43)],
+                registers_post = [
+                    (Register(0x42), $lhs),
+                    (Register(0x43), $rhs),
+                    (Register(0x0A), $expected)
+                ],
+                flags_post = [(Zero, $zero)],
+            );
+        }
+    }
+
+    create_comparison_test!(
+        compare_lower_value_against_higher_value,
+        10,
+        12,
+        Word::MAX,
+        zero = false
+    );
+
+    create_comparison_test!(
+        compare_higher_value_against_lower_value,
+        14,
+        12,
+        1,
+        zero = false
+    );
+
+    create_comparison_test!(compare_equal_values, 12, 12, 0, zero = true);
+
+    create_comparison_test!(
+        unsigned_compare_treats_negative_values_as_large,
+        -1i32 as Word,
+        1,
+        1,
+        zero = false
+    );
+
+    macro_rules! create_signed_comparison_test{
+        (
+            $test_name:ident,
+            $lhs:expr,
+            $rhs:expr,
+            $expected:expr,
+            zero = $zero:literal
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[CompareSignedTargetLhsRhs {
+                    target: Register(0x0A),
+                    lhs: Register(0x42),
+                    rhs: Register(0x43),
+                }],
+                registers_pre = [$lhs as Word => Register(0x42), $rhs as Word => Register(0x43)],
+                registers_post = [(Register(0x0A), $expected)],
+                flags_post = [(Zero, $zero)],
+            );
+        }
+    }
+
+    create_signed_comparison_test!(
+        signed_compare_negative_value_against_positive_value,
+        -1i32,
+        1i32,
+        Word::MAX,
+        zero = false
+    );
+
+    create_signed_comparison_test!(
+        signed_compare_positive_value_against_negative_value,
+        5i32,
+        -12i32,
+        1,
+        zero = false
+    );
+
+    create_signed_comparison_test!(
+        signed_compare_two_negative_values,
+        -14i32,
+        -12i32,
+        Word::MAX,
+        zero = false
+    );
+
+    create_signed_comparison_test!(
+        signed_compare_equal_negative_values,
+        -12i32,
+        -12i32,
+        0,
+        zero = true
+    );
+
+    macro_rules! create_bool_comparison_test{
+        (
+            $test_name:ident,
+            $instruction:ident,
+            $lhs:expr,
+            $rhs:expr,
+            $expected:expr
+        ) => {
+            create_test!(
+                $test_name,
+                opcodes = &[$instruction {
+                    target: Register(0x0A),
+                    lhs: Register(0x42),
+                    rhs: Register(0x43),
+                }],
+                registers_pre = [$lhs as Word => Register(0x42), $rhs as Word => Register(0x43)],
+                registers_post = [(Register(0x0A), $expected)],
+            );
+        }
+    }
+
+    create_bool_comparison_test!(
+        bool_compare_greater_is_unsigned,
+        BoolCompareGreater,
+        -1i32,
+        1i32,
+        1
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_greater_with_negative_lhs,
+        BoolCompareSignedGreater,
+        -1i32,
+        1i32,
+        0
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_greater_with_negative_rhs,
+        BoolCompareSignedGreater,
+        1i32,
+        -1i32,
+        1
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_greater_or_equals_with_equal_values,
+        BoolCompareSignedGreaterOrEquals,
+        -7i32,
+        -7i32,
+        1
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_greater_or_equals_with_lower_value,
+        BoolCompareSignedGreaterOrEquals,
+        -8i32,
+        -7i32,
+        0
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_less_with_negative_lhs,
+        BoolCompareSignedLess,
+        i32::MIN,
+        0i32,
+        1
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_less_with_negative_rhs,
+        BoolCompareSignedLess,
+        0i32,
+        i32::MIN,
+        0
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_less_or_equals_with_equal_values,
+        BoolCompareSignedLessOrEquals,
+        -3i32,
+        -3i32,
+        1
+    );
+
+    create_bool_comparison_test!(
+        bool_compare_signed_less_or_equals_with_greater_value,
+        BoolCompareSignedLessOrEquals,
+        3i32,
+        -3i32,
+        0
+    );
+
+    #[test]
+    fn push_and_pop_stack_value() {
+        let mut machine = Machine::new(create_mock_periphery(), false, MachineProfile::Uniform, 1);
+        let source_register = 0xAB.into();
+        let target_register = 0x06.into();
+        let data = 42;
+        machine.processors[0].registers[source_register] = data;
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+        let machine = execute_instruction_with_machine(
+            machine,
+            PushRegister {
+                register: source_register,
+            },
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START + Word::SIZE as Address
+        );
+        assert_eq!(
+            machine.memory.read_data(address_constants::STACK_START),
+            data
+        );
+        let machine = execute_instruction_with_machine(
+            machine,
+            PopRegister {
+                register: target_register,
+            },
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+        assert_eq!(machine.processors[0].registers[target_register], data);
+    }
+
+    #[test]
+    fn push_and_pop_immediate() {
+        let machine = Machine::new(create_mock_periphery(), false, MachineProfile::Uniform, 1);
+        let target_register = 0x06.into();
+        let data = 42;
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+        let machine = execute_instruction_with_machine(machine, PushImmediate { immediate: data });
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START + Word::SIZE as Address
+        );
+        assert_eq!(
+            machine.memory.read_data(address_constants::STACK_START),
+            data
+        );
+        let machine = execute_instruction_with_machine(
+            machine,
+            PopRegister {
+                register: target_register,
+            },
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+        assert_eq!(machine.processors[0].registers[target_register], data);
+    }
+
+    #[test]
+    fn push_and_pop_multiple_stack_values() {
+        let values = [1, 4, 5, 42, 2, 3];
+        let mut machine = Machine::new(create_mock_periphery(), false, MachineProfile::Uniform, 1);
+        for (register, value) in (0..).map(Register).zip(values) {
+            machine.processors[0].registers[register] = value;
+            machine = execute_instruction_with_machine(machine, PushRegister { register });
+            assert_eq!(
+                machine.processors[0].get_stack_pointer(),
+                address_constants::STACK_START
+                    + (register.0 as Address + 1) * Word::SIZE as Address
+            );
+            assert_eq!(
+                machine.memory.read_data(
+                    address_constants::STACK_START + register.0 as Address * Word::SIZE as Address
+                ),
+                value
+            );
+        }
+        for &value in values.iter().rev() {
+            let target = 0xAB.into();
+            machine = execute_instruction_with_machine(machine, PopRegister { register: target });
+            assert_eq!(machine.processors[0].registers[target], value);
+        }
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+    }
+
+    #[test]
+    fn enter_and_leave_stack_frame() {
+        let locals_size = 3 * Word::SIZE as Address;
+        let old_frame_pointer = 0xCAFE;
+        let mut machine = create_machine_with_opcodes(&[
+            PushImmediate { immediate: 42 },
+            EnterImmediate {
+                immediate: locals_size,
+            },
+            Leave {},
+        ]);
+        machine.processors[0].registers[Processor::FRAME_POINTER] = old_frame_pointer;
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        let frame_pointer = address_constants::STACK_START + 2 * Word::SIZE as Address;
+        assert_eq!(
+            machine.processors[0].registers[Processor::FRAME_POINTER],
+            frame_pointer
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            frame_pointer + locals_size
+        );
+        assert_eq!(
+            machine
+                .memory
+                .read_data(address_constants::STACK_START + Word::SIZE as Address),
+            old_frame_pointer
+        );
+
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::FRAME_POINTER],
+            old_frame_pointer
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START + Word::SIZE as Address
+        );
+    }
+
+    create_test!(
+        store_and_load_relative_to_stack_pointer,
+        setup = {
+            let stack_pointer = address_constants::STACK_START + 4 * Word::SIZE as Address;
+        },
+        opcodes = &[
+            MoveStackSourceOffset {
+                source: 0x01.into(),
+                immediate: -8i32 as Word,
+            },
+            MoveTargetStackOffset {
+                target: 0x02.into(),
+                immediate: -8i32 as Word,
+            },
+        ],
+        registers_pre = [stack_pointer => Processor::STACK_POINTER, 42 => 0x01],
+        registers_post = [(0x02.into(), 42)],
+        memory_post = [(stack_pointer - 8, 42)],
+    );
+
+    create_test!(
+        store_and_load_relative_to_frame_pointer,
+        setup = {
+            let frame_pointer = address_constants::STACK_START + 4 * Word::SIZE as Address;
+        },
+        opcodes = &[
+            MoveFrameSourceOffset {
+                source: 0x01.into(),
+                immediate: 4,
+            },
+            MoveTargetFrameOffset {
+                target: 0x02.into(),
+                immediate: -12i32 as Word,
+            },
+        ],
+        registers_pre = [frame_pointer => Processor::FRAME_POINTER, 42 => 0x01],
+        memory_pre = [1337 => frame_pointer - 12],
+        registers_post = [(0x02.into(), 1337)],
+        memory_post = [(frame_pointer + 4, 42)],
+    );
+
+    const TRAP_VECTOR: Address =
+        address_constants::ENTRY_POINT + 100 * Instruction::SIZE as Address;
+    const NUM_USER_MODE_SETUP_INSTRUCTIONS: usize = 9;
+
+    /// Creates a machine that sets up the trap vector and the user memory region, switches into
+    /// user mode and then continues with the given opcodes. The trap handler consists of the
+    /// given kernel opcodes.
+    fn create_machine_in_user_mode(
+        user_memory: std::ops::Range<Address>,
+        user_opcodes: &[Opcode],
+        kernel_opcodes: &[Opcode],
+    ) -> Machine<MockDisplay> {
+        let user_code_start = address_constants::ENTRY_POINT
+            + (NUM_USER_MODE_SETUP_INSTRUCTIONS * Instruction::SIZE) as Address;
+        let mut opcodes = vec![
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: TRAP_VECTOR,
+            },
+            SetTrapVectorSource {
+                source: 0xF0.into(),
+            },
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: user_memory.start,
+            },
+            MoveRegisterImmediate {
+                register: 0xF1.into(),
+                immediate: user_memory.end,
+            },
+            SetUserMemoryStartEnd {
+                start: 0xF0.into(),
+                end: 0xF1.into(),
+            },
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: user_code_start,
+            },
+            SetTrapReturnAddressSource {
+                source: 0xF0.into(),
+            },
+            NoOp {},
+            SystemReturn {},
+        ];
+        assert_eq!(opcodes.len(), NUM_USER_MODE_SETUP_INSTRUCTIONS);
+        opcodes.extend_from_slice(user_opcodes);
+        let mut machine = create_machine_with_opcodes(&opcodes);
+        for (&opcode, address) in kernel_opcodes
+            .iter()
+            .zip((TRAP_VECTOR..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        for _ in 0..NUM_USER_MODE_SETUP_INSTRUCTIONS {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            user_code_start
+        );
+        machine
+    }
+
+    fn user_instruction_address(index: usize) -> Address {
+        address_constants::ENTRY_POINT
+            + ((NUM_USER_MODE_SETUP_INSTRUCTIONS + index) * Instruction::SIZE) as Address
+    }
+
+    const TRAP_INFO_KERNEL: [Opcode; 2] = [
+        GetTrapCauseValue {
+            cause: Register(0xE0),
+            value: Register(0xE1),
+        },
+        GetTrapReturnAddressTarget {
+            target: Register(0xE2),
+        },
+    ];
+
+    fn assert_trap(
+        machine: &mut Machine<MockDisplay>,
+        cause: TrapCause,
+        value: Word,
+        return_address: Address,
+    ) {
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            TRAP_VECTOR
+        );
+        for _ in 0..TRAP_INFO_KERNEL.len() {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(
+            machine.processors[0].registers[Register(0xE0)],
+            cause as Word
+        );
+        assert_eq!(machine.processors[0].registers[Register(0xE1)], value);
+        assert_eq!(
+            machine.processors[0].registers[Register(0xE2)],
+            return_address
+        );
+    }
+
+    #[test]
+    fn system_call_traps_into_kernel_and_returns() {
+        let mut machine = create_machine_in_user_mode(
+            0..Memory::SIZE as Address,
+            &[
+                SystemCallImmediate { immediate: 42 },
+                MoveRegisterImmediate {
+                    register: 0x05.into(),
+                    immediate: 1,
+                },
+            ],
+            &[TRAP_INFO_KERNEL[0], TRAP_INFO_KERNEL[1], SystemReturn {}],
+        );
+        machine.execute_next_instruction();
+        assert_trap(
+            &mut machine,
+            TrapCause::SystemCall,
+            42,
+            user_instruction_address(1),
+        );
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            user_instruction_address(1)
+        );
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x05.into()], 1);
+    }
+
+    #[test]
+    fn trap_raised_in_supervisor_mode_returns_to_supervisor_mode() {
+        let mut machine = create_machine_with_opcodes(&[
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: TRAP_VECTOR,
+            },
+            SetTrapVectorSource {
+                source: 0xF0.into(),
+            },
+            SystemCallImmediate { immediate: 42 },
+            // privileged, so this traps again if the kernel lost its privileges
+            SetTrapVectorSource {
+                source: 0xF0.into(),
+            },
+        ]);
+        for (&opcode, address) in [TRAP_INFO_KERNEL[0], SystemReturn {}]
+            .iter()
+            .zip((TRAP_VECTOR..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        for _ in 0..3 + 2 {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(
+            machine.processors[0].registers[Register(0xE0)],
+            TrapCause::SystemCall as Word
+        );
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            address_constants::ENTRY_POINT + 3 * Instruction::SIZE as Address
+        );
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            address_constants::ENTRY_POINT + 4 * Instruction::SIZE as Address
+        );
+    }
+
+    /// Creates a machine whose cores set up the trap vector and then execute the given opcode.
+    /// The trap handler stores the trap information.
+    fn create_multicore_machine_with_trap_handler(
+        opcode: Opcode,
+        num_cores: Word,
+    ) -> Machine<MockDisplay> {
+        let mut machine = create_multicore_machine(
+            &[
+                MoveRegisterImmediate {
+                    register: 0xF0.into(),
+                    immediate: TRAP_VECTOR,
+                },
+                SetTrapVectorSource {
+                    source: 0xF0.into(),
+                },
+                opcode,
+            ],
+            num_cores,
+        );
+        for (&opcode, address) in TRAP_INFO_KERNEL
+            .iter()
+            .zip((TRAP_VECTOR..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        machine
+    }
+
+    #[test]
+    fn stack_accesses_outside_of_the_own_part_of_the_stack_trap() {
+        let mut machine = create_multicore_machine_with_trap_handler(
+            PopRegister {
+                register: 0x01.into(),
+            },
+            2,
+        );
+        let stack_pointers: Vec<_> = machine
+            .processors
+            .iter()
+            .map(|processor| processor.get_stack_pointer())
+            .collect();
+        assert_eq!(stack_pointers[0], address_constants::STACK_START);
+        for _ in 0..3 * 2 {
+            machine.execute_next_instruction();
+        }
+        for _ in 0..TRAP_INFO_KERNEL.len() * 2 {
+            machine.execute_next_instruction();
+        }
+        // the boot core underflows the stack, the second core pops from the part of the boot core
+        for (processor, stack_pointer) in machine.processors.iter().zip(stack_pointers) {
+            assert_eq!(
+                processor.registers[Register(0xE0)],
+                TrapCause::StackFault as Word
+            );
+            assert_eq!(
+                processor.registers[Register(0xE1)],
+                stack_pointer - Word::SIZE as Address
+            );
+            assert_eq!(
+                processor.registers[Register(0xE2)],
+                address_constants::ENTRY_POINT + 2 * Instruction::SIZE as Address
+            );
+            assert_eq!(processor.get_stack_pointer(), stack_pointer);
+        }
+    }
+
+    #[test]
+    fn leave_without_enter_traps() {
+        let mut machine = create_multicore_machine_with_trap_handler(Leave {}, 1);
+        for _ in 0..3 {
+            machine.execute_next_instruction();
+        }
+        assert_trap(
+            &mut machine,
+            TrapCause::StackFault,
+            (Word::SIZE as Word).wrapping_neg(),
+            address_constants::ENTRY_POINT + 2 * Instruction::SIZE as Address,
+        );
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+    }
+
+    #[test]
+    fn leave_into_the_stack_of_another_core_traps() {
+        let mut machine = create_multicore_machine_with_trap_handler(Leave {}, 2);
+        let stack_pointers: Vec<_> = machine
+            .processors
+            .iter()
+            .map(|processor| processor.get_stack_pointer())
+            .collect();
+        // each core's frame pointer points into the part of the stack of the other core
+        for (processor, &other_stack_pointer) in machine
+            .processors
+            .iter_mut()
+            .zip(stack_pointers.iter().rev())
+        {
+            processor.registers[Processor::FRAME_POINTER] =
+                other_stack_pointer + Word::SIZE as Address;
+        }
+        for _ in 0..(3 + TRAP_INFO_KERNEL.len()) * 2 {
+            machine.execute_next_instruction();
+        }
+        for (processor, (&stack_pointer, &other_stack_pointer)) in machine
+            .processors
+            .iter()
+            .zip(stack_pointers.iter().zip(stack_pointers.iter().rev()))
+        {
+            assert_eq!(
+                processor.registers[Register(0xE0)],
+                TrapCause::StackFault as Word
+            );
+            assert_eq!(processor.registers[Register(0xE1)], other_stack_pointer);
+            assert_eq!(processor.get_stack_pointer(), stack_pointer);
+        }
+    }
+
+    #[test]
+    fn privileged_instruction_in_user_mode_traps() {
+        let mut machine = create_machine_in_user_mode(
+            0..Memory::SIZE as Address,
+            &[SwapFramebuffers {}],
+            &TRAP_INFO_KERNEL,
+        );
+        machine.execute_next_instruction();
+        assert_trap(
+            &mut machine,
+            TrapCause::PrivilegedInstruction,
+            0x0035,
+            user_instruction_address(0),
+        );
+    }
+
+    #[test]
+    fn returning_from_a_trap_stays_in_user_mode() {
+        let mut machine = create_machine_in_user_mode(
+            0..Memory::SIZE as Address,
+            &[SystemCallImmediate { immediate: 0 }, SystemReturn {}],
+            &[TRAP_INFO_KERNEL[0], TRAP_INFO_KERNEL[1], SystemReturn {}],
+        );
+        machine.execute_next_instruction();
+        assert_trap(
+            &mut machine,
+            TrapCause::SystemCall,
+            0,
+            user_instruction_address(1),
+        );
+        machine.execute_next_instruction();
+        // SystemReturn is privileged, so user code cannot use it to switch modes
+        machine.execute_next_instruction();
+        assert_trap(
+            &mut machine,
+            TrapCause::PrivilegedInstruction,
+            0x009F,
+            user_instruction_address(1),
+        );
+    }
+
+    #[test]
+    fn load_outside_of_user_memory_faults_without_side_effects() {
+        let user_memory = address_constants::ENTRY_POINT..address_constants::ENTRY_POINT + 0x1000;
+        let mut machine = create_machine_in_user_mode(
+            user_memory.clone(),
+            &[
+                MoveTargetPointer {
+                    target: 0x01.into(),
+                    pointer: 0x02.into(),
+                },
+                MoveTargetPointer {
+                    target: 0x01.into(),
+                    pointer: 0x03.into(),
+                },
+            ],
+            &TRAP_INFO_KERNEL,
+        );
+        machine.memory.write_data(user_memory.end - 4, 42);
+        machine.processors[0].registers[0x01.into()] = 7;
+        machine.processors[0].registers[0x02.into()] = user_memory.end - 4;
+        machine.processors[0].registers[0x03.into()] = user_memory.end - 2;
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 42);
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 42);
+        assert_trap(
+            &mut machine,
+            TrapCause::ProtectionFault,
+            user_memory.end - 2,
+            user_instruction_address(1),
+        );
+    }
+
+    #[test]
+    fn push_outside_of_user_memory_faults_without_changing_the_stack_pointer() {
+        let mut machine = create_machine_in_user_mode(
+            address_constants::ENTRY_POINT..Memory::SIZE as Address,
+            &[PushImmediate { immediate: 42 }],
+            &TRAP_INFO_KERNEL,
+        );
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].get_stack_pointer(),
+            address_constants::STACK_START
+        );
+        assert_trap(
+            &mut machine,
+            TrapCause::ProtectionFault,
+            address_constants::STACK_START,
+            user_instruction_address(0),
+        );
+    }
+
+    #[test]
+    fn executing_code_outside_of_user_memory_faults() {
+        let user_memory = user_instruction_address(0)..user_instruction_address(0) + 0x1000;
+        let target = user_memory.end;
+        let mut machine = create_machine_in_user_mode(
+            user_memory,
+            &[JumpImmediate { immediate: target }],
+            &TRAP_INFO_KERNEL,
+        );
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            target
+        );
+        machine.execute_next_instruction();
+        assert_trap(&mut machine, TrapCause::ProtectionFault, target, target);
+    }
+
+    const PAGE_DIRECTORY: Address = 0x80_0000;
+    const NUM_PAGING_SETUP_INSTRUCTIONS: usize = 5;
+
+    /// Maps the page at the virtual address to the physical frame. The page table for each
+    /// directory entry is placed behind the page directory.
+    fn map_page(
+        memory: &mut Memory,
+        directory: Address,
+        virtual_address: Address,
+        frame: Address,
+        flags: PageFlags,
+    ) {
+        let directory_index = virtual_address >> 22;
+        let table_index = (virtual_address >> 12) & 0x3FF;
+        let table = directory + (directory_index + 1) * PAGE_SIZE as Address;
+        memory.write_data(
+            directory + directory_index * Word::SIZE as Address,
+            table | PageFlags::PRESENT.bits(),
+        );
+        memory.write_data(
+            table + table_index * Word::SIZE as Address,
+            frame | (flags | PageFlags::PRESENT).bits(),
+        );
+    }
+
+    /// Maps the pages containing the code and the trap handler to the same physical addresses.
+    fn identity_map_code(memory: &mut Memory, directory: Address) {
+        let first_page = address_constants::ENTRY_POINT & !(PAGE_SIZE as Address - 1);
+        for page in (first_page..TRAP_VECTOR + PAGE_SIZE as Address).step_by(PAGE_SIZE) {
+            map_page(
+                memory,
+                directory,
+                page,
+                page,
+                PageFlags::READ | PageFlags::EXECUTE,
+            );
+        }
+    }
+
+    /// Creates a machine that identity maps its code, sets up the trap vector, enables paging
+    /// and then continues with the given opcodes. Additional pages are mapped according to
+    /// `mappings` (virtual address, physical frame, flags).
+    fn create_machine_with_paging(
+        mappings: &[(Address, Address, PageFlags)],
+        opcodes: &[Opcode],
+    ) -> Machine<MockDisplay> {
+        let mut all_opcodes = vec![
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: TRAP_VECTOR,
+            },
+            SetTrapVectorSource {
+                source: 0xF0.into(),
+            },
+            MoveRegisterImmediate {
+                register: 0xF0.into(),
+                immediate: PAGE_DIRECTORY,
+            },
+            SetPageTableBaseSource {
+                source: 0xF0.into(),
+            },
+            EnablePaging {},
+        ];
+        assert_eq!(all_opcodes.len(), NUM_PAGING_SETUP_INSTRUCTIONS);
+        all_opcodes.extend_from_slice(opcodes);
+        let mut machine = create_machine_with_opcodes(&all_opcodes);
+        identity_map_code(&mut machine.memory, PAGE_DIRECTORY);
+        for &(virtual_address, frame, flags) in mappings {
+            map_page(
+                &mut machine.memory,
+                PAGE_DIRECTORY,
+                virtual_address,
+                frame,
+                flags,
+            );
+        }
+        for (&opcode, address) in TRAP_INFO_KERNEL
+            .iter()
+            .zip((TRAP_VECTOR..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        for _ in 0..NUM_PAGING_SETUP_INSTRUCTIONS {
+            machine.execute_next_instruction();
+        }
+        machine
+    }
+
+    fn paging_instruction_address(index: usize) -> Address {
+        address_constants::ENTRY_POINT
+            + ((NUM_PAGING_SETUP_INSTRUCTIONS + index) * Instruction::SIZE) as Address
+    }
+
+    #[test]
+    fn paging_translates_memory_accesses() {
+        let read_write = PageFlags::READ | PageFlags::WRITE;
+        let mut machine = create_machine_with_paging(
+            &[
+                (0xC000_0000, 0xE0_0000, read_write),
+                (0xC000_1000, 0xD0_0000, read_write),
+            ],
+            &[
+                MoveRegisterImmediate {
+                    register: 0x01.into(),
+                    immediate: 42,
+                },
+                MoveAddressRegister {
+                    target_address: 0xC000_0010,
+                    register: 0x01.into(),
+                },
+                MoveRegisterImmediate {
+                    register: 0x02.into(),
+                    immediate: 0xC000_0FFC,
+                },
+                MoveRegisterImmediate {
+                    register: 0x03.into(),
+                    immediate: 0xAB,
+                },
+                MoveRegisterImmediate {
+                    register: 0x04.into(),
+                    immediate: 8,
+                },
+                // the block crosses a page boundary into a different physical frame
+                MemoryFillDestinationValueLength {
+                    destination: 0x02.into(),
+                    value: 0x03.into(),
+                    length: 0x04.into(),
+                },
+            ],
+        );
+        for _ in 0..6 {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(machine.memory.read_data(0xE0_0010), 42);
+        assert_eq!(machine.memory.read_data(0xE0_0FFC), 0xABAB_ABAB);
+        assert_eq!(machine.memory.read_data(0xD0_0000), 0xABAB_ABAB);
+        assert_eq!(machine.memory.read_data(0xE0_1000), 0);
+    }
+
+    #[test]
+    fn stack_is_accessed_through_paging() {
+        let mut machine = create_machine_with_paging(
+            &[(0xC000_0000, 0xE0_0000, PageFlags::READ | PageFlags::WRITE)],
+            &[
+                PushImmediate { immediate: 42 },
+                PopRegister {
+                    register: 0x01.into(),
+                },
+                CallImmediate {
+                    immediate: paging_instruction_address(4),
+                },
+                NoOp {},
+                Return {},
+            ],
+        );
+        // the virtual stack lies outside of the physical stack region
+        machine.processors[0].set_stack_pointer(0xC000_0000);
+        machine.execute_next_instruction();
+        assert_eq!(machine.memory.read_data(0xE0_0000), 42);
+        assert_eq!(machine.processors[0].get_stack_pointer(), 0xC000_0004);
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 42);
+        assert_eq!(machine.processors[0].get_stack_pointer(), 0xC000_0000);
+
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            paging_instruction_address(4)
+        );
+        assert_eq!(machine.processors[0].get_stack_pointer(), 0xC000_0004);
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            paging_instruction_address(3)
+        );
+        assert_eq!(machine.processors[0].get_stack_pointer(), 0xC000_0000);
+    }
+
+    #[test]
+    fn access_to_unmapped_page_faults_without_side_effects() {
+        let mut machine = create_machine_with_paging(
+            &[],
+            &[MoveTargetPointer {
+                target: 0x01.into(),
+                pointer: 0x02.into(),
+            }],
+        );
+        machine.processors[0].registers[0x01.into()] = 7;
+        machine.processors[0].registers[0x02.into()] = 0xC000_0000;
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 7);
+        assert_trap(
+            &mut machine,
+            TrapCause::PageFault,
+            0xC000_0000,
+            paging_instruction_address(0),
+        );
+    }
+
+    #[test]
+    fn write_to_read_only_page_faults() {
+        let mut machine = create_machine_with_paging(
+            &[(0xC000_0000, 0xE0_0000, PageFlags::READ)],
+            &[
+                MoveTargetPointer {
+                    target: 0x01.into(),
+                    pointer: 0x02.into(),
+                },
+                MovePointerSource {
+                    pointer: 0x02.into(),
+                    source: 0x01.into(),
+                },
+            ],
+        );
+        machine.memory.write_data(0xE0_0004, 42);
+        machine.processors[0].registers[0x02.into()] = 0xC000_0004;
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 42);
+        machine.execute_next_instruction();
+        assert_trap(
+            &mut machine,
+            TrapCause::PageFault,
+            0xC000_0004,
+            paging_instruction_address(1),
+        );
+    }
+
+    #[test]
+    fn instruction_cache_stays_valid_when_switching_address_spaces() {
+        const SECOND_PAGE_DIRECTORY: Address = 0xA0_0000;
+        const CODE_PAGE: Address = 0x40_0000;
+        const FIRST_FRAME: Address = 0xE0_0000;
+        const SECOND_FRAME: Address = 0xD0_0000;
+
+        let mut machine = create_machine_with_paging(
+            &[(CODE_PAGE, FIRST_FRAME, PageFlags::EXECUTE)],
+            &[
+                MoveRegisterImmediate {
+                    register: 0x01.into(),
+                    immediate: SECOND_PAGE_DIRECTORY,
+                },
+                JumpImmediate {
+                    immediate: CODE_PAGE,
+                },
+            ],
+        );
+        identity_map_code(&mut machine.memory, SECOND_PAGE_DIRECTORY);
+        map_page(
+            &mut machine.memory,
+            SECOND_PAGE_DIRECTORY,
+            CODE_PAGE,
+            SECOND_FRAME,
+            PageFlags::EXECUTE,
+        );
+        let frames = [
+            (
+                FIRST_FRAME,
+                [
+                    MoveRegisterImmediate {
+                        register: 0x05.into(),
+                        immediate: 1,
+                    },
+                    SetPageTableBaseSource {
+                        source: 0x01.into(),
+                    },
+                    HaltAndCatchFire {},
+                ],
+            ),
+            (
+                SECOND_FRAME,
+                [
+                    HaltAndCatchFire {},
+                    HaltAndCatchFire {},
+                    MoveRegisterImmediate {
+                        register: 0x05.into(),
+                        immediate: 2,
+                    },
+                ],
+            ),
+        ];
+        for (frame, opcodes) in frames {
+            for (&opcode, address) in opcodes.iter().zip((frame..).step_by(Instruction::SIZE)) {
+                machine.memory.write_opcode(address, opcode);
+            }
+        }
+        machine.generate_instruction_cache();
+
+        for _ in 0..3 {
+            machine.execute_next_instruction();
+        }
+        assert_eq!(machine.processors[0].registers[0x05.into()], 1);
+        // the same virtual address now refers to the code in the second frame
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x05.into()], 2);
+        assert_eq!(
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
+            CODE_PAGE + 3 * Instruction::SIZE as Address
+        );
+    }
+
+    create_test!(
+        compare_and_swap_replaces_expected_value,
+        setup = {
+            let address = 0xF0;
+        },
+        opcodes = &[CompareAndSwapTargetPointerExpectedDesired {
+            target: 0x01.into(),
+            pointer: 0x02.into(),
+            expected: 0x03.into(),
+            desired: 0x04.into(),
+        }],
+        registers_pre = [address => 0x02, 10 => 0x03, 20 => 0x04],
+        memory_pre = [10 => address],
+        registers_post = [(0x01.into(), 10)],
+        memory_post = [(address, 20)],
+        flags_post = [(Zero, true)],
+    );
+
+    create_test!(
+        compare_and_swap_keeps_unexpected_value,
+        setup = {
+            let address = 0xF0;
+        },
+        opcodes = &[CompareAndSwapTargetPointerExpectedDesired {
+            target: 0x01.into(),
+            pointer: 0x02.into(),
+            expected: 0x03.into(),
+            desired: 0x04.into(),
+        }],
+        registers_pre = [address => 0x02, 11 => 0x03, 20 => 0x04],
+        memory_pre = [10 => address],
+        registers_post = [(0x01.into(), 10)],
+        memory_post = [(address, 10)],
+        flags_post = [(Zero, false)],
+    );
+
+    pub(crate) fn create_multicore_machine(
+        opcodes: &[Opcode],
+        num_cores: Word,
+    ) -> Machine<MockDisplay> {
+        create_multicore_machine_with_profile(opcodes, num_cores, MachineProfile::Uniform)
+    }
+
+    fn create_multicore_machine_with_profile(
+        opcodes: &[Opcode],
+        num_cores: Word,
+        profile: MachineProfile,
+    ) -> Machine<MockDisplay> {
+        let mut machine = Machine::new(create_mock_periphery(), false, profile, num_cores);
+        for (&opcode, address) in opcodes
+            .iter()
+            .zip((address_constants::ENTRY_POINT..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        machine
+    }
+
+    #[test]
+    fn cores_are_scheduled_round_robin() {
+        let mut machine = create_multicore_machine(
+            &[
+                GetCoreIdTarget {
+                    target: 0x01.into(),
+                },
+                GetNumCoresTarget {
+                    target: 0x02.into(),
+                },
+            ],
+            3,
+        );
+        for core_id in 0..3 {
+            machine.execute_next_instruction();
+            assert_eq!(
+                machine.processors[core_id].registers[0x01.into()],
+                core_id as Word
+            );
+            assert_eq!(machine.processors[core_id].registers[0x02.into()], 0);
+        }
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x02.into()], 3);
+        assert_eq!(machine.processors[1].registers[0x02.into()], 0);
+        assert_ne!(
+            machine.processors[0].get_stack_pointer(),
+            machine.processors[1].get_stack_pointer()
+        );
+    }
+
+    #[test]
+    fn fetch_and_add_on_shared_memory() {
+        let address = 0xF0;
+        let mut machine = create_multicore_machine(
+            &[FetchAndAddTargetPointerSource {
+                target: 0x01.into(),
+                pointer: 0x02.into(),
+                source: 0x03.into(),
+            }],
+            2,
+        );
+        for processor in &mut machine.processors {
+            processor.registers[0x02.into()] = address;
+            processor.registers[0x03.into()] = 5;
+        }
+        machine.memory.write_data(address, 1);
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 1);
+        assert_eq!(machine.processors[1].registers[0x01.into()], 6);
+        assert_eq!(machine.memory.read_data(address), 11);
+    }
+
+    #[test]
+    fn waiting_core_resumes_after_wake_up() {
+        let secondary_entry_point =
+            address_constants::ENTRY_POINT + 0x100 * Instruction::SIZE as Address;
+        let mut machine = create_multicore_machine(
+            &[
+                NoOp {},
+                WakeUpCoreSource {
+                    source: 0x01.into(),
+                },
+            ],
+            2,
+        );
+        machine.processors[0].registers[0x01.into()] = 1;
+        for (&opcode, address) in [
+            WaitForWakeUp {},
+            MoveRegisterImmediate {
+                register: 0x02.into(),
+                immediate: 42,
+            },
+        ]
+        .iter()
+        .zip((secondary_entry_point..).step_by(Instruction::SIZE))
+        {
+            machine.memory.write_opcode(address, opcode);
+        }
+        machine.generate_instruction_cache();
+        machine.processors[1].set_instruction_pointer(secondary_entry_point);
+
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert!(machine.processors[1].is_waiting_for_wake_up());
+        machine.execute_next_instruction(); // wakes up the secondary core
+        assert!(!machine.processors[1].is_waiting_for_wake_up());
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[1].registers[0x02.into()], 42);
+    }
+
+    #[test]
+    fn waiting_cores_stay_in_step_with_the_machine_clock() {
+        let profile = MachineProfile::Realistic;
+        let secondary_entry_point =
+            address_constants::ENTRY_POINT + 0x100 * Instruction::SIZE as Address;
+        let mut machine = create_multicore_machine_with_profile(
+            &[
+                NoOp {},
+                DivmodTargetModLhsRhs {
+                    result: 0x01.into(),
+                    remainder: 0x02.into(),
+                    lhs: 0x03.into(),
+                    rhs: 0x04.into(),
+                },
+            ],
+            2,
+            profile,
+        );
+        machine
+            .memory
+            .write_opcode(secondary_entry_point, WaitForWakeUp {});
+        machine.generate_instruction_cache();
+        machine.processors[1].set_instruction_pointer(secondary_entry_point);
+        let basic_cycles = profile.cycles(CostClass::Basic) as u64;
+        let divide_cycles = profile.cycles(CostClass::Divide) as u64;
+
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert!(machine.processors[1].is_waiting_for_wake_up());
+        assert_eq!(machine.get_cycle_count(), basic_cycles);
+
+        // the round takes as long as the division, also for the waiting core
+        machine.execute_next_instruction();
+        assert_eq!(machine.get_cycle_count(), basic_cycles + divide_cycles);
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[1].get_cycle_count(),
+            basic_cycles + divide_cycles
+        );
+    }
+
+    create_test!(
+        get_random_word_from_seeded_generator,
+        setup = {
+            let mut random = Random::new(RANDOM_SEED);
+            let first_word = random.next_word();
+            let second_word = random.next_word();
+        },
+        opcodes = &[
+            GetRandomTarget {
+                target: 0x01.into()
+            },
+            GetRandomTarget {
+                target: 0x02.into()
+            },
+        ],
+        registers_post = [(0x01.into(), first_word), (0x02.into(), second_word)],
+    );
+
+    create_test!(
+        poll_date_time_in_local_timezone,
+        opcodes = &[
+            PollDateTime {
+                year: 0x01.into(),
+                month: 0x02.into(),
+                day: 0x03.into(),
+                hour: 0x04.into(),
+                minute: 0x05.into(),
+                second: 0x06.into(),
+            },
+            GetWeekdayTimezoneOffset {
+                weekday: 0x07.into(),
+                offset: 0x08.into(),
+            },
+        ],
+        registers_post = [
+            (0x01.into(), 2024),
+            (0x02.into(), 3),
+            (0x03.into(), 1),
+            (0x04.into(), 0),
+            (0x05.into(), 30),
+            (0x06.into(), 15),
+            (0x07.into(), 4),
+            (0x08.into(), 3600)
+        ],
+    );
+
+    create_test!(
+        set_negative_timezone_offset,
+        opcodes = &[
+            SetTimezoneOffsetSource {
+                source: 0x01.into()
+            },
+            PollDateTime {
+                year: 0x02.into(),
+                month: 0x03.into(),
+                day: 0x04.into(),
+                hour: 0x05.into(),
+                minute: 0x06.into(),
+                second: 0x07.into(),
+            },
+            GetWeekdayTimezoneOffset {
+                weekday: 0x08.into(),
+                offset: 0x09.into(),
+            },
+        ],
+        registers_pre = [(-5 * 3600) as Word => 0x01],
+        registers_post = [(0x04.into(), 29), (0x05.into(), 18), (0x09.into(), (-5 * 3600) as Word)],
     );
 
-    create_comparison_test!(compare_equal_values, 12, 12, 0, zero = true);
-
     #[test]
-    fn push_and_pop_stack_value() {
-        let mut machine = Machine::new(create_mock_periphery(), false);
-        let source_register = 0xAB.into();
-        let target_register = 0x06.into();
-        let data = 42;
-        machine.processor.registers[source_register] = data;
+    fn poll_mouse_state_and_wheel_delta() {
+        let poll_mouse = PollMouse {
+            x: 0x01.into(),
+            y: 0x02.into(),
+            buttons: 0x03.into(),
+            wheel: 0x04.into(),
+        };
+        let mut machine = create_machine_with_opcodes(&[poll_mouse, poll_mouse, poll_mouse]);
+        machine.periphery.mouse.add_wheel_movement(-1.0);
+        machine.periphery.mouse.add_wheel_movement(-1.5);
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 120);
+        assert_eq!(machine.processors[0].registers[0x02.into()], 340);
         assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START
+            machine.processors[0].registers[0x03.into()],
+            (MouseButtons::LEFT | MouseButtons::MIDDLE).bits()
         );
-        let machine = execute_instruction_with_machine(
-            machine,
-            PushRegister {
-                register: source_register,
+        assert_eq!(machine.processors[0].registers[0x04.into()], -2i32 as Word);
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x04.into()], 0);
+        machine.periphery.mouse.add_wheel_movement(-0.5);
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x04.into()], -1i32 as Word);
+    }
+
+    #[test]
+    fn read_gamepad_buttons_and_axes() {
+        let mut machine = create_machine_with_opcodes(&[
+            IsGamepadConnectedTargetGamepad {
+                target: 0x01.into(),
+                gamepad: 0x00.into(),
             },
-        );
-        assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START + Word::SIZE as Address
-        );
-        assert_eq!(
-            machine.memory.read_data(address_constants::STACK_START),
-            data
-        );
-        let machine = execute_instruction_with_machine(
-            machine,
-            PopRegister {
-                register: target_register,
+            GetGamepadButtonsTargetGamepad {
+                target: 0x02.into(),
+                gamepad: 0x00.into(),
             },
-        );
+            GetGamepadAxisTargetGamepadAxis {
+                target: 0x03.into(),
+                gamepad: 0x00.into(),
+                axis: 0x0A.into(),
+            },
+            GetGamepadAxisTargetGamepadAxis {
+                target: 0x04.into(),
+                gamepad: 0x00.into(),
+                axis: 0x0B.into(),
+            },
+            IsGamepadConnectedTargetGamepad {
+                target: 0x05.into(),
+                gamepad: 0x0C.into(),
+            },
+            GetGamepadButtonsTargetGamepad {
+                target: 0x06.into(),
+                gamepad: 0x0C.into(),
+            },
+        ]);
+        machine.processors[0].registers[0x0A.into()] = GamepadAxis::LeftX as Word;
+        machine.processors[0].registers[0x0B.into()] = GamepadAxis::RightTrigger as Word;
+        machine.processors[0].registers[0x0C.into()] = 1;
+        machine.processors[0].registers[0x06.into()] = 42;
+        for _ in 0..6 {
+            machine.execute_next_instruction();
+        }
+        let registers = &machine.processors[0].registers;
+        assert_eq!(registers[0x01.into()], 1);
         assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START
+            registers[0x02.into()],
+            1 << GamepadButton::FaceDown as Word | 1 << GamepadButton::Start as Word
         );
-        assert_eq!(machine.processor.registers[target_register], data);
+        assert_eq!(registers[0x03.into()], -32767i32 as Word);
+        assert_eq!(registers[0x04.into()], 16384);
+        assert_eq!(registers[0x05.into()], 0);
+        assert_eq!(registers[0x06.into()], 0);
+        assert!(machine.processors[0].get_flag(Flag::Zero));
     }
 
     #[test]
-    fn push_and_pop_immediate() {
-        let machine = Machine::new(create_mock_periphery(), false);
-        let target_register = 0x06.into();
-        let data = 42;
-        assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START
-        );
-        let machine = execute_instruction_with_machine(machine, PushImmediate { immediate: data });
-        assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START + Word::SIZE as Address
-        );
-        assert_eq!(
-            machine.memory.read_data(address_constants::STACK_START),
-            data
-        );
-        let machine = execute_instruction_with_machine(
-            machine,
-            PopRegister {
-                register: target_register,
+    fn regenerating_instruction_cache_picks_up_modified_code() {
+        let mut machine = create_machine_with_opcodes(&[
+            MoveRegisterImmediate {
+                register: 0x01.into(),
+                immediate: 1,
+            },
+            MoveRegisterImmediate {
+                register: 0x02.into(),
+                immediate: 2,
+            },
+        ]);
+        let second_instruction = address_constants::ENTRY_POINT + Instruction::SIZE as Address;
+        machine.memory.write_opcode(
+            second_instruction,
+            MoveRegisterImmediate {
+                register: 0x02.into(),
+                immediate: 42,
             },
         );
-        assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START
-        );
-        assert_eq!(machine.processor.registers[target_register], data);
+        // a write that only covers the last byte of the instruction still has to be noticed
+        let end = second_instruction as usize + Instruction::SIZE;
+        machine
+            .instruction_cache
+            .regenerate(&machine.memory, end - 1..end, machine.profile);
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[0x01.into()], 1);
+        assert_eq!(machine.processors[0].registers[0x02.into()], 42);
     }
 
     #[test]
-    fn push_and_pop_multiple_stack_values() {
-        let values = [1, 4, 5, 42, 2, 3];
-        let mut machine = Machine::new(create_mock_periphery(), false);
-        for (register, value) in (0..).map(Register).zip(values) {
-            machine.processor.registers[register] = value;
-            machine = execute_instruction_with_machine(machine, PushRegister { register });
-            assert_eq!(
-                machine.processor.get_stack_pointer(),
-                address_constants::STACK_START
-                    + (register.0 as Address + 1) * Word::SIZE as Address
-            );
-            assert_eq!(
-                machine.memory.read_data(
-                    address_constants::STACK_START + register.0 as Address * Word::SIZE as Address
-                ),
-                value
-            );
-        }
-        for &value in values.iter().rev() {
-            let target = 0xAB.into();
-            machine = execute_instruction_with_machine(machine, PopRegister { register: target });
-            assert_eq!(machine.processor.registers[target], value);
-        }
+    #[cfg(feature = "debugger")]
+    fn data_accesses_are_recorded() {
+        use crate::mmu::MemoryAccess;
+        use crate::processor::DataAccess;
+
+        let mut machine = create_machine_with_opcodes(&[MoveAddressRegister {
+            register: 0x01.into(),
+            target_address: 0x100,
+        }]);
+        machine.memory.write_data(0x100, 0xAABB_CCDD);
+        machine.processors[0].registers[0x01.into()] = 42;
+        machine.processors[0].data_accesses = Some(Vec::new());
+        machine.processors[0].execute_next_instruction(
+            &mut machine.memory,
+            &mut machine.periphery,
+            &mut machine.instruction_cache,
+        );
         assert_eq!(
-            machine.processor.get_stack_pointer(),
-            address_constants::STACK_START
+            machine.processors[0].data_accesses,
+            Some(vec![DataAccess {
+                range: 0x100..0x104,
+                access: MemoryAccess::Write,
+                old_bytes: vec![0xAA, 0xBB, 0xCC, 0xDD],
+            }])
         );
     }
 
     #[test]
     fn call_and_return() {
-        let mut machine: Machine<MockDisplay> = Machine::new(create_mock_periphery(), false);
+        let mut machine: Machine<MockDisplay> =
+            Machine::new(create_mock_periphery(), false, MachineProfile::Uniform, 1);
         let call_address = address_constants::ENTRY_POINT + 200 * Instruction::SIZE as Address;
         machine.memory.write_opcode(
             address_constants::ENTRY_POINT,
@@ -1667,20 +4303,20 @@ mod tests {
             address_constants::ENTRY_POINT + Instruction::SIZE as Address
         );
         assert_eq!(
-            machine.processor.registers[Processor::INSTRUCTION_POINTER],
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
             call_address
         );
 
         machine.execute_next_instruction(); // write value into register
-        assert_eq!(machine.processor.registers[target_register], value);
+        assert_eq!(machine.processors[0].registers[target_register], value);
         assert_eq!(
-            machine.processor.registers[Processor::INSTRUCTION_POINTER],
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
             call_address + Instruction::SIZE as Address
         );
 
         machine.execute_next_instruction(); // jump back from subroutine
         assert_eq!(
-            machine.processor.registers[Processor::INSTRUCTION_POINTER],
+            machine.processors[0].registers[Processor::INSTRUCTION_POINTER],
             address_constants::ENTRY_POINT + Instruction::SIZE as Address
         );
     }
@@ -2070,6 +4706,237 @@ mod tests {
         false
     );
 
+    macro_rules! create_conditional_comparison_tests {
+        (
+            $move_test_name:ident,
+            $set_test_name:ident,
+            $move_instruction:ident,
+            $set_instruction:ident,
+            $lhs:literal,
+            $rhs:literal,
+            $condition_holds:literal
+        ) => {
+            create_test!(
+                $move_test_name,
+                opcodes = &[
+                    Opcode::CompareTargetLhsRhs {
+                        target: 0.into(),
+                        lhs: 1.into(),
+                        rhs: 2.into(),
+                    },
+                    Opcode::$move_instruction {
+                        target: 0xA.into(),
+                        source: 0xB.into(),
+                        comparison: 0.into(),
+                    },
+                ],
+                registers_pre = [$lhs => 1, $rhs => 2, 13 => 0xA, 42 => 0xB],
+                registers_post = [(0xA.into(), if $condition_holds { 42 } else { 13 })],
+            );
+
+            create_test!(
+                $set_test_name,
+                opcodes = &[
+                    Opcode::CompareTargetLhsRhs {
+                        target: 0.into(),
+                        lhs: 1.into(),
+                        rhs: 2.into(),
+                    },
+                    Opcode::$set_instruction {
+                        target: 0xA.into(),
+                        comparison: 0.into(),
+                    },
+                ],
+                registers_pre = [$lhs => 1, $rhs => 2, 13 => 0xA],
+                registers_post = [(0xA.into(), $condition_holds as Word)],
+            );
+        };
+    }
+
+    create_conditional_comparison_tests!(
+        move_if_equal_that_moves,
+        set_if_equal_that_sets,
+        MoveTargetSourceIfEqual,
+        SetTargetIfEqual,
+        42,
+        42,
+        true
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_equal_that_does_not_move,
+        set_if_equal_that_does_not_set,
+        MoveTargetSourceIfEqual,
+        SetTargetIfEqual,
+        41,
+        42,
+        false
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_greater_than_that_moves,
+        set_if_greater_than_that_sets,
+        MoveTargetSourceIfGreaterThan,
+        SetTargetIfGreaterThan,
+        43,
+        42,
+        true
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_greater_than_that_does_not_move,
+        set_if_greater_than_that_does_not_set,
+        MoveTargetSourceIfGreaterThan,
+        SetTargetIfGreaterThan,
+        42,
+        42,
+        false
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_less_than_that_moves,
+        set_if_less_than_that_sets,
+        MoveTargetSourceIfLessThan,
+        SetTargetIfLessThan,
+        41,
+        42,
+        true
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_less_than_that_does_not_move,
+        set_if_less_than_that_does_not_set,
+        MoveTargetSourceIfLessThan,
+        SetTargetIfLessThan,
+        43,
+        42,
+        false
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_greater_than_or_equal_that_moves,
+        set_if_greater_than_or_equal_that_sets,
+        MoveTargetSourceIfGreaterThanOrEqual,
+        SetTargetIfGreaterThanOrEqual,
+        42,
+        42,
+        true
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_greater_than_or_equal_that_does_not_move,
+        set_if_greater_than_or_equal_that_does_not_set,
+        MoveTargetSourceIfGreaterThanOrEqual,
+        SetTargetIfGreaterThanOrEqual,
+        41,
+        42,
+        false
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_less_than_or_equal_that_moves,
+        set_if_less_than_or_equal_that_sets,
+        MoveTargetSourceIfLessThanOrEqual,
+        SetTargetIfLessThanOrEqual,
+        42,
+        42,
+        true
+    );
+
+    create_conditional_comparison_tests!(
+        move_if_less_than_or_equal_that_does_not_move,
+        set_if_less_than_or_equal_that_does_not_set,
+        MoveTargetSourceIfLessThanOrEqual,
+        SetTargetIfLessThanOrEqual,
+        43,
+        42,
+        false
+    );
+
+    macro_rules! create_conditional_flag_tests {
+        (
+            $move_test_name:ident,
+            $set_test_name:ident,
+            $move_instruction:ident,
+            $set_instruction:ident,
+            $flag:ident = $flag_value:literal,
+            $condition_holds:literal
+        ) => {
+            create_test!(
+                $move_test_name,
+                opcodes = &[Opcode::$move_instruction {
+                    target: 0xA.into(),
+                    source: 0xB.into(),
+                }],
+                registers_pre = [13 => 0xA, 42 => 0xB],
+                flags_pre = [$flag_value => $flag],
+                registers_post = [(0xA.into(), if $condition_holds { 42 } else { 13 })],
+            );
+
+            create_test!(
+                $set_test_name,
+                opcodes = &[Opcode::$set_instruction { target: 0xA.into() }],
+                registers_pre = [13 => 0xA],
+                flags_pre = [$flag_value => $flag],
+                registers_post = [(0xA.into(), $condition_holds as Word)],
+            );
+        };
+    }
+
+    create_conditional_flag_tests!(
+        move_if_zero_that_moves,
+        set_if_zero_that_sets,
+        MoveTargetSourceIfZero,
+        SetTargetIfZero,
+        Zero = true,
+        true
+    );
+
+    create_conditional_flag_tests!(
+        move_if_not_zero_that_does_not_move,
+        set_if_not_zero_that_does_not_set,
+        MoveTargetSourceIfNotZero,
+        SetTargetIfNotZero,
+        Zero = true,
+        false
+    );
+
+    create_conditional_flag_tests!(
+        move_if_carry_that_does_not_move,
+        set_if_carry_that_does_not_set,
+        MoveTargetSourceIfCarry,
+        SetTargetIfCarry,
+        Carry = false,
+        false
+    );
+
+    create_conditional_flag_tests!(
+        move_if_not_carry_that_moves,
+        set_if_not_carry_that_sets,
+        MoveTargetSourceIfNotCarry,
+        SetTargetIfNotCarry,
+        Carry = false,
+        true
+    );
+
+    create_conditional_flag_tests!(
+        move_if_divide_by_zero_that_moves,
+        set_if_divide_by_zero_that_sets,
+        MoveTargetSourceIfDivideByZero,
+        SetTargetIfDivideByZero,
+        DivideByZero = true,
+        true
+    );
+
+    create_conditional_flag_tests!(
+        move_if_not_divide_by_zero_that_does_not_move,
+        set_if_not_divide_by_zero_that_does_not_set,
+        MoveTargetSourceIfNotDivideByZero,
+        SetTargetIfNotDivideByZero,
+        DivideByZero = true,
+        false
+    );
+
     create_test!(
         no_op_does_advance_the_instruction_pointer,
         opcodes = &[NoOp {}],
@@ -2080,7 +4947,6 @@ mod tests {
     );
 
     #[test]
-    #[cfg(feature = "graphics")]
     fn get_keystate() {
         let keycode_register = 0.into();
         let target_register = 1.into();
@@ -2093,26 +4959,32 @@ mod tests {
                 target: target_register,
                 keycode: keycode_register,
             },
+            Opcode::GetKeyState {
+                target: target_register,
+                keycode: keycode_register,
+            },
         ]);
         machine.periphery.keyboard = Keyboard::new(Box::new(|keycode| {
-            if raylib::input::key_from_i32(keycode.try_into().expect("keycode out of range"))
-                .expect("invalid keycode")
-                == raylib::consts::KeyboardKey::KEY_A
-            {
+            if keycode == Key::A {
                 KeyState::Down
             } else {
                 KeyState::Up
             }
         }));
-        machine.processor.registers[keycode_register] = raylib::consts::KeyboardKey::KEY_A as Word;
+        machine.processors[0].registers[keycode_register] = Key::A as Word;
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].registers[target_register], 1);
+        assert!(!machine.processors[0].get_flag(Flag::Zero));
+
+        machine.processors[0].registers[keycode_register] = Key::B as Word;
         machine.execute_next_instruction();
-        assert_eq!(machine.processor.registers[target_register], 1);
-        assert!(!machine.processor.get_flag(Flag::Zero));
+        assert_eq!(machine.processors[0].registers[target_register], 0);
+        assert!(machine.processors[0].get_flag(Flag::Zero));
 
-        machine.processor.registers[keycode_register] = raylib::consts::KeyboardKey::KEY_B as Word;
+        machine.processors[0].registers[keycode_register] = 0xDEAD;
         machine.execute_next_instruction();
-        assert_eq!(machine.processor.registers[target_register], 0);
-        assert!(machine.processor.get_flag(Flag::Zero));
+        assert_eq!(machine.processors[0].registers[target_register], 0);
+        assert!(machine.processors[0].get_flag(Flag::Zero));
     }
 
     create_test!(
@@ -2158,17 +5030,17 @@ mod tests {
         ]);
         machine.execute_next_instruction();
         assert_eq!(
-            machine.processor.registers[0.into()],
+            machine.processors[0].registers[0.into()],
             address_constants::SECOND_FRAMEBUFFER_START
         );
         machine.execute_next_instruction();
         assert_eq!(
-            machine.processor.registers[0.into()],
+            machine.processors[0].registers[0.into()],
             address_constants::SECOND_FRAMEBUFFER_START
         );
         machine.execute_next_instruction();
         assert_eq!(
-            machine.processor.registers[0.into()],
+            machine.processors[0].registers[0.into()],
             address_constants::FIRST_FRAMEBUFFER_START
         );
     }
@@ -2208,4 +5080,105 @@ mod tests {
         registers_pre = [15 => 0, 15 => 1],
         registers_post = [(0.into(), 0), (1.into(), 0)],
     );
+
+    #[test]
+    fn uniform_profile_counts_one_cycle_per_instruction() {
+        let mut machine = create_machine_with_opcodes_and_profile(
+            &[
+                Opcode::MoveRegisterAddress {
+                    register: 0.into(),
+                    source_address: 0,
+                },
+                Opcode::DivmodTargetModLhsRhs {
+                    result: 1.into(),
+                    remainder: 2.into(),
+                    lhs: 3.into(),
+                    rhs: 4.into(),
+                },
+            ],
+            MachineProfile::Uniform,
+        );
+        machine.execute_next_instruction();
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].get_cycle_count(), 2);
+    }
+
+    #[test]
+    fn realistic_profile_charges_memory_accesses_and_divisions() {
+        let profile = MachineProfile::Realistic;
+        let mut machine = create_machine_with_opcodes_and_profile(
+            &[
+                Opcode::MoveRegisterImmediate {
+                    register: 0.into(),
+                    immediate: 42,
+                },
+                Opcode::MoveAddressRegister {
+                    register: 0.into(),
+                    target_address: 0,
+                },
+                Opcode::DivmodTargetModLhsRhs {
+                    result: 1.into(),
+                    remainder: 2.into(),
+                    lhs: 0.into(),
+                    rhs: 0.into(),
+                },
+            ],
+            profile,
+        );
+        machine.execute_next_instruction();
+        assert_eq!(machine.processors[0].get_cycle_count(), 1);
+        machine.execute_next_instruction();
+        let after_store = 1 + profile.cycles(CostClass::Store) as u64;
+        assert_eq!(machine.processors[0].get_cycle_count(), after_store);
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].get_cycle_count(),
+            after_store + profile.cycles(CostClass::Divide) as u64
+        );
+        assert!(profile.cycles(CostClass::Divide) > profile.cycles(CostClass::Multiply));
+        assert!(profile.cycles(CostClass::Load) > profile.cycles(CostClass::Basic));
+    }
+
+    #[test]
+    fn realistic_profile_charges_taken_branches_more_than_not_taken_branches() {
+        let profile = MachineProfile::Realistic;
+        let opcodes = [Opcode::JumpImmediateIfZero {
+            immediate: address_constants::ENTRY_POINT + 42 * Instruction::SIZE as Address,
+        }];
+
+        let mut machine = create_machine_with_opcodes_and_profile(&opcodes, profile);
+        machine.processors[0].set_flag(Flag::Zero, false);
+        machine.execute_next_instruction();
+        let not_taken_cycles = machine.processors[0].get_cycle_count();
+        assert_eq!(not_taken_cycles, profile.cycles(CostClass::Branch) as u64);
+
+        let mut machine = create_machine_with_opcodes_and_profile(&opcodes, profile);
+        machine.processors[0].set_flag(Flag::Zero, true);
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].get_cycle_count(),
+            not_taken_cycles + profile.branch_taken_penalty() as u64
+        );
+        assert!(profile.branch_taken_penalty() > 0);
+    }
+
+    #[test]
+    fn realistic_profile_charges_memory_blocks_per_word() {
+        let profile = MachineProfile::Realistic;
+        let mut machine = create_machine_with_opcodes_and_profile(
+            &[Opcode::MemoryFillDestinationValueLength {
+                destination: 0.into(),
+                value: 1.into(),
+                length: 2.into(),
+            }],
+            profile,
+        );
+        machine.processors[0].registers[0.into()] = 0x100;
+        machine.processors[0].registers[2.into()] = 9;
+        machine.execute_next_instruction();
+        assert_eq!(
+            machine.processors[0].get_cycle_count(),
+            profile.cycles(CostClass::Block) as u64 + 3 * profile.block_cycles_per_word() as u64
+        );
+    }
 }
@@ -0,0 +1,88 @@
+use serde::Serialize;
+
+/// Groups of opcodes that share the same cycle cost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CostClass {
+    /// register-only operations like moves, additions or comparisons
+    Basic,
+    /// reading from memory
+    Load,
+    /// writing into memory
+    Store,
+    /// pushing onto or popping from the stack
+    Stack,
+    Multiply,
+    Divide,
+    /// floating point operations (except for divisions and square roots)
+    Float,
+    /// copying, filling or comparing memory blocks (additional cycles are charged per word)
+    Block,
+    /// atomic read-modify-write operations on memory
+    Atomic,
+    /// unconditional jumps
+    Jump,
+    /// conditional jumps (taking the branch costs extra cycles)
+    Branch,
+    Call,
+    Return,
+    /// accessing the keyboard, the timer or the display
+    Periphery,
+    /// debugging and profiling instructions
+    Debug,
+}
+
+/// Determines how many cycles each instruction takes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MachineProfile {
+    /// Every instruction takes exactly one cycle (regardless of the size of memory blocks).
+    Uniform,
+    /// Memory accesses, multiplications, divisions and taken branches are more expensive than
+    /// register-only operations.
+    #[default]
+    Realistic,
+}
+
+impl MachineProfile {
+    /// Returns the number of cycles of an instruction of the given cost class. For conditional
+    /// jumps, this is the cost of not taking the branch.
+    pub fn cycles(self, cost_class: CostClass) -> u8 {
+        use CostClass::*;
+        match self {
+            MachineProfile::Uniform => 1,
+            MachineProfile::Realistic => match cost_class {
+                Basic => 1,
+                Load => 3,
+                Store => 3,
+                Stack => 3,
+                Multiply => 4,
+                Divide => 24,
+                Float => 4,
+                Block => 3,
+                Atomic => 6,
+                Jump => 2,
+                Branch => 1,
+                Call => 5,
+                Return => 5,
+                Periphery => 2,
+                Debug => 1,
+            },
+        }
+    }
+
+    /// Returns the number of additional cycles a conditional jump takes if the branch is taken.
+    pub fn branch_taken_penalty(self) -> u8 {
+        match self {
+            MachineProfile::Uniform => 0,
+            MachineProfile::Realistic => 2,
+        }
+    }
+
+    /// Returns the number of additional cycles a memory block instruction takes for every
+    /// (started) word of the block.
+    pub fn block_cycles_per_word(self) -> u8 {
+        match self {
+            MachineProfile::Uniform => 0,
+            MachineProfile::Realistic => 1,
+        }
+    }
+}
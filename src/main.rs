@@ -1,17 +1,26 @@
 mod address_constants;
+mod clock;
 mod cursor;
 #[cfg(feature = "debugger")]
 mod debugger;
 mod display;
 mod dumper;
+mod gamepad;
 mod keyboard;
 mod machine;
+mod machine_profile;
 mod memory;
+mod mmu;
+mod mouse;
+mod numbering;
 mod opcodes;
 mod periphery;
 mod processor;
+mod random;
+mod rtc;
 mod terminal;
 mod timer;
+mod trap;
 
 use std::{
     cell::RefCell,
@@ -25,18 +34,27 @@ use std::{
 };
 
 use address_constants::ENTRY_POINT;
+use chrono::{DateTime, FixedOffset, Utc};
 use clap::StructOpt;
+use clock::Clock;
 use cursor::Cursor;
 use display::{Display, DisplayImplementation};
-use keyboard::{KeyState, Keyboard};
+use gamepad::{GamepadAxis, GamepadButton, GamepadScript, Gamepads};
+use keyboard::{Key, KeyState, Keyboard};
 use machine::Machine;
+use machine_profile::MachineProfile;
 use memory::Memory;
+use mmu::{PageFlags, PAGE_SIZE};
+use mouse::{Mouse, MouseButtons, MouseState};
 use num_format::{CustomFormat, ToFormattedString};
 use opcodes::Opcode;
 use periphery::PeripheryImplementation;
 use processor::Processor;
+use random::Random;
+use rtc::{Rtc, TimeSource};
 use serde::{Deserialize, Serialize};
 use timer::Timer;
+use trap::TrapCause;
 
 #[cfg(feature = "graphics")]
 use raylib::prelude::*;
@@ -47,7 +65,7 @@ use display::MockDisplay;
 use crate::{
     cursor::CursorMode,
     opcodes::OpcodeDescription,
-    processor::{Flag, NUM_REGISTERS},
+    processor::{Flag, MAX_NUM_CORES, NUM_REGISTERS},
 };
 
 pub struct Size2D {
@@ -130,6 +148,14 @@ enum Action {
         /// instruction.
         #[clap(short, long, action)]
         exit_on_halt: bool,
+
+        /// The emulated clock frequency in Hz. If omitted, the program runs as fast as the
+        /// host allows.
+        #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+        clock_hz: Option<u64>,
+
+        #[clap(flatten)]
+        machine: MachineArgs,
     },
     /// Emit a sample program as machine code
     Emit {
@@ -140,6 +166,10 @@ enum Action {
     Json {
         /// Output path of the JSON file to be written
         path: Option<PathBuf>,
+
+        /// The machine profile whose cycle costs are written
+        #[clap(long, value_enum, default_value = "realistic")]
+        profile: MachineProfile,
     },
     #[cfg(feature = "debugger")]
     /// Debugs a ROM file (typically *.backseat)
@@ -149,31 +179,102 @@ enum Action {
         /// The path to the font file
         #[clap(long)]
         font_path: Option<String>,
+
+        #[clap(flatten)]
+        machine: MachineArgs,
+
+        #[clap(flatten)]
+        debug: DebugArgs,
     },
 }
 
+/// Options of the debugger
+#[cfg(feature = "debugger")]
+#[derive(clap::Args, Debug)]
+struct DebugArgs {
+    /// The protocol that debugger frontends use (`dap` only supports a single core, only `json`
+    /// supports observers)
+    #[clap(long, value_enum, default_value = "json")]
+    protocol: debugger::DebugProtocol,
+
+    /// The address that the debugger listens on
+    #[clap(long, default_value = "127.0.0.1")]
+    address: std::net::IpAddr,
+
+    /// The port that the debugger listens on (defaults to a fixed port per protocol, 0 picks a
+    /// free port, which is printed as `Debugger-Port:`)
+    #[clap(long)]
+    port: Option<u16>,
+
+    /// The maximum number of bytes that the recorded execution history occupies to be able to
+    /// step backwards, the oldest instructions are dropped first (0 disables the execution
+    /// history)
+    #[clap(long, default_value = "67108864")]
+    history_bytes: usize,
+}
+
+/// Options that configure the emulated machine and its periphery
+#[derive(clap::Args, Debug)]
+struct MachineArgs {
+    /// The machine profile that determines the cycle costs of the instructions
+    #[clap(long, value_enum, default_value = "realistic")]
+    profile: MachineProfile,
+
+    /// The number of cores sharing the memory (each core gets an equal part of the stack)
+    #[clap(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(Word).range(1..=MAX_NUM_CORES as i64)
+    )]
+    cores: Word,
+
+    /// Seed of the random number generator for reproducible runs. If omitted, the
+    /// generator is seeded from the randomness of the host.
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Start the real-time clock at the given time (RFC 3339, e.g. 2000-01-01T00:00:00+01:00)
+    /// instead of the host time. Its offset becomes the timezone offset. The clock advances
+    /// with the executed cycles if a clock frequency is given, otherwise it stays at that
+    /// time.
+    #[clap(long)]
+    rtc_time: Option<DateTime<FixedOffset>>,
+
+    /// Replay the gamepad inputs from the given JSON file instead of using connected gamepads
+    /// (see `GamepadScript` for the format). The times of the script are measured in executed
+    /// cycles at the clock frequency (1 MHz if no clock frequency is given).
+    #[clap(long)]
+    gamepad_script: Option<PathBuf>,
+}
+
 struct RunOptions {
     exit_on_halt: bool,
+    clock_hz: Option<u64>,
+    machine: MachineArgs,
     #[cfg(feature = "debugger")]
-    debug: bool,
+    debug: Option<DebugArgs>,
     font_path: String,
 }
 
 impl RunOptions {
-    fn new(exit_on_halt: bool) -> Self {
+    fn new(exit_on_halt: bool, clock_hz: Option<u64>, machine: MachineArgs) -> Self {
         Self {
             exit_on_halt,
+            clock_hz,
+            machine,
             #[cfg(feature = "debugger")]
-            debug: false,
+            debug: None,
             font_path: DEFAULT_FONT_PATH.into(),
         }
     }
 
     #[cfg(feature = "debugger")]
-    fn new_debug(font_path: Option<String>) -> Self {
+    fn new_debug(font_path: Option<String>, machine: MachineArgs, debug: DebugArgs) -> Self {
         Self {
             exit_on_halt: true,
-            debug: true,
+            clock_hz: None,
+            machine,
+            debug: Some(debug),
             font_path: font_path.unwrap_or(DEFAULT_FONT_PATH.into()),
         }
     }
@@ -189,11 +290,37 @@ struct Args {
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     match args.action {
-        Action::Run { path, exit_on_halt } => run(path.as_deref(), RunOptions::new(exit_on_halt)),
+        Action::Run {
+            path,
+            exit_on_halt,
+            clock_hz,
+            machine,
+        } => run(
+            path.as_deref(),
+            RunOptions::new(exit_on_halt, clock_hz, machine),
+        ),
         Action::Emit { path } => emit(path.as_deref()),
-        Action::Json { path } => print_json(path.as_deref()),
+        Action::Json { path, profile } => print_json(path.as_deref(), profile),
         #[cfg(feature = "debugger")]
-        Action::Debug { path, font_path } => run(path.as_deref(), RunOptions::new_debug(font_path)),
+        Action::Debug {
+            path,
+            font_path,
+            machine,
+            debug,
+        } => {
+            if machine.cores > 1 && !debug.protocol.supports_multiple_cores() {
+                let protocol = clap::ValueEnum::to_possible_value(&debug.protocol).unwrap();
+                return Err(format!(
+                    "--protocol {} does not support multiple cores",
+                    protocol.get_name()
+                )
+                .into());
+            }
+            run(
+                path.as_deref(),
+                RunOptions::new_debug(font_path, machine, debug),
+            )
+        }
     }
 }
 
@@ -215,7 +342,10 @@ enum Constant {
     UnsignedInteger(u64),
 }
 
-fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
+fn print_json(
+    output_filename: Option<&Path>,
+    profile: MachineProfile,
+) -> Result<(), Box<dyn Error>> {
     #[derive(Serialize)]
     struct JsonInfo {
         opcodes: HashMap<&'static str, OpcodeDescription>,
@@ -224,7 +354,7 @@ fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
     }
 
     let json_info = JsonInfo {
-        opcodes: Opcode::as_hashmap(),
+        opcodes: Opcode::as_hashmap(profile),
         constants: HashMap::from([
             (
                 "ENTRY_POINT",
@@ -243,6 +373,10 @@ fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
                 "STACK_POINTER",
                 Constant::Register(Processor::STACK_POINTER.0.into()),
             ),
+            (
+                "FRAME_POINTER",
+                Constant::Register(Processor::FRAME_POINTER.0.into()),
+            ),
             (
                 "STACK_START",
                 Constant::Address(address_constants::STACK_START),
@@ -303,6 +437,71 @@ fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
                 "TERMINAL_CURSOR_MODE_INVISIBLE",
                 Constant::UnsignedInteger(CursorMode::Invisible as _),
             ),
+            (
+                "TRAP_CAUSE_SYSTEM_CALL",
+                Constant::UnsignedInteger(TrapCause::SystemCall as _),
+            ),
+            (
+                "TRAP_CAUSE_PRIVILEGED_INSTRUCTION",
+                Constant::UnsignedInteger(TrapCause::PrivilegedInstruction as _),
+            ),
+            (
+                "TRAP_CAUSE_PROTECTION_FAULT",
+                Constant::UnsignedInteger(TrapCause::ProtectionFault as _),
+            ),
+            (
+                "TRAP_CAUSE_PAGE_FAULT",
+                Constant::UnsignedInteger(TrapCause::PageFault as _),
+            ),
+            (
+                "TRAP_CAUSE_STACK_FAULT",
+                Constant::UnsignedInteger(TrapCause::StackFault as _),
+            ),
+            ("PAGE_SIZE", Constant::UnsignedInteger(PAGE_SIZE as _)),
+            (
+                "PAGE_FLAG_PRESENT",
+                Constant::UnsignedInteger(PageFlags::PRESENT.bits() as _),
+            ),
+            (
+                "PAGE_FLAG_READ",
+                Constant::UnsignedInteger(PageFlags::READ.bits() as _),
+            ),
+            (
+                "PAGE_FLAG_WRITE",
+                Constant::UnsignedInteger(PageFlags::WRITE.bits() as _),
+            ),
+            (
+                "PAGE_FLAG_EXECUTE",
+                Constant::UnsignedInteger(PageFlags::EXECUTE.bits() as _),
+            ),
+            (
+                "PAGE_FLAG_USER",
+                Constant::UnsignedInteger(PageFlags::USER.bits() as _),
+            ),
+            (
+                "MOUSE_BUTTON_LEFT",
+                Constant::UnsignedInteger(MouseButtons::LEFT.bits() as _),
+            ),
+            (
+                "MOUSE_BUTTON_RIGHT",
+                Constant::UnsignedInteger(MouseButtons::RIGHT.bits() as _),
+            ),
+            (
+                "MOUSE_BUTTON_MIDDLE",
+                Constant::UnsignedInteger(MouseButtons::MIDDLE.bits() as _),
+            ),
+            (
+                "NUM_GAMEPADS",
+                Constant::UnsignedInteger(gamepad::NUM_GAMEPADS as _),
+            ),
+            (
+                "GAMEPAD_AXIS_MAX",
+                Constant::UnsignedInteger(gamepad::AXIS_MAX as _),
+            ),
+            (
+                "RANDOM_WORD_MAX",
+                Constant::UnsignedInteger(random::MAX_RANDOM_WORD as _),
+            ),
             (
                 "DISPLAY_WIDTH",
                 Constant::UnsignedInteger(display::WIDTH as _),
@@ -311,7 +510,10 @@ fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
                 "DISPLAY_HEIGHT",
                 Constant::UnsignedInteger(display::HEIGHT as _),
             ),
-        ]),
+        ])
+        .into_iter()
+        .chain(numbering_constants())
+        .collect(),
         flags: Flag::as_hashmap(),
     };
     let json_string = serde_json::to_string_pretty(&json_info).unwrap();
@@ -323,6 +525,20 @@ fn print_json(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Publishes the numbering of the keys and of the gamepad buttons and axes.
+fn numbering_constants() -> impl Iterator<Item = (&'static str, Constant)> {
+    let keys = Key::ALL
+        .iter()
+        .map(|&key| (key.json_name(), Constant::UnsignedInteger(key as _)));
+    let buttons = GamepadButton::ALL
+        .iter()
+        .map(|&button| (button.json_name(), Constant::UnsignedInteger(button as _)));
+    let axes = GamepadAxis::ALL
+        .iter()
+        .map(|&axis| (axis.json_name(), Constant::UnsignedInteger(axis as _)));
+    keys.chain(buttons).chain(axes)
+}
+
 fn emit(output_filename: Option<&Path>) -> Result<(), Box<dyn Error>> {
     let opcodes = &[
         Opcode::MoveRegisterImmediate {
@@ -400,14 +616,28 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
 
     #[cfg(feature = "graphics")]
     let raylib_handle_copy = Rc::clone(&raylib_handle);
+    #[cfg(feature = "graphics")]
+    let raylib_mouse_handle = Rc::clone(&raylib_handle);
+    #[cfg(feature = "graphics")]
+    let raylib_gamepad_handle = Rc::clone(&raylib_handle);
+
+    let gamepad_script = match &options.machine.gamepad_script {
+        Some(path) => Some(GamepadScript::from_json(
+            &std::fs::read_to_string(path)?,
+            options
+                .clock_hz
+                .unwrap_or(GamepadScript::DEFAULT_CYCLES_PER_SECOND),
+        )?),
+        None => None,
+    };
     let periphery = PeripheryImplementation {
         timer: Timer::new(ms_since_epoch),
         keyboard: Keyboard::new(Box::new(move |key| {
             #[cfg(feature = "graphics")]
-            match raylib_handle_copy.borrow().is_key_down(
-                raylib::input::key_from_i32(key.try_into().expect("keycode out of range"))
-                    .expect("invalid keycode"),
-            ) {
+            match raylib_handle_copy
+                .borrow()
+                .is_key_down(keyboard::get_raylib_key(key))
+            {
                 true => KeyState::Down,
                 false => KeyState::Up,
             }
@@ -415,6 +645,43 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
             #[cfg(not(feature = "graphics"))]
             KeyState::Up
         })),
+        mouse: Mouse::new(Box::new(move || {
+            #[cfg(feature = "graphics")]
+            {
+                let handle = raylib_mouse_handle.borrow();
+                let position = handle.get_mouse_position();
+                let (x, y) = display::window_to_display_position(position.x, position.y);
+                let mut buttons = MouseButtons::empty();
+                for (raylib_button, button) in [
+                    (MouseButton::MOUSE_LEFT_BUTTON, MouseButtons::LEFT),
+                    (MouseButton::MOUSE_RIGHT_BUTTON, MouseButtons::RIGHT),
+                    (MouseButton::MOUSE_MIDDLE_BUTTON, MouseButtons::MIDDLE),
+                ] {
+                    buttons.set(button, handle.is_mouse_button_down(raylib_button));
+                }
+                MouseState { x, y, buttons }
+            }
+
+            #[cfg(not(feature = "graphics"))]
+            MouseState::default()
+        })),
+        gamepads: Gamepads::new(match gamepad_script {
+            Some(script) => {
+                Box::new(move |gamepad, cycle_count| script.state_at(gamepad, cycle_count))
+            }
+            None => Box::new(move |gamepad, _| {
+                #[cfg(feature = "graphics")]
+                {
+                    gamepad::get_raylib_gamepad_state(&raylib_gamepad_handle.borrow(), gamepad)
+                }
+
+                #[cfg(not(feature = "graphics"))]
+                {
+                    let _ = gamepad;
+                    None
+                }
+            }),
+        }),
         #[cfg(feature = "graphics")]
         display: DisplayImplementation::new(&mut raylib_handle.borrow_mut(), &raylib_thread),
 
@@ -425,13 +692,28 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
             visible: true,
             time_of_next_toggle: Instant::now() + Cursor::TOGGLE_INTERVAL,
         },
+        random: options
+            .machine
+            .seed
+            .map_or_else(Random::with_host_entropy, Random::new),
+        rtc: create_rtc(&options),
     };
 
-    let mut machine = Machine::new(periphery, options.exit_on_halt);
+    let mut machine = Machine::new(
+        periphery,
+        options.exit_on_halt,
+        options.machine.profile,
+        options.machine.cores,
+    );
 
     #[cfg(feature = "debugger")]
-    if options.debug {
-        machine.start_debugger();
+    if let Some(debug) = &options.debug {
+        let port = debug.port.unwrap_or(debug.protocol.default_port());
+        machine.start_debugger(debugger::DebuggerOptions {
+            protocol: debug.protocol,
+            address: std::net::SocketAddr::new(debug.address, port),
+            history_bytes: debug.history_bytes,
+        });
     }
 
     match rom_filename {
@@ -453,8 +735,13 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
         next_clock_frequency_render: ms_since_epoch() + 1000,
         num_clock_frequency_accumulations: 0,
         clock_frequency_average: 0,
+        target_clock_frequency: options.clock_hz,
     };
 
+    let mut clock = options
+        .clock_hz
+        .map(|frequency| Clock::new(frequency, ms_since_epoch(), 0));
+
     let custom_number_format = CustomFormat::builder().separator(" ").build()?;
 
     while {
@@ -479,6 +766,11 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
             &custom_number_format,
         );
 
+        if let Some(clock) = &mut clock {
+            run_clocked(current_time, clock, &mut machine);
+            continue;
+        }
+
         let num_cycles = match (
             time_measurements.clock_frequency_average,
             current_time > time_measurements.next_render_time,
@@ -498,7 +790,11 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
 
         // Update GUI after each cycle in debug mode.
         #[cfg(feature = "debugger")]
-        let num_cycles = if options.debug { 1 } else { num_cycles };
+        let num_cycles = if options.debug.is_some() {
+            1
+        } else {
+            num_cycles
+        };
 
         for _ in 0..num_cycles {
             execute_next_instruction(&mut machine);
@@ -506,7 +802,7 @@ fn run(rom_filename: Option<&Path>, options: RunOptions) -> Result<(), Box<dyn E
     }
 
     #[cfg(feature = "debugger")]
-    if options.debug {
+    if options.debug.is_some() {
         machine.stop_debugger();
     }
 
@@ -555,6 +851,36 @@ where
     }
 }
 
+fn create_rtc(options: &RunOptions) -> Rtc {
+    let Some(start) = options.machine.rtc_time else {
+        return Rtc::host();
+    };
+    let source = match options.clock_hz {
+        Some(cycles_per_second) => TimeSource::Virtual {
+            start: start.with_timezone(&Utc),
+            cycles_per_second,
+        },
+        None => TimeSource::Fixed(start.with_timezone(&Utc)),
+    };
+    Rtc::new(source, *start.offset())
+}
+
+/// Executes all cycles that are due according to the clock or sleeps if there are none.
+fn run_clocked<Display>(current_time: u64, clock: &mut Clock, machine: &mut Machine<Display>)
+where
+    Display: crate::Display + 'static,
+{
+    let start_cycle_count = machine.get_cycle_count();
+    let cycles_due = clock.cycles_due(current_time, start_cycle_count);
+    if cycles_due == 0 || machine.is_halted() {
+        std::thread::sleep(Duration::from_millis(1));
+        return;
+    }
+    while machine.get_cycle_count() - start_cycle_count < cycles_due && !machine.is_halted() {
+        machine.execute_next_instruction();
+    }
+}
+
 fn opcodes_to_machine_code(instructions: &[Opcode]) -> Vec<u8> {
     instructions
         .iter()
@@ -576,6 +902,7 @@ struct TimeMeasurements {
     next_clock_frequency_render: u64,
     num_clock_frequency_accumulations: u64,
     clock_frequency_average: u64,
+    target_clock_frequency: Option<u64>,
 }
 
 #[cfg(feature = "graphics")]
@@ -591,10 +918,16 @@ fn render_if_needed(
     if current_time >= time_measurements.next_render_time {
         time_measurements.next_render_time += 1000 / TARGET_FPS;
 
+        // the wheel movement is updated once per frame
+        machine
+            .periphery
+            .mouse
+            .add_wheel_movement(raylib_handle.get_mouse_wheel_move());
+
         let mut draw_handle = raylib_handle.begin_drawing(thread);
         render(&mut draw_handle, machine, font);
 
-        let current_cycle_count = machine.processor.get_cycle_count();
+        let current_cycle_count = machine.get_cycle_count();
         if current_time != time_measurements.last_render_time {
             calculate_clock_frequency(current_time, time_measurements, current_cycle_count);
             draw_clock_frequency(
@@ -646,16 +979,51 @@ fn draw_clock_frequency(
     draw_handle: &mut RaylibDrawHandle,
     font: &Font,
 ) {
+    let actual_frequency = (time_measurements.clock_frequency_average / 1000)
+        .to_formatted_string(custom_number_format);
+    let (text, offset) = match time_measurements.target_clock_frequency {
+        Some(target_frequency) => (
+            format!(
+                "{} / {} kHz",
+                actual_frequency,
+                (target_frequency / 1000).to_formatted_string(custom_number_format)
+            ),
+            350.0,
+        ),
+        None => (format!("{} kHz", actual_frequency), 200.0),
+    };
     draw_handle.draw_text_ex(
         font,
-        &*format!(
-            "{} kHz",
-            (time_measurements.clock_frequency_average / 1000)
-                .to_formatted_string(custom_number_format)
-        ),
-        Vector2::new(SCREEN_SIZE.width as f32 - 200.0, 100.0),
+        &*text,
+        Vector2::new(SCREEN_SIZE.width as f32 - offset, 100.0),
         30.0,
         1.0,
         Color::WHITE,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_json_publishes_random_word_max() {
+        let path = std::env::temp_dir().join(format!("print_json_{}.json", std::process::id()));
+        print_json(Some(&path), MachineProfile::Realistic).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            json["constants"]["RANDOM_WORD_MAX"]["UnsignedInteger"],
+            Word::MAX
+        );
+        assert!(json["opcodes"].get("GetRandomTarget").is_some());
+    }
+
+    #[test]
+    fn zero_clock_frequency_is_rejected() {
+        let parse = |clock_hz| Args::try_parse_from(["bss", "run", "--clock-hz", clock_hz]);
+        assert!(parse("0").is_err());
+        assert!(parse("1").is_ok());
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use crate::{opcodes::Opcode, Address, Byte, Halfword, Instruction, Size, Word};
 
 pub struct Memory {
@@ -21,6 +23,14 @@ impl Memory {
         &mut self.data
     }
 
+    /// Returns the index range of the block with the given start address and length, or
+    /// `None` if the block does not fit into the memory.
+    pub fn block_range(address: Address, length: Word) -> Option<Range<usize>> {
+        let start = address as usize;
+        let end = start.checked_add(length as usize)?;
+        (end <= Self::SIZE).then_some(start..end)
+    }
+
     pub fn read_opcode(
         &self,
         address: Address,
@@ -133,4 +143,15 @@ mod tests {
             data = data.wrapping_add(1);
         }
     }
+
+    #[test]
+    fn block_range_is_checked_against_memory_size() {
+        assert_eq!(Memory::block_range(0x10, 4), Some(0x10..0x14));
+        assert_eq!(
+            Memory::block_range(Memory::SIZE as Address - 4, 4),
+            Some(Memory::SIZE - 4..Memory::SIZE)
+        );
+        assert_eq!(Memory::block_range(Memory::SIZE as Address - 4, 5), None);
+        assert_eq!(Memory::block_range(Address::MAX, Word::MAX), None);
+    }
 }
@@ -0,0 +1,240 @@
+use bitflags::bitflags;
+
+use crate::{memory::Memory, trap::PrivilegeMode, Address, Size, Word};
+
+/// Size of a page (and of a page table) in bytes.
+pub const PAGE_SIZE: usize = 4096;
+
+const PAGE_OFFSET_MASK: Address = PAGE_SIZE as Address - 1;
+const FRAME_MASK: Address = !PAGE_OFFSET_MASK;
+const PAGE_OFFSET_BITS: u32 = PAGE_SIZE.trailing_zeros();
+const TABLE_INDEX_BITS: u32 = (PAGE_SIZE / Word::SIZE).trailing_zeros();
+const NUM_TLB_ENTRIES: usize = 64;
+
+bitflags! {
+    /// Flags stored in the lower bits of page directory and page table entries. The upper bits
+    /// contain the (page aligned) physical address of the page table or page. Page directory
+    /// entries only use `PRESENT`.
+    pub struct PageFlags: Word {
+        const PRESENT = 0b1 << 0;
+        const READ = 0b1 << 1;
+        const WRITE = 0b1 << 2;
+        const EXECUTE = 0b1 << 3;
+        const USER = 0b1 << 4;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read,
+    Write,
+    Execute,
+}
+
+impl MemoryAccess {
+    fn required_flag(self) -> PageFlags {
+        match self {
+            MemoryAccess::Read => PageFlags::READ,
+            MemoryAccess::Write => PageFlags::WRITE,
+            MemoryAccess::Execute => PageFlags::EXECUTE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TlbEntry {
+    virtual_page: Address,
+    frame: Address,
+    flags: PageFlags,
+}
+
+/// Translates virtual addresses into physical addresses using a two-level page table. The
+/// page directory (located at the page table base) and the page tables each consist of 1024
+/// entries. The upper 10 bits of a virtual address select the directory entry, the next 10
+/// bits the page table entry and the lower 12 bits are the offset into the page.
+///
+/// Translations are cached in a TLB, which has to be flushed after modifying the page tables.
+/// Changing the page table base flushes the TLB implicitly.
+#[derive(Debug)]
+pub struct Mmu {
+    pub enabled: bool,
+    page_table_base: Address,
+    tlb: [Option<TlbEntry>; NUM_TLB_ENTRIES],
+}
+
+impl Default for Mmu {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            page_table_base: 0,
+            tlb: [None; NUM_TLB_ENTRIES],
+        }
+    }
+}
+
+impl Mmu {
+    pub fn page_table_base(&self) -> Address {
+        self.page_table_base
+    }
+
+    /// Sets the address of the page directory (the lower 12 bits are ignored).
+    pub fn set_page_table_base(&mut self, address: Address) {
+        self.page_table_base = address & FRAME_MASK;
+        self.flush_tlb();
+    }
+
+    pub fn flush_tlb(&mut self) {
+        self.tlb = [None; NUM_TLB_ENTRIES];
+    }
+
+    /// Translates the virtual address into a physical address. Returns `None` if the page is
+    /// not mapped or if the access is not permitted (i.e. on a page fault).
+    pub fn translate(
+        &mut self,
+        memory: &Memory,
+        address: Address,
+        access: MemoryAccess,
+        mode: PrivilegeMode,
+    ) -> Option<Address> {
+        if !self.enabled {
+            return Some(address);
+        }
+        let virtual_page = address >> PAGE_OFFSET_BITS;
+        let tlb_index = virtual_page as usize % NUM_TLB_ENTRIES;
+        let entry = match self.tlb[tlb_index] {
+            Some(entry) if entry.virtual_page == virtual_page => entry,
+            _ => {
+                let entry = self.walk_page_table(memory, virtual_page)?;
+                self.tlb[tlb_index] = Some(entry);
+                entry
+            }
+        };
+        let permitted = entry.flags.contains(access.required_flag())
+            && (mode == PrivilegeMode::Supervisor || entry.flags.contains(PageFlags::USER));
+        permitted.then_some(entry.frame | (address & PAGE_OFFSET_MASK))
+    }
+
+    fn walk_page_table(&self, memory: &Memory, virtual_page: Address) -> Option<TlbEntry> {
+        let directory_index = virtual_page >> TABLE_INDEX_BITS;
+        let table_index = virtual_page & ((1 << TABLE_INDEX_BITS) - 1);
+        let directory_entry = read_table_entry(memory, self.page_table_base, directory_index)?;
+        if !PageFlags::from_bits_truncate(directory_entry).contains(PageFlags::PRESENT) {
+            return None;
+        }
+        let table_entry = read_table_entry(memory, directory_entry & FRAME_MASK, table_index)?;
+        let flags = PageFlags::from_bits_truncate(table_entry);
+        let frame = table_entry & FRAME_MASK;
+        (flags.contains(PageFlags::PRESENT) && (frame as usize) < Memory::SIZE).then_some(
+            TlbEntry {
+                virtual_page,
+                frame,
+                flags,
+            },
+        )
+    }
+}
+
+/// Reads an entry of the page table (or page directory) at the given physical address.
+/// Tables outside of the physical memory cause page faults.
+fn read_table_entry(memory: &Memory, table: Address, index: Address) -> Option<Word> {
+    let address = table as usize + index as usize * Word::SIZE;
+    (address + Word::SIZE <= Memory::SIZE).then(|| memory.read_data(address as Address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTORY: Address = 0x10_0000;
+    const TABLE: Address = 0x10_1000;
+
+    fn create_mapping(virtual_address: Address, frame: Address, flags: PageFlags) -> Memory {
+        let mut memory = Memory::new();
+        let directory_index = virtual_address >> 22;
+        let table_index = (virtual_address >> 12) & 0x3FF;
+        memory.write_data(
+            DIRECTORY + directory_index * Word::SIZE as Address,
+            TABLE | PageFlags::PRESENT.bits(),
+        );
+        memory.write_data(
+            TABLE + table_index * Word::SIZE as Address,
+            frame | (flags | PageFlags::PRESENT).bits(),
+        );
+        memory
+    }
+
+    fn create_mmu() -> Mmu {
+        let mut mmu = Mmu::default();
+        mmu.set_page_table_base(DIRECTORY);
+        mmu.enabled = true;
+        mmu
+    }
+
+    #[test]
+    fn disabled_mmu_does_not_translate() {
+        let mut mmu = Mmu::default();
+        assert_eq!(
+            mmu.translate(
+                &Memory::new(),
+                0xDEAD_BEEF,
+                MemoryAccess::Write,
+                PrivilegeMode::User
+            ),
+            Some(0xDEAD_BEEF)
+        );
+    }
+
+    #[test]
+    fn mapped_page_is_translated() {
+        let memory = create_mapping(0xC040_3000, 0x2000, PageFlags::READ);
+        let mut mmu = create_mmu();
+        assert_eq!(
+            mmu.translate(
+                &memory,
+                0xC040_3ABC,
+                MemoryAccess::Read,
+                PrivilegeMode::Supervisor
+            ),
+            Some(0x2ABC)
+        );
+        assert_eq!(
+            mmu.translate(
+                &memory,
+                0xC040_4000,
+                MemoryAccess::Read,
+                PrivilegeMode::Supervisor
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn permissions_are_checked() {
+        let memory = create_mapping(0x3000, 0x2000, PageFlags::READ | PageFlags::EXECUTE);
+        let mut mmu = create_mmu();
+        let mut translate = |access, mode| mmu.translate(&memory, 0x3000, access, mode).is_some();
+        assert!(translate(MemoryAccess::Read, PrivilegeMode::Supervisor));
+        assert!(translate(MemoryAccess::Execute, PrivilegeMode::Supervisor));
+        assert!(!translate(MemoryAccess::Write, PrivilegeMode::Supervisor));
+        assert!(!translate(MemoryAccess::Read, PrivilegeMode::User));
+    }
+
+    #[test]
+    fn tlb_keeps_translations_until_flushed() {
+        let mut memory = create_mapping(0x3000, 0x2000, PageFlags::READ);
+        let mut mmu = create_mmu();
+        let translate = |mmu: &mut Mmu, memory: &Memory| {
+            mmu.translate(
+                memory,
+                0x3000,
+                MemoryAccess::Read,
+                PrivilegeMode::Supervisor,
+            )
+        };
+        assert_eq!(translate(&mut mmu, &memory), Some(0x2000));
+        memory.write_data(TABLE + 3 * Word::SIZE as Address, 0);
+        assert_eq!(translate(&mut mmu, &memory), Some(0x2000));
+        mmu.flush_tlb();
+        assert_eq!(translate(&mut mmu, &memory), None);
+    }
+}
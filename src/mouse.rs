@@ -0,0 +1,52 @@
+use bitflags::bitflags;
+
+use crate::Word;
+
+bitflags! {
+    #[derive(Default)]
+    pub struct MouseButtons: Word {
+        const LEFT = 0b1 << 0;
+        const RIGHT = 0b1 << 1;
+        const MIDDLE = 0b1 << 2;
+    }
+}
+
+/// Position (in display coordinates) and pressed buttons of the mouse.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MouseState {
+    pub x: Word,
+    pub y: Word,
+    pub buttons: MouseButtons,
+}
+
+pub struct Mouse {
+    get_state_callback: Box<dyn FnMut() -> MouseState>,
+    wheel_movement: f32,
+}
+
+impl Mouse {
+    pub fn new(get_state_callback: Box<dyn FnMut() -> MouseState>) -> Self {
+        Self {
+            get_state_callback,
+            wheel_movement: 0.0,
+        }
+    }
+
+    pub fn get_state(&mut self) -> MouseState {
+        (self.get_state_callback)()
+    }
+
+    /// Accumulates the movement of the mouse wheel (has to be called once per frame).
+    #[cfg(any(feature = "graphics", test))]
+    pub fn add_wheel_movement(&mut self, movement: f32) {
+        self.wheel_movement += movement;
+    }
+
+    /// Returns the number of whole steps the wheel has been moved since the last call
+    /// (positive values mean upwards). Fractions are kept for the next call.
+    pub fn take_wheel_delta(&mut self) -> i32 {
+        let delta = self.wheel_movement.trunc();
+        self.wheel_movement -= delta;
+        delta as i32
+    }
+}
@@ -0,0 +1,36 @@
+/// Defines an enum with a stable numbering that is published under the given names in the JSON
+/// output (and can be converted back from a word).
+macro_rules! define_numbering {
+    ($(#[$attribute:meta])* $name:ident { $($variant:ident = $number:literal => $json_name:literal),+ $(,)? }) => {
+        $(#[$attribute])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize)]
+        #[serde(rename_all = "snake_case")]
+        pub enum $name {
+            $($variant = $number,)+
+        }
+
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant,)+];
+
+            /// The name under which the number is published in the JSON output.
+            pub fn json_name(self) -> &'static str {
+                match self {
+                    $($name::$variant => $json_name,)+
+                }
+            }
+        }
+
+        impl TryFrom<$crate::Word> for $name {
+            type Error = ();
+
+            fn try_from(number: $crate::Word) -> Result<Self, Self::Error> {
+                match number {
+                    $($number => Ok($name::$variant),)+
+                    _ => Err(()),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use define_numbering;
@@ -1,6 +1,9 @@
-use crate::{Address, AsHalfwords, AsWords, Instruction, Register, Word};
+use crate::{
+    machine_profile::{CostClass, MachineProfile},
+    Address, AsHalfwords, AsWords, Instruction, Register, Word,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 macro_rules! type_to_abbreviation {
     (immediate) => {
@@ -163,7 +166,7 @@ macro_rules! opcodes {
         $identifier:ident,
         $code:literal,
         registers( $($register_usage:ident $register_letter:ident $register_name:ident ),* ) $(, $type:ident )? ;
-        cycles = $num_cycles:literal,
+        cycles = $cost_class:ident,
         Increment::$should_increment:ident,
         $comment:literal
     },)+ ) => {
@@ -206,12 +209,15 @@ macro_rules! opcodes {
             arguments: Vec<Argument>,
             opcode_type: Option<&'static str>,
             cycles: usize,
+            cycles_if_branch_taken: Option<usize>,
+            cycles_per_word: Option<usize>,
+            privileged: bool,
             should_increment: bool,
             docstring: &'static str,
         }
 
         impl Opcode {
-            pub fn as_hashmap() -> HashMap<&'static str, OpcodeDescription> {
+            pub fn as_hashmap(profile: MachineProfile) -> HashMap<&'static str, OpcodeDescription> {
                 let mut result = HashMap::new();
                 $(
                     {
@@ -252,7 +258,19 @@ macro_rules! opcodes {
                             opcode: $code,
                             arguments,
                             opcode_type: type_to_opcode_type!($($type)?),
-                            cycles: $num_cycles,
+                            cycles: profile.cycles(CostClass::$cost_class).into(),
+                            cycles_if_branch_taken: match CostClass::$cost_class {
+                                CostClass::Branch => Some(
+                                    (profile.cycles(CostClass::Branch) + profile.branch_taken_penalty()).into()
+                                ),
+                                _ => None,
+                            },
+                            cycles_per_word: match CostClass::$cost_class {
+                                CostClass::Block => Some(profile.block_cycles_per_word().into()),
+                                _ => None,
+                            },
+                            privileged: Opcode::try_from(($code as Instruction) << (Instruction::BITS - u16::BITS))
+                                .map_or(false, Opcode::is_privileged),
                             should_increment: matches!(Increment::$should_increment, Increment::Yes),
                             docstring: $comment,
                         });
@@ -277,13 +295,55 @@ macro_rules! opcodes {
                 }
             }
 
-            pub fn get_num_cycles(self) -> u8 {
+            pub fn get_cost_class(self) -> CostClass {
                 match self {
                     $(
-                        Self::$identifier{ .. } => $num_cycles,
+                        Self::$identifier{ .. } => CostClass::$cost_class,
                     )+
                 }
             }
+
+            pub fn get_num_cycles(self, profile: MachineProfile) -> u8 {
+                profile.cycles(self.get_cost_class())
+            }
+        }
+
+        /// Disassembles the opcode, e.g. `MoveRegisterImmediate R1, 0x2a`. Addresses and
+        /// immediates are placed like in the documentation of the opcodes.
+        impl fmt::Display for Opcode {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                macro_rules! is_target_address {
+                    (immediate) => { false };
+                    (source_address) => { false };
+                    (target_address) => { true };
+                }
+                let (name, arguments) = match *self {
+                    $(
+                        Self::$identifier{ $( $register_name, )* $($type)? } => {
+                            #[allow(unused_mut)]
+                            let mut arguments = Vec::<String>::new();
+                            $(
+                                if is_target_address!($type) {
+                                    arguments.push(format!("{:#x}", $type));
+                                }
+                            )?
+                            $(
+                                arguments.push(format!("R{}", $register_name.0));
+                            )*
+                            $(
+                                if !is_target_address!($type) {
+                                    arguments.push(format!("{:#x}", $type));
+                                }
+                            )?
+                            (stringify!($identifier), arguments)
+                        }
+                    )+
+                };
+                match arguments.is_empty() {
+                    true => write!(f, "{}", name),
+                    false => write!(f, "{} {}", name, arguments.join(", ")),
+                }
+            }
         }
 
         impl TryFrom<Instruction> for Opcode {
@@ -329,121 +389,284 @@ macro_rules! opcodes {
 
 opcodes!(
     // move instructions
-    { MoveRegisterImmediate, 0x0000, registers(Target R register), immediate; cycles = 1, Increment::Yes, "move the value C into register R" },
-    { MoveRegisterAddress, 0x0001, registers(Target R register), source_address; cycles = 1, Increment::Yes, "move the value at address A into register R" },
-    { MoveTargetSource, 0x0002, registers(Target T target, Source S source); cycles = 1, Increment::Yes, "move the contents of register S into register T" },
-    { MoveAddressRegister, 0x0003, registers(Source R register), target_address; cycles = 1, Increment::Yes, "move the contents of register R into memory at address A" },
-    { MoveTargetPointer, 0x0004, registers(Target T target, Source P pointer); cycles = 1, Increment::Yes, "move the contents addressed by the value of register P into register T" },
-    { MovePointerSource, 0x0005, registers(Target P pointer, Source S source); cycles = 1, Increment::Yes, "move the contents of register S into memory at address specified by register P" },
+    { MoveRegisterImmediate, 0x0000, registers(Target R register), immediate; cycles = Basic, Increment::Yes, "move the value C into register R" },
+    { MoveRegisterAddress, 0x0001, registers(Target R register), source_address; cycles = Load, Increment::Yes, "move the value at address A into register R" },
+    { MoveTargetSource, 0x0002, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the contents of register S into register T" },
+    { MoveAddressRegister, 0x0003, registers(Source R register), target_address; cycles = Store, Increment::Yes, "move the contents of register R into memory at address A" },
+    { MoveTargetPointer, 0x0004, registers(Target T target, Source P pointer); cycles = Load, Increment::Yes, "move the contents addressed by the value of register P into register T" },
+    { MovePointerSource, 0x0005, registers(Target P pointer, Source S source); cycles = Store, Increment::Yes, "move the contents of register S into memory at address specified by register P" },
     // move instructions for byte-sized access
-    { MoveByteRegisterAddress, 0x0041, registers(Target R register), source_address; cycles = 1, Increment::Yes, "move the value at address A into register R (1 byte)"},
-    { MoveByteAddressRegister, 0x0042, registers(Source R register), target_address; cycles = 1, Increment::Yes, "move the contents of register R into memory at address A (1 byte)" },
-    { MoveByteTargetPointer, 0x0043, registers(Target T target, Source P pointer); cycles = 1, Increment::Yes, "move the contents addressed by the value of register P into register T (1 byte)" },
-    { MoveBytePointerSource, 0x0044, registers(Target P pointer, Source S source); cycles = 1, Increment::Yes, "move the contents of register S into memory at address specified by register P (1 byte)" },
+    { MoveByteRegisterAddress, 0x0041, registers(Target R register), source_address; cycles = Load, Increment::Yes, "move the value at address A into register R (1 byte)"},
+    { MoveByteAddressRegister, 0x0042, registers(Source R register), target_address; cycles = Store, Increment::Yes, "move the contents of register R into memory at address A (1 byte)" },
+    { MoveByteTargetPointer, 0x0043, registers(Target T target, Source P pointer); cycles = Load, Increment::Yes, "move the contents addressed by the value of register P into register T (1 byte)" },
+    { MoveBytePointerSource, 0x0044, registers(Target P pointer, Source S source); cycles = Store, Increment::Yes, "move the contents of register S into memory at address specified by register P (1 byte)" },
     // move instructions for halfword-sized access
-    { MoveHalfwordRegisterAddress, 0x0045, registers(Target R register), source_address; cycles = 1, Increment::Yes, "move the value at address A into register R (2 bytes)"},
-    { MoveHalfwordAddressRegister, 0x0046, registers(Source R register), target_address; cycles = 1, Increment::Yes, "move the contents of register R into memory at address A (2 bytes)" },
-    { MoveHalfwordTargetPointer, 0x0047, registers(Target T target, Source P pointer); cycles = 1, Increment::Yes, "move the contents addressed by the value of register P into register T (2 bytes)" },
-    { MoveHalfwordPointerSource, 0x0048, registers(Target P pointer, Source S source); cycles = 1, Increment::Yes, "move the contents of register S into memory at address specified by register P (2 bytes)" },
+    { MoveHalfwordRegisterAddress, 0x0045, registers(Target R register), source_address; cycles = Load, Increment::Yes, "move the value at address A into register R (2 bytes)"},
+    { MoveHalfwordAddressRegister, 0x0046, registers(Source R register), target_address; cycles = Store, Increment::Yes, "move the contents of register R into memory at address A (2 bytes)" },
+    { MoveHalfwordTargetPointer, 0x0047, registers(Target T target, Source P pointer); cycles = Load, Increment::Yes, "move the contents addressed by the value of register P into register T (2 bytes)" },
+    { MoveHalfwordPointerSource, 0x0048, registers(Target P pointer, Source S source); cycles = Store, Increment::Yes, "move the contents of register S into memory at address specified by register P (2 bytes)" },
     // offset move-instructions
-    { MovePointerSourceOffset, 0x0049, registers(Target P pointer, Source S source), immediate; cycles = 1, Increment::Yes, "move the value in register S into memory at address pointer + immediate" },
-    { MoveBytePointerSourceOffset, 0x004A, registers(Target P pointer, Source S source), immediate; cycles = 1, Increment::Yes, "move the value in register S into memory at address pointer + immediate (1 byte)" },
-    { MoveHalfwordPointerSourceOffset, 0x004B, registers(Target P pointer, Source S source), immediate; cycles = 1, Increment::Yes, "move the value in register S into memory at address pointer + immediate (2 bytes)" },
-    { MoveTargetPointerOffset, 0x004C, registers(Target T target, Source P pointer), immediate; cycles = 1, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
-    { MoveByteTargetPointerOffset, 0x004D, registers(Target T target, Source P pointer), immediate; cycles = 1, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
-    { MoveHalfwordTargetPointerOffset, 0x004E, registers(Target T target, Source P pointer), immediate; cycles = 1, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
+    { MovePointerSourceOffset, 0x0049, registers(Target P pointer, Source S source), immediate; cycles = Store, Increment::Yes, "move the value in register S into memory at address pointer + immediate" },
+    { MoveBytePointerSourceOffset, 0x004A, registers(Target P pointer, Source S source), immediate; cycles = Store, Increment::Yes, "move the value in register S into memory at address pointer + immediate (1 byte)" },
+    { MoveHalfwordPointerSourceOffset, 0x004B, registers(Target P pointer, Source S source), immediate; cycles = Store, Increment::Yes, "move the value in register S into memory at address pointer + immediate (2 bytes)" },
+    { MoveTargetPointerOffset, 0x004C, registers(Target T target, Source P pointer), immediate; cycles = Load, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
+    { MoveByteTargetPointerOffset, 0x004D, registers(Target T target, Source P pointer), immediate; cycles = Load, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
+    { MoveHalfwordTargetPointerOffset, 0x004E, registers(Target T target, Source P pointer), immediate; cycles = Load, Increment::Yes, "move the contents addressed by the sum of the pointer and the immediate into the register T" },
 
     // halt and catch fire
-    { HaltAndCatchFire, 0x0006, registers(); cycles = 1, Increment::No, "halt and catch fire" },
+    { HaltAndCatchFire, 0x0006, registers(); cycles = Debug, Increment::No, "halt and catch fire (privileged)" },
 
     // artimetic (sic!) instructions
-    { AddTargetLhsRhs, 0x0007, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "add the values in registers L and R, store the result in T, set zero and carry flags appropriately" },
-    { AddWithCarryTargetLhsRhs, 0x0034, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "add (with carry) the values in registers L and R, store the result in T, set zero and carry flags appropriately" },
-    { SubtractTargetLhsRhs, 0x0008, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "subtract (without carry) the values in registers L and R, store the result in T, set zero and carry flags appropriately" },
-    { SubtractWithCarryTargetLhsRhs, 0x0009, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "subtract (with carry) the values in registers L and R, store the result in T, set zero and carry flags appropriately" },
-    { MultiplyHighLowLhsRhs, 0x000A, registers(Target H high, Target T low, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "multiply the values in registers L and R, store the low part of the result in T, the high part in H, set zero and carry flags appropriately" },
-    { DivmodTargetModLhsRhs, 0x000B, registers(Target D result, Target M remainder, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "divmod the values in registers L and R, store the result in D and the remainder in M set zero and divide-by-zero flags appropriately" },
+    { AddTargetLhsRhs, 0x0007, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "add the values in registers L and R, store the result in T, set zero, carry and overflow flags appropriately" },
+    { AddWithCarryTargetLhsRhs, 0x0034, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "add (with carry) the values in registers L and R, store the result in T, set zero, carry and overflow flags appropriately" },
+    { SubtractTargetLhsRhs, 0x0008, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "subtract (without carry) the values in registers L and R, store the result in T, set zero, carry and overflow flags appropriately" },
+    { SubtractWithCarryTargetLhsRhs, 0x0009, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "subtract (with carry) the values in registers L and R, store the result in T, set zero, carry and overflow flags appropriately" },
+    { MultiplyHighLowLhsRhs, 0x000A, registers(Target H high, Target T low, Source L lhs, Source R rhs); cycles = Multiply, Increment::Yes, "multiply the values in registers L and R, store the low part of the result in T, the high part in H, set zero and carry flags appropriately" },
+    { DivmodTargetModLhsRhs, 0x000B, registers(Target D result, Target M remainder, Source L lhs, Source R rhs); cycles = Divide, Increment::Yes, "divmod the values in registers L and R, store the result in D and the remainder in M set zero and divide-by-zero flags appropriately" },
+
+    // signed arithmetic instructions (operands are interpreted as two's complement)
+    { MultiplySignedHighLowLhsRhs, 0x0050, registers(Target H high, Target T low, Source L lhs, Source R rhs); cycles = Multiply, Increment::Yes, "multiply the signed values in registers L and R, store the low part of the result in T, the high part in H, set zero flag appropriately and set the overflow flag if the result does not fit into T" },
+    { DivmodSignedTargetModLhsRhs, 0x0051, registers(Target D result, Target M remainder, Source L lhs, Source R rhs); cycles = Divide, Increment::Yes, "divmod the signed values in registers L and R (rounding towards zero), store the result in D and the remainder (which has the sign of L) in M, set zero, divide-by-zero and overflow flags appropriately" },
+
+    // arithmetic instructions with an immediate operand (there is no room for a second target register)
+    { MultiplyTargetSourceImmediate, 0x0068, registers(Target T target, Source S source), immediate; cycles = Multiply, Increment::Yes, "multiply the value in register SS by the constant CC, store the low part of the result in TT, set zero and carry (if the high part is not zero) flags appropriately" },
+    { DivideTargetSourceImmediate, 0x0069, registers(Target T target, Source S source), immediate; cycles = Divide, Increment::Yes, "divide the value in register SS by the constant CC, store the result in TT, set zero and divide-by-zero flags appropriately (dividing by zero stores 0)" },
+    { ModuloTargetSourceImmediate, 0x006A, registers(Target T target, Source S source), immediate; cycles = Divide, Increment::Yes, "divide the value in register SS by the constant CC, store the remainder in TT, set zero and divide-by-zero flags appropriately (dividing by zero stores the value of SS)" },
+    { MultiplySignedTargetSourceImmediate, 0x00B8, registers(Target T target, Source S source), immediate; cycles = Multiply, Increment::Yes, "multiply the signed value in register SS by the signed constant CC, store the low part of the result in TT, set zero flag appropriately and set the overflow flag if the result does not fit into TT" },
+    { DivideSignedTargetSourceImmediate, 0x00B9, registers(Target T target, Source S source), immediate; cycles = Divide, Increment::Yes, "divide the signed value in register SS by the signed constant CC (rounding towards zero), store the result in TT, set zero, divide-by-zero and overflow flags appropriately (dividing by zero stores 0)" },
+    { ModuloSignedTargetSourceImmediate, 0x00BA, registers(Target T target, Source S source), immediate; cycles = Divide, Increment::Yes, "divide the signed value in register SS by the signed constant CC (rounding towards zero), store the remainder (which has the sign of SS) in TT, set zero and divide-by-zero flags appropriately (dividing by zero stores the value of SS)" },
+    { AddWithCarryTargetSourceImmediate, 0x00BB, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "add (with carry) the constant CC to the value in register SS, store the result in TT, set zero, carry and overflow flags appropriately" },
+    { SubtractWithCarryTargetSourceImmediate, 0x00BC, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "subtract (with carry) the constant CC from the value in register SS, store the result in TT, set zero, carry and overflow flags appropriately" },
 
     // bitwise instructions
-    { AndTargetLhsRhs, 0x000C, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "and the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
-    { OrTargetLhsRhs, 0x000D, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "or the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
-    { XorTargetLhsRhs, 0x000E, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "xor the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
-    { NotTargetSource, 0x000F, registers(Target T target, Source S source); cycles = 1, Increment::Yes, "not the value in register SS, store the result in TT, set zero flag appropriately" },
-    { LeftShiftTargetLhsRhs, 0x0010, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "left shift the value in register LL by RR bits, store the result in TT, set zero and carry flags appropriately" },
-    { RightShiftTargetLhsRhs, 0x0011, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "right shift the value in register LL by RR bits, store the result in TT, set zero and carry flags appropriately" },
-    { AddTargetSourceImmediate, 0x0012, registers(Target T target, Source S source), immediate; cycles = 1, Increment::Yes, "add the constant CC to the value in register SS and store the result in TT, set zero and carry flags appropriately" },
-    { SubtractTargetSourceImmediate, 0x0013, registers(Target T target, Source S source), immediate; cycles = 1, Increment::Yes, "subtract the constant CC from the value in register SS and store the result in TT, set zero and carry flags appropriately" },
+    { AndTargetLhsRhs, 0x000C, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "and the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
+    { OrTargetLhsRhs, 0x000D, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "or the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
+    { XorTargetLhsRhs, 0x000E, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "xor the values in registers LL and RR, store the result in TT, set zero flag appropriately" },
+    { NotTargetSource, 0x000F, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "not the value in register SS, store the result in TT, set zero flag appropriately" },
+    { LeftShiftTargetLhsRhs, 0x0010, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "left shift the value in register LL by RR bits, store the result in TT, set zero and carry flags appropriately" },
+    { RightShiftTargetLhsRhs, 0x0011, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "right shift the value in register LL by RR bits, store the result in TT, set zero and carry flags appropriately" },
+    { ArithmeticRightShiftTargetLhsRhs, 0x0052, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "right shift the value in register LL by RR bits while preserving its sign, store the result in TT, set zero and carry flags appropriately" },
+    { AddTargetSourceImmediate, 0x0012, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "add the constant CC to the value in register SS and store the result in TT, set zero, carry and overflow flags appropriately" },
+    { SubtractTargetSourceImmediate, 0x0013, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "subtract the constant CC from the value in register SS and store the result in TT, set zero, carry and overflow flags appropriately" },
+    { AndTargetSourceImmediate, 0x0062, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "and the value in register SS and the constant CC, store the result in TT, set zero flag appropriately" },
+    { OrTargetSourceImmediate, 0x0063, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "or the value in register SS and the constant CC, store the result in TT, set zero flag appropriately" },
+    { XorTargetSourceImmediate, 0x0064, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "xor the value in register SS and the constant CC, store the result in TT, set zero flag appropriately" },
+    { LeftShiftTargetSourceImmediate, 0x0065, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "left shift the value in register SS by CC bits, store the result in TT, set zero and carry flags appropriately" },
+    { RightShiftTargetSourceImmediate, 0x0066, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "right shift the value in register SS by CC bits, store the result in TT, set zero and carry flags appropriately" },
+    { ArithmeticRightShiftTargetSourceImmediate, 0x0067, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "right shift the value in register SS by CC bits while preserving its sign, store the result in TT, set zero and carry flags appropriately" },
+
+    // bit manipulation instructions
+    { RotateLeftTargetLhsRhs, 0x007A, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "rotate the value in register LL left by RR bits (modulo 32), store the result in TT, set zero flag appropriately, set carry flag to the last bit that got rotated around (cleared if RR is a multiple of 32)" },
+    { RotateRightTargetLhsRhs, 0x007B, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "rotate the value in register LL right by RR bits (modulo 32), store the result in TT, set zero flag appropriately, set carry flag to the last bit that got rotated around (cleared if RR is a multiple of 32)" },
+    { PopulationCountTargetSource, 0x007C, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "count the bits that are set in the value in register SS, store the result in TT, set zero flag appropriately" },
+    { CountLeadingZerosTargetSource, 0x007D, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "count the leading zero bits of the value in register SS, store the result in TT, set zero flag appropriately, set carry flag if SS is zero (result is 32)" },
+    { CountTrailingZerosTargetSource, 0x007E, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "count the trailing zero bits of the value in register SS, store the result in TT, set zero flag appropriately, set carry flag if SS is zero (result is 32)" },
+    { ByteSwapTargetSource, 0x007F, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "reverse the byte order of the value in register SS (converts between big and little endian), store the result in TT, set zero flag appropriately" },
+    { BitFieldExtractTargetSourceOffsetWidth, 0x0080, registers(Target T target, Source S source, Source O offset, Source W width); cycles = Basic, Increment::Yes, "extract WW bits starting at bit OO (counted from the least significant bit) of the value in register SS, store them zero-extended in TT, bits beyond the word are read as zero, set zero flag appropriately" },
+    { BitFieldInsertTargetSourceOffsetWidth, 0x0081, registers(Target T target, Source S source, Source O offset, Source W width); cycles = Basic, Increment::Yes, "replace WW bits starting at bit OO (counted from the least significant bit) of the value in register TT with the lowest WW bits of the value in register SS, bits beyond the word are discarded, set zero flag appropriately" },
 
     // comparison
-    { CompareTargetLhsRhs, 0x0014, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "compare the values in registers LL and RR, store the result (Word::MAX, 0, 1) in TT, set zero flag appropriately" },
-    { BoolCompareEquals, 0x003A, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the values in registers L and R are equal and stores the result as boolean (0 or 1) in T" },
-    { BoolCompareNotEquals, 0x003B, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the values in registers L and R are not equal and stores the result as boolean (0 or 1) in T" },
-    { BoolCompareGreater, 0x003C, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the value in registers L is greater than the value in register R and stores the result as boolean (0 or 1) in T" },
-    { BoolCompareGreaterOrEquals, 0x003D, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the value in registers L is greater than or equals the value in register R and stores the result as boolean (0 or 1) in T" },
-    { BoolCompareLess, 0x003E, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the value in registers L is less than the value in register R and stores the result as boolean (0 or 1) in T" },
-    { BoolCompareLessOrEquals, 0x003F, registers(Target T target, Source L lhs, Source R rhs); cycles = 1, Increment::Yes, "checks whether the value in registers L is less than or equals the value in register R and stores the result as boolean (0 or 1) in T" },
+    { CompareTargetLhsRhs, 0x0014, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "compare the values in registers LL and RR, store the result (Word::MAX, 0, 1) in TT, set zero flag appropriately" },
+    { BoolCompareEquals, 0x003A, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the values in registers L and R are equal and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareNotEquals, 0x003B, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the values in registers L and R are not equal and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareGreater, 0x003C, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the value in registers L is greater than the value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareGreaterOrEquals, 0x003D, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the value in registers L is greater than or equals the value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareLess, 0x003E, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the value in registers L is less than the value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareLessOrEquals, 0x003F, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the value in registers L is less than or equals the value in register R and stores the result as boolean (0 or 1) in T" },
+    { CompareSignedTargetLhsRhs, 0x0053, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "compare the signed values in registers LL and RR, store the result (Word::MAX, 0, 1) in TT, set zero flag appropriately" },
+    { BoolCompareSignedGreater, 0x0054, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the signed value in registers L is greater than the signed value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedGreaterOrEquals, 0x0055, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the signed value in registers L is greater than or equals the signed value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedLess, 0x0056, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the signed value in registers L is less than the signed value in register R and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedLessOrEquals, 0x0057, registers(Target T target, Source L lhs, Source R rhs); cycles = Basic, Increment::Yes, "checks whether the signed value in registers L is less than or equals the signed value in register R and stores the result as boolean (0 or 1) in T" },
+
+    // comparison with an immediate operand
+    { CompareTargetSourceImmediate, 0x006B, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "compare the value in register SS with the constant CC, store the result (Word::MAX, 0, 1) in TT, set zero flag appropriately" },
+    { CompareSignedTargetSourceImmediate, 0x006C, registers(Target T target, Source S source), immediate; cycles = Basic, Increment::Yes, "compare the signed value in register SS with the signed constant CC, store the result (Word::MAX, 0, 1) in TT, set zero flag appropriately" },
+    { BoolCompareEqualsImmediate, 0x006D, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L equals the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareNotEqualsImmediate, 0x006E, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L does not equal the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareGreaterImmediate, 0x006F, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L is greater than the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareGreaterOrEqualsImmediate, 0x0070, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L is greater than or equals the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareLessImmediate, 0x0071, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L is less than the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareLessOrEqualsImmediate, 0x0072, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the value in register L is less than or equals the constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedGreaterImmediate, 0x0073, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the signed value in register L is greater than the signed constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedGreaterOrEqualsImmediate, 0x0074, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the signed value in register L is greater than or equals the signed constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedLessImmediate, 0x0075, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the signed value in register L is less than the signed constant C and stores the result as boolean (0 or 1) in T" },
+    { BoolCompareSignedLessOrEqualsImmediate, 0x0076, registers(Target T target, Source L lhs), immediate; cycles = Basic, Increment::Yes, "checks whether the signed value in register L is less than or equals the signed constant C and stores the result as boolean (0 or 1) in T" },
+
+    // floating point instructions (operands are IEEE-754 single precision values, the NaN, infinity and inexact flags are set according to the result)
+    { FloatAddTargetLhsRhs, 0x0058, registers(Target T target, Source L lhs, Source R rhs); cycles = Float, Increment::Yes, "add the floating point values in registers L and R, store the result in T, set zero, NaN, infinity and inexact flags appropriately" },
+    { FloatSubtractTargetLhsRhs, 0x0059, registers(Target T target, Source L lhs, Source R rhs); cycles = Float, Increment::Yes, "subtract the floating point value in register R from the one in register L, store the result in T, set zero, NaN, infinity and inexact flags appropriately" },
+    { FloatMultiplyTargetLhsRhs, 0x005A, registers(Target T target, Source L lhs, Source R rhs); cycles = Float, Increment::Yes, "multiply the floating point values in registers L and R, store the result in T, set zero, NaN, infinity and inexact flags appropriately" },
+    { FloatDivideTargetLhsRhs, 0x005B, registers(Target T target, Source L lhs, Source R rhs); cycles = Divide, Increment::Yes, "divide the floating point value in register L by the one in register R, store the result in T, set zero, NaN, infinity, inexact and divide-by-zero flags appropriately" },
+    { FloatCompareTargetLhsRhs, 0x005C, registers(Target T target, Source L lhs, Source R rhs); cycles = Float, Increment::Yes, "compare the floating point values in registers LL and RR, store the result (Word::MAX, 0, 1) in TT, if any of the values is NaN, store 2 (unordered) and set the NaN flag, set zero flag appropriately" },
+    { FloatSquareRootTargetSource, 0x005D, registers(Target T target, Source S source); cycles = Divide, Increment::Yes, "calculate the square root of the floating point value in register S, store the result in T, set zero, NaN, infinity and inexact flags appropriately" },
+    { FloatFloorTargetSource, 0x005E, registers(Target T target, Source S source); cycles = Float, Increment::Yes, "round the floating point value in register S down to the next integral value, store the result in T, set zero, NaN and infinity flags appropriately" },
+    { FloatCeilTargetSource, 0x005F, registers(Target T target, Source S source); cycles = Float, Increment::Yes, "round the floating point value in register S up to the next integral value, store the result in T, set zero, NaN and infinity flags appropriately" },
+    { IntegerToFloatTargetSource, 0x0060, registers(Target T target, Source S source); cycles = Float, Increment::Yes, "convert the signed integer in register S into a floating point value, store the result in T, set zero and inexact flags appropriately" },
+    { FloatToIntegerTargetSource, 0x0061, registers(Target T target, Source S source); cycles = Float, Increment::Yes, "convert the floating point value in register S into a signed integer (rounding towards zero), store the result in T, NaN results in 0 and sets the NaN flag, values out of range are saturated and set the overflow flag, set zero and inexact flags appropriately" },
+
+    // memory block instructions (the cycle count grows with the length, blocks exceeding the memory are an error)
+    { MemoryCopyDestinationSourceLength, 0x0077, registers(Target D destination, Source S source, Source L length); cycles = Block, Increment::Yes, "copy L bytes from the address in register S to the address in register D (the blocks may overlap)" },
+    { MemoryFillDestinationValueLength, 0x0078, registers(Target D destination, Source V value, Source L length); cycles = Block, Increment::Yes, "fill L bytes starting at the address in register D with the lowest byte of the value in register V" },
+    { MemoryCompareTargetLhsRhsLength, 0x0079, registers(Target T target, Source L lhs, Source R rhs, Source N length); cycles = Block, Increment::Yes, "compare N bytes starting at the addresses in registers L and R lexicographically, store the result (Word::MAX, 0, 1) in T, set zero flag appropriately" },
 
     // stack instructions
-    { PushRegister, 0x0015, registers(Source R register); cycles = 1, Increment::Yes, "pushes the value of register RR onto the stack" },
-    { PushImmediate, 0x004F, registers(), immediate; cycles = 1, Increment::Yes, "pushes the immediate value onto the stack" },
-    { PopRegister, 0x0016, registers(Target R register); cycles = 1, Increment::Yes, "pops from the stack and stores the value in register RR" },
-    { Pop, 0x0040, registers(); cycles = 1, Increment::Yes, "pops from the stack and discards the value" },
-    { CallImmediate, 0x0017, registers(), immediate; cycles = 1, Increment::No, "push the current instruction pointer onto the stack and jump to the specified address" },
-    { CallRegister, 0x0036, registers(Source R register); cycles = 1, Increment::No, "push the current instruction pointer onto the stack and jump to the address stored in register R" },
-    { CallPointer, 0x0037, registers(Source P pointer); cycles = 1, Increment::No, "push the current instruction pointer onto the stack and jump to the address stored in memory at the location specified by the value in register P" },
-    { Return, 0x0018, registers(); cycles = 1, Increment::No, "pop the return address from the stack and jump to it" },
+    { PushRegister, 0x0015, registers(Source R register); cycles = Stack, Increment::Yes, "pushes the value of register RR onto the stack" },
+    { PushImmediate, 0x004F, registers(), immediate; cycles = Stack, Increment::Yes, "pushes the immediate value onto the stack" },
+    { PopRegister, 0x0016, registers(Target R register); cycles = Stack, Increment::Yes, "pops from the stack and stores the value in register RR" },
+    { Pop, 0x0040, registers(); cycles = Stack, Increment::Yes, "pops from the stack and discards the value" },
+    { CallImmediate, 0x0017, registers(), immediate; cycles = Call, Increment::No, "push the current instruction pointer onto the stack and jump to the specified address" },
+    { CallRegister, 0x0036, registers(Source R register); cycles = Call, Increment::No, "push the current instruction pointer onto the stack and jump to the address stored in register R" },
+    { CallPointer, 0x0037, registers(Source P pointer); cycles = Call, Increment::No, "push the current instruction pointer onto the stack and jump to the address stored in memory at the location specified by the value in register P" },
+    { Return, 0x0018, registers(); cycles = Return, Increment::No, "pop the return address from the stack and jump to it" },
+
+    // system instructions (privileged instructions cause a trap when executed in user mode)
+    { SystemCallImmediate, 0x009E, registers(), immediate; cycles = Call, Increment::No, "trap into supervisor mode with the cause \"system call\" and the immediate as trap value, SystemReturn continues after this instruction" },
+    { SystemReturn, 0x009F, registers(); cycles = Return, Increment::No, "switch back into the mode the trap was raised in (user mode if no trap was raised) and jump to the trap return address (privileged)" },
+    { SetTrapVectorSource, 0x00A0, registers(Source S source); cycles = Basic, Increment::Yes, "set the address where execution continues when a trap occurs to the value in register S (privileged)" },
+    { GetTrapCauseValue, 0x00A1, registers(Target C cause, Target V value); cycles = Basic, Increment::Yes, "store the cause of the last trap in register C and the trap value (system call number, opcode number or faulting address) in register V (privileged)" },
+    { GetTrapReturnAddressTarget, 0x00A2, registers(Target T target); cycles = Basic, Increment::Yes, "store the trap return address in register T, for faults this is the address of the faulting instruction (privileged)" },
+    { SetTrapReturnAddressSource, 0x00A3, registers(Source S source); cycles = Basic, Increment::Yes, "set the trap return address (used by SystemReturn) to the value in register S (privileged)" },
+    { SetUserMemoryStartEnd, 0x00A4, registers(Source S start, Source E end); cycles = Basic, Increment::Yes, "restrict memory accesses and instruction fetches in user mode to the addresses from the value in register S (inclusive) to the value in register E (exclusive), violations cause a protection fault (privileged)" },
+    { SetPageTableBaseSource, 0x00A5, registers(Source S source); cycles = Basic, Increment::Yes, "set the physical address of the page directory to the value in register S and flush the TLB (privileged)" },
+    { GetPageTableBaseTarget, 0x00A6, registers(Target T target); cycles = Basic, Increment::Yes, "store the physical address of the page directory in register T (privileged)" },
+    { EnablePaging, 0x00A7, registers(); cycles = Basic, Increment::Yes, "translate all following memory accesses and instruction fetches through the page table, unmapped pages or missing permissions cause a page fault (privileged)" },
+    { DisablePaging, 0x00A8, registers(); cycles = Basic, Increment::Yes, "access the physical memory directly again (privileged)" },
+    { FlushTlb, 0x00A9, registers(); cycles = Basic, Increment::Yes, "discard all cached address translations, necessary after modifying the page tables (privileged)" },
+    { GetCoreIdTarget, 0x00AA, registers(Target T target); cycles = Basic, Increment::Yes, "store the ID of the executing core in register T (the boot core has the ID 0)" },
+    { GetNumCoresTarget, 0x00AB, registers(Target T target); cycles = Basic, Increment::Yes, "store the number of cores in register T" },
+    { CompareAndSwapTargetPointerExpectedDesired, 0x00AC, registers(Target T target, Source P pointer, Source E expected, Source D desired); cycles = Atomic, Increment::Yes, "atomically load the word at the address in register P into register T and replace it with the value in register D if it equals the value in register E, the zero flag is set if the word has been replaced" },
+    { FetchAndAddTargetPointerSource, 0x00AD, registers(Target T target, Source P pointer, Source S source); cycles = Atomic, Increment::Yes, "atomically load the word at the address in register P into register T and add the value in register S to the word in memory (wrapping)" },
+    { WakeUpCoreSource, 0x00AE, registers(Source S source); cycles = Basic, Increment::Yes, "wake up the core whose ID is in register S, if it is not waiting its next WaitForWakeUp returns immediately (IDs of non-existent cores are ignored)" },
+    { WaitForWakeUp, 0x00AF, registers(); cycles = Basic, Increment::No, "suspend the executing core until another core wakes it up, returns immediately if a wake-up is already pending" },
+    { GetRandomTarget, 0x00B0, registers(Target T target); cycles = Periphery, Increment::Yes, "store a random word from the random number generator in register T (privileged)" },
+    { PollDateTime, 0x00B1, registers(Target Y year, Target M month, Target D day, Target H hour, Target I minute, Target S second); cycles = Periphery, Increment::Yes, "latch the current local date and time of the real-time clock and store the year, the month (1-12), the day (1-31), the hour, the minute and the second into the registers Y, M, D, H, I and S (privileged)" },
+    { GetWeekdayTimezoneOffset, 0x00B2, registers(Target W weekday, Target O offset); cycles = Periphery, Increment::Yes, "store the weekday (0 = Monday) of the date latched by the last PollDateTime in register W and its timezone offset (signed, in seconds east of UTC) in register O (privileged)" },
+    { SetTimezoneOffsetSource, 0x00B3, registers(Source S source); cycles = Periphery, Increment::Yes, "set the timezone offset of the real-time clock to the signed value in register S (in seconds east of UTC), offsets of a day or more are ignored (privileged)" },
+    { PollMouse, 0x00B4, registers(Target X x, Target Y y, Target B buttons, Target W wheel); cycles = Periphery, Increment::Yes, "store the mouse position in display coordinates in registers X and Y, the pressed mouse buttons (bit mask) in register B and the number of steps the wheel has been moved since the last PollMouse (signed, positive means upwards) in register W (privileged)" },
+    { GetGamepadButtonsTargetGamepad, 0x00B5, registers(Target T target, Source G gamepad); cycles = Periphery, Increment::Yes, "store the pressed buttons (bit mask, the GAMEPAD_BUTTON_* constants are the bit positions) of the gamepad whose number is in register G in register T, 0 if it is not connected (privileged)" },
+    { GetGamepadAxisTargetGamepadAxis, 0x00B6, registers(Target T target, Source G gamepad, Source A axis); cycles = Periphery, Increment::Yes, "store the value (signed, from -GAMEPAD_AXIS_MAX to GAMEPAD_AXIS_MAX) of the axis whose number is in register A of the gamepad whose number is in register G in register T, 0 if the gamepad is not connected or there is no such axis (privileged)" },
+    { IsGamepadConnectedTargetGamepad, 0x00B7, registers(Target T target, Source G gamepad); cycles = Periphery, Increment::Yes, "store 1 in register T if the gamepad whose number is in register G is connected, otherwise 0 (and set the zero flag) (privileged)" },
+
+    // stack frame instructions (offsets are signed, the frame pointer is a dedicated register)
+    { MoveTargetStackOffset, 0x0082, registers(Target T target), immediate; cycles = Load, Increment::Yes, "move the contents addressed by the sum of the stack pointer and the signed offset C into register T" },
+    { MoveStackSourceOffset, 0x0083, registers(Source S source), immediate; cycles = Store, Increment::Yes, "move the value in register S into memory at the address stack pointer + C (C is a signed offset)" },
+    { MoveTargetFrameOffset, 0x0084, registers(Target T target), immediate; cycles = Load, Increment::Yes, "move the contents addressed by the sum of the frame pointer and the signed offset C into register T" },
+    { MoveFrameSourceOffset, 0x0085, registers(Source S source), immediate; cycles = Store, Increment::Yes, "move the value in register S into memory at the address frame pointer + C (C is a signed offset)" },
+    { EnterImmediate, 0x0086, registers(), immediate; cycles = Stack, Increment::Yes, "push the frame pointer onto the stack, set the frame pointer to the stack pointer and reserve C bytes for local variables on the stack" },
+    { Leave, 0x0087, registers(); cycles = Stack, Increment::Yes, "set the stack pointer to the frame pointer (freeing the local variables) and pop the previous frame pointer from the stack" },
 
     // unconditional jumps
-    { JumpImmediate, 0x0019, registers(), immediate; cycles = 1, Increment::No, "jump to the given address" },
-    { JumpRegister, 0x001A, registers(Source R register); cycles = 1, Increment::No, "jump to the address stored in register R" },
+    { JumpImmediate, 0x0019, registers(), immediate; cycles = Jump, Increment::No, "jump to the given address" },
+    { JumpRegister, 0x001A, registers(Source R register); cycles = Jump, Increment::No, "jump to the address stored in register R" },
 
     // conditional jumps, address given as immediate
-    { JumpImmediateIfEqual, 0x001B, registers(Source C comparison), immediate; cycles = 1, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"equality\"" },
-    { JumpImmediateIfGreaterThan, 0x001C, registers(Source C comparison), immediate; cycles = 1, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"greater than\"" },
-    { JumpImmediateIfLessThan, 0x001D, registers(Source C comparison), immediate; cycles = 1, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"less than\"" },
-    { JumpImmediateIfGreaterThanOrEqual, 0x001E, registers(Source C comparison), immediate; cycles = 1, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"greater than\" or \"equal\"" },
-    { JumpImmediateIfLessThanOrEqual, 0x001F, registers(Source C comparison), immediate; cycles = 1, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"less than\" or \"equal\"" },
-    { JumpImmediateIfZero, 0x0020, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the zero flag is set" },
-    { JumpImmediateIfNotZero, 0x0021, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the zero flag is not set" },
-    { JumpImmediateIfCarry, 0x0022, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the carry flag is set" },
-    { JumpImmediateIfNotCarry, 0x0023, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the carry flag is not set" },
-    { JumpImmediateIfDivideByZero, 0x0024, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the divide by zero flag is set" },
-    { JumpImmediateIfNotDivideByZero, 0x0025, registers(), immediate; cycles = 1, Increment::No, "jump to the specified address if the divide by zero flag is not set" },
+    { JumpImmediateIfEqual, 0x001B, registers(Source C comparison), immediate; cycles = Branch, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"equality\"" },
+    { JumpImmediateIfGreaterThan, 0x001C, registers(Source C comparison), immediate; cycles = Branch, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"greater than\"" },
+    { JumpImmediateIfLessThan, 0x001D, registers(Source C comparison), immediate; cycles = Branch, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"less than\"" },
+    { JumpImmediateIfGreaterThanOrEqual, 0x001E, registers(Source C comparison), immediate; cycles = Branch, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"greater than\" or \"equal\"" },
+    { JumpImmediateIfLessThanOrEqual, 0x001F, registers(Source C comparison), immediate; cycles = Branch, Increment::No, "jump to the specified address if the comparison result in register C corresponds to \"less than\" or \"equal\"" },
+    { JumpImmediateIfZero, 0x0020, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the zero flag is set" },
+    { JumpImmediateIfNotZero, 0x0021, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the zero flag is not set" },
+    { JumpImmediateIfCarry, 0x0022, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the carry flag is set" },
+    { JumpImmediateIfNotCarry, 0x0023, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the carry flag is not set" },
+    { JumpImmediateIfDivideByZero, 0x0024, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the divide by zero flag is set" },
+    { JumpImmediateIfNotDivideByZero, 0x0025, registers(), immediate; cycles = Branch, Increment::No, "jump to the specified address if the divide by zero flag is not set" },
 
     // conditional jumps, address given as register
-    { JumpRegisterIfEqual, 0x0026, registers(Source P pointer, Source C comparison); cycles = 1, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"equality\"" },
-    { JumpRegisterIfGreaterThan, 0x0027, registers(Source P pointer, Source C comparison); cycles = 1, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"greater than\"" },
-    { JumpRegisterIfLessThan, 0x0028, registers(Source P pointer, Source C comparison); cycles = 1, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"less than\"" },
-    { JumpRegisterIfGreaterThanOrEqual, 0x0029, registers(Source P pointer, Source C comparison); cycles = 1, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"greater than\" or \"equal\"" },
-    { JumpRegisterIfLessThanOrEqual, 0x002A, registers(Source P pointer, Source C comparison); cycles = 1, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"less than\" or \"equal\"" },
-    { JumpRegisterIfZero, 0x002B, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the zero flag is set" },
-    { JumpRegisterIfNotZero, 0x002C, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the zero flag is not set" },
-    { JumpRegisterIfCarry, 0x002D, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the carry flag is set" },
-    { JumpRegisterIfNotCarry, 0x002E, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the carry flag is not set" },
-    { JumpRegisterIfDivideByZero, 0x002F, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the divide by zero flag is set" },
-    { JumpRegisterIfNotDivideByZero, 0x0030, registers(Source P pointer); cycles = 1, Increment::No, "jump to the address specified in register P if the divide by zero flag is not set" },
+    { JumpRegisterIfEqual, 0x0026, registers(Source P pointer, Source C comparison); cycles = Branch, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"equality\"" },
+    { JumpRegisterIfGreaterThan, 0x0027, registers(Source P pointer, Source C comparison); cycles = Branch, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"greater than\"" },
+    { JumpRegisterIfLessThan, 0x0028, registers(Source P pointer, Source C comparison); cycles = Branch, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"less than\"" },
+    { JumpRegisterIfGreaterThanOrEqual, 0x0029, registers(Source P pointer, Source C comparison); cycles = Branch, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"greater than\" or \"equal\"" },
+    { JumpRegisterIfLessThanOrEqual, 0x002A, registers(Source P pointer, Source C comparison); cycles = Branch, Increment::No, "jump to the address specified in register P if the comparison result in register C corresponds to \"less than\" or \"equal\"" },
+    { JumpRegisterIfZero, 0x002B, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the zero flag is set" },
+    { JumpRegisterIfNotZero, 0x002C, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the zero flag is not set" },
+    { JumpRegisterIfCarry, 0x002D, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the carry flag is set" },
+    { JumpRegisterIfNotCarry, 0x002E, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the carry flag is not set" },
+    { JumpRegisterIfDivideByZero, 0x002F, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the divide by zero flag is set" },
+    { JumpRegisterIfNotDivideByZero, 0x0030, registers(Source P pointer); cycles = Branch, Increment::No, "jump to the address specified in register P if the divide by zero flag is not set" },
+
+    // conditional moves and set-on-condition instructions (conditions match the conditional jumps)
+    { MoveTargetSourceIfEqual, 0x0088, registers(Target T target, Source S source, Source C comparison); cycles = Basic, Increment::Yes, "move the value in register S into register T if the comparison result in register C corresponds to \"equality\", otherwise leave T unchanged" },
+    { MoveTargetSourceIfGreaterThan, 0x0089, registers(Target T target, Source S source, Source C comparison); cycles = Basic, Increment::Yes, "move the value in register S into register T if the comparison result in register C corresponds to \"greater than\", otherwise leave T unchanged" },
+    { MoveTargetSourceIfLessThan, 0x008A, registers(Target T target, Source S source, Source C comparison); cycles = Basic, Increment::Yes, "move the value in register S into register T if the comparison result in register C corresponds to \"less than\", otherwise leave T unchanged" },
+    { MoveTargetSourceIfGreaterThanOrEqual, 0x008B, registers(Target T target, Source S source, Source C comparison); cycles = Basic, Increment::Yes, "move the value in register S into register T if the comparison result in register C corresponds to \"greater than\" or \"equal\", otherwise leave T unchanged" },
+    { MoveTargetSourceIfLessThanOrEqual, 0x008C, registers(Target T target, Source S source, Source C comparison); cycles = Basic, Increment::Yes, "move the value in register S into register T if the comparison result in register C corresponds to \"less than\" or \"equal\", otherwise leave T unchanged" },
+    { MoveTargetSourceIfZero, 0x008D, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the zero flag is set, otherwise leave T unchanged" },
+    { MoveTargetSourceIfNotZero, 0x008E, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the zero flag is not set, otherwise leave T unchanged" },
+    { MoveTargetSourceIfCarry, 0x008F, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the carry flag is set, otherwise leave T unchanged" },
+    { MoveTargetSourceIfNotCarry, 0x0090, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the carry flag is not set, otherwise leave T unchanged" },
+    { MoveTargetSourceIfDivideByZero, 0x0091, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the divide by zero flag is set, otherwise leave T unchanged" },
+    { MoveTargetSourceIfNotDivideByZero, 0x0092, registers(Target T target, Source S source); cycles = Basic, Increment::Yes, "move the value in register S into register T if the divide by zero flag is not set, otherwise leave T unchanged" },
+    { SetTargetIfEqual, 0x0093, registers(Target T target, Source C comparison); cycles = Basic, Increment::Yes, "store 1 in register T if the comparison result in register C corresponds to \"equality\", otherwise store 0" },
+    { SetTargetIfGreaterThan, 0x0094, registers(Target T target, Source C comparison); cycles = Basic, Increment::Yes, "store 1 in register T if the comparison result in register C corresponds to \"greater than\", otherwise store 0" },
+    { SetTargetIfLessThan, 0x0095, registers(Target T target, Source C comparison); cycles = Basic, Increment::Yes, "store 1 in register T if the comparison result in register C corresponds to \"less than\", otherwise store 0" },
+    { SetTargetIfGreaterThanOrEqual, 0x0096, registers(Target T target, Source C comparison); cycles = Basic, Increment::Yes, "store 1 in register T if the comparison result in register C corresponds to \"greater than\" or \"equal\", otherwise store 0" },
+    { SetTargetIfLessThanOrEqual, 0x0097, registers(Target T target, Source C comparison); cycles = Basic, Increment::Yes, "store 1 in register T if the comparison result in register C corresponds to \"less than\" or \"equal\", otherwise store 0" },
+    { SetTargetIfZero, 0x0098, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the zero flag is set, otherwise store 0" },
+    { SetTargetIfNotZero, 0x0099, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the zero flag is not set, otherwise store 0" },
+    { SetTargetIfCarry, 0x009A, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the carry flag is set, otherwise store 0" },
+    { SetTargetIfNotCarry, 0x009B, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the carry flag is not set, otherwise store 0" },
+    { SetTargetIfDivideByZero, 0x009C, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the divide by zero flag is set, otherwise store 0" },
+    { SetTargetIfNotDivideByZero, 0x009D, registers(Target T target); cycles = Basic, Increment::Yes, "store 1 in register T if the divide by zero flag is not set, otherwise store 0" },
 
     // no-op
-    { NoOp, 0x0031, registers(); cycles = 1, Increment::Yes, "does nothing" },
+    { NoOp, 0x0031, registers(); cycles = Basic, Increment::Yes, "does nothing" },
 
     // input
-    { GetKeyState, 0x0032, registers(Target T target, Source K keycode); cycles = 1, Increment::Yes, "store the keystate (1 = held down, 0 = not held down) of the key specified by register K into register T and set the zero flag appropriately" },
+    { GetKeyState, 0x0032, registers(Target T target, Source K keycode); cycles = Periphery, Increment::Yes, "store the keystate (1 = held down, 0 = not held down) of the key whose keycode (see the KEY_* constants) is in register K into register T and set the zero flag appropriately, unknown keycodes are never held down (privileged)" },
 
     // Timing
-    { PollTime, 0x0033, registers(Target H high, Target L low); cycles = 1, Increment::Yes, "store the number of milliseconds since the UNIX epoch into registers high and low" },
+    { PollTime, 0x0033, registers(Target H high, Target L low); cycles = Periphery, Increment::Yes, "store the number of milliseconds since the UNIX epoch into registers high and low (privileged)" },
 
     // Rendering
-    { SwapFramebuffers, 0x0035, registers(); cycles = 1, Increment::Yes, "swap the display buffers" },
-    { InvisibleFramebufferAddress, 0x0038, registers(Target T target); cycles = 1, Increment::Yes, "get the start address of the framebuffer that's currently invisible (use the address to draw without tearing)" },
+    { SwapFramebuffers, 0x0035, registers(); cycles = Periphery, Increment::Yes, "swap the display buffers (privileged)" },
+    { InvisibleFramebufferAddress, 0x0038, registers(Target T target); cycles = Periphery, Increment::Yes, "get the start address of the framebuffer that's currently invisible (use the address to draw without tearing) (privileged)" },
 
     // Debugging and profiling
-    { PollCycleCountHighLow, 0x0039, registers(Target H high, Target L low); cycles = 1, Increment::Yes, "store the current cycle (64 bit value) count into registers H and L (H: most significant bytes, L: least significant bytes)" },
-    { DumpRegisters, 0xFFFF, registers(); cycles = 1, Increment::Yes, "dump the contents of all registers into the file 'registers_YYYY-MM-DD_X.bin' where YYYY-MM-DD is the current date and X is an increasing number" },
-    { DumpMemory, 0xFFFE, registers(); cycles = 1, Increment::Yes, "dump the contents of the whole memory into the file 'memory_YYYY-MM-DD_X.bin' where YYYY-MM-DD is the current date and X is an increasing number" },
-    { AssertRegisterRegister, 0xFFFD, registers(Source E expected, Source A actual); cycles = 1, Increment::Yes, "assert that the expected register value equals the actual register value (behavior of the VM on a failed assertion is implementation defined)" },
-    { AssertRegisterImmediate, 0xFFFC, registers(Source A actual), immediate; cycles = 1, Increment::Yes, "assert that the actual register value equals the immediate (behavior of the VM on a failed assertion is implementation defined)"},
-    { AssertPointerImmediate, 0xFFFB, registers(Source P pointer), immediate; cycles = 1, Increment::Yes, "assert that the value in memory pointed at by P equals the immediate (behavior of the VM on a failed assertion is implementation defined)"},
-    { DebugBreak, 0xFFFA, registers(); cycles = 1, Increment::Yes, "behavior is implementation defined" },
-    { PrintRegister, 0xFFF9, registers(Source R register); cycles = 1, Increment::Yes, "prints the value of the register as debug output"},
-    { Checkpoint, 0xFFF8, registers(), immediate; cycles = 1, Increment::Yes, "makes the emulator check the value of the internal checkpoint counter, fails on mismatch" },
+    { PollCycleCountHighLow, 0x0039, registers(Target H high, Target L low); cycles = Debug, Increment::Yes, "store the current cycle (64 bit value) count into registers H and L (H: most significant bytes, L: least significant bytes)" },
+    { DumpRegisters, 0xFFFF, registers(); cycles = Debug, Increment::Yes, "dump the contents of all registers into the file 'registers_YYYY-MM-DD_X.bin' where YYYY-MM-DD is the current date and X is an increasing number (privileged)" },
+    { DumpMemory, 0xFFFE, registers(); cycles = Debug, Increment::Yes, "dump the contents of the whole memory into the file 'memory_YYYY-MM-DD_X.bin' where YYYY-MM-DD is the current date and X is an increasing number (privileged)" },
+    { AssertRegisterRegister, 0xFFFD, registers(Source E expected, Source A actual); cycles = Debug, Increment::Yes, "assert that the expected register value equals the actual register value (behavior of the VM on a failed assertion is implementation defined)" },
+    { AssertRegisterImmediate, 0xFFFC, registers(Source A actual), immediate; cycles = Debug, Increment::Yes, "assert that the actual register value equals the immediate (behavior of the VM on a failed assertion is implementation defined)"},
+    { AssertPointerImmediate, 0xFFFB, registers(Source P pointer), immediate; cycles = Load, Increment::Yes, "assert that the value in memory pointed at by P equals the immediate (behavior of the VM on a failed assertion is implementation defined)"},
+    { DebugBreak, 0xFFFA, registers(); cycles = Debug, Increment::Yes, "behavior is implementation defined" },
+    { PrintRegister, 0xFFF9, registers(Source R register); cycles = Debug, Increment::Yes, "prints the value of the register as debug output"},
+    { Checkpoint, 0xFFF8, registers(), immediate; cycles = Debug, Increment::Yes, "makes the emulator check the value of the internal checkpoint counter, fails on mismatch" },
 );
+
+impl Opcode {
+    /// Returns whether the instruction may only be executed in supervisor mode (accessing the
+    /// periphery, halting the machine and managing traps).
+    pub fn is_privileged(self) -> bool {
+        matches!(
+            self,
+            Self::HaltAndCatchFire {}
+                | Self::GetKeyState { .. }
+                | Self::PollTime { .. }
+                | Self::PollMouse { .. }
+                | Self::GetGamepadButtonsTargetGamepad { .. }
+                | Self::GetGamepadAxisTargetGamepadAxis { .. }
+                | Self::IsGamepadConnectedTargetGamepad { .. }
+                | Self::GetRandomTarget { .. }
+                | Self::PollDateTime { .. }
+                | Self::GetWeekdayTimezoneOffset { .. }
+                | Self::SetTimezoneOffsetSource { .. }
+                | Self::SwapFramebuffers {}
+                | Self::InvisibleFramebufferAddress { .. }
+                | Self::DumpRegisters {}
+                | Self::DumpMemory {}
+                | Self::SystemReturn {}
+                | Self::SetTrapVectorSource { .. }
+                | Self::GetTrapCauseValue { .. }
+                | Self::GetTrapReturnAddressTarget { .. }
+                | Self::SetTrapReturnAddressSource { .. }
+                | Self::SetUserMemoryStartEnd { .. }
+                | Self::SetPageTableBaseSource { .. }
+                | Self::GetPageTableBaseTarget { .. }
+                | Self::EnablePaging {}
+                | Self::DisablePaging {}
+                | Self::FlushTlb {}
+        )
+    }
+}
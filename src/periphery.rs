@@ -1,4 +1,7 @@
-use crate::{cursor::Cursor, display, keyboard::Keyboard, timer::Timer};
+use crate::{
+    cursor::Cursor, display, gamepad::Gamepads, keyboard::Keyboard, mouse::Mouse, random::Random,
+    rtc::Rtc, timer::Timer,
+};
 
 pub trait Periphery {
     type Handle;
@@ -6,17 +9,25 @@ pub trait Periphery {
 
     fn timer(&mut self) -> &mut Timer;
     fn keyboard(&mut self) -> &mut Keyboard;
+    fn mouse(&mut self) -> &mut Mouse;
+    fn gamepads(&mut self) -> &mut Gamepads;
     fn display(
         &mut self,
     ) -> &mut dyn display::Display<Handle = Self::Handle, Thread = Self::Thread>;
     fn cursor(&mut self) -> &mut Cursor;
+    fn random(&mut self) -> &mut Random;
+    fn rtc(&mut self) -> &mut Rtc;
 }
 
 pub struct PeripheryImplementation<Display: display::Display> {
     pub timer: Timer,
     pub keyboard: Keyboard,
+    pub mouse: Mouse,
+    pub gamepads: Gamepads,
     pub display: Display,
     pub cursor: Cursor,
+    pub random: Random,
+    pub rtc: Rtc,
 }
 
 impl<Display: display::Display> Periphery for PeripheryImplementation<Display> {
@@ -31,6 +42,14 @@ impl<Display: display::Display> Periphery for PeripheryImplementation<Display> {
         &mut self.keyboard
     }
 
+    fn mouse(&mut self) -> &mut Mouse {
+        &mut self.mouse
+    }
+
+    fn gamepads(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
     fn display(
         &mut self,
     ) -> &mut dyn display::Display<Handle = Self::Handle, Thread = Self::Thread> {
@@ -40,4 +59,12 @@ impl<Display: display::Display> Periphery for PeripheryImplementation<Display> {
     fn cursor(&mut self) -> &mut Cursor {
         &mut self.cursor
     }
+
+    fn random(&mut self) -> &mut Random {
+        &mut self.random
+    }
+
+    fn rtc(&mut self) -> &mut Rtc {
+        &mut self.rtc
+    }
 }
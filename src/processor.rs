@@ -1,15 +1,20 @@
 #![allow(non_upper_case_globals)]
 
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, Range};
 
+use crate::gamepad::GamepadAxis;
 use crate::keyboard::KeyState;
+use crate::machine_profile::MachineProfile;
+use crate::mmu::{MemoryAccess, Mmu, PAGE_SIZE};
 use crate::opcodes::Opcode;
 use crate::periphery::Periphery;
+use crate::trap::{PrivilegeMode, TrapCause, TrapState};
 use crate::{address_constants, Byte, Halfword};
 use crate::{dumper, static_assert};
 use crate::{memory::Memory, Address, Instruction, Word};
 use crate::{Register, Size};
 use bitflags::bitflags;
+use chrono::{Datelike, Timelike};
 use std::collections::HashMap;
 
 const _: () = static_assert(address_constants::ENTRY_POINT as usize % Instruction::SIZE == 0);
@@ -23,6 +28,8 @@ pub enum ExecutionResult {
     Error,
     Normal,
     Halted,
+    /// The instruction completed normally and the core with the given ID has to be woken up.
+    WakeUpCore(Word),
 }
 
 macro_rules! define_flags {
@@ -50,9 +57,43 @@ macro_rules! define_flags {
 define_flags![
     (Zero, shift = 0),
     (Carry, shift = 1),
-    (DivideByZero, shift = 2)
+    (DivideByZero, shift = 2),
+    (Overflow, shift = 3),
+    (NaN, shift = 4),
+    (Infinity, shift = 5),
+    (Inexact, shift = 6)
 ];
 
+/// Returns whether a result of a signed operation does not fit into a signed word.
+fn signed_result_overflows(result: i64) -> bool {
+    i32::try_from(result).is_err()
+}
+
+/// Returns whether the sum of two floating point values had to be rounded.
+fn float_sum_is_inexact(lhs: f32, rhs: f32, sum: f32) -> bool {
+    if !sum.is_finite() {
+        return lhs.is_finite() && rhs.is_finite();
+    }
+    // error-free transformation of the sum (TwoSum algorithm)
+    let virtual_rhs = sum - lhs;
+    let virtual_lhs = sum - virtual_rhs;
+    let error = (lhs - virtual_lhs) + (rhs - virtual_rhs);
+    error != 0.0
+}
+
+/// Returns whether the floating point result differs from the exact result (calculated
+/// with double precision).
+fn float_result_is_inexact(exact_result: f64, result: f32) -> bool {
+    !result.is_nan() && exact_result != result as f64
+}
+
+/// Returns a mask of the lowest `width` bits (all bits if `width` exceeds the word size).
+fn bit_field_mask(width: Word) -> Word {
+    Word::MAX
+        .checked_shr(Word::BITS - width.min(Word::BITS))
+        .unwrap_or(0)
+}
+
 pub struct Registers<const SIZE: usize>([Word; SIZE]);
 
 impl<const SIZE: usize> Registers<SIZE> {
@@ -80,6 +121,9 @@ impl<const SIZE: usize> IndexMut<Register> for Registers<SIZE> {
 
 pub const NUM_REGISTERS: usize = 256;
 
+/// The maximum number of cores, so that every core still gets a usable part of the stack.
+pub const MAX_NUM_CORES: Word = 64;
+
 pub type CachedInstruction<ConcretePeriphery> =
     Box<dyn Fn(&mut Processor, &mut Memory, &mut ConcretePeriphery) -> ExecutionResult>;
 
@@ -87,27 +131,113 @@ pub struct InstructionCache<ConcretePeriphery: Periphery> {
     pub cache: Box<[CachedInstruction<ConcretePeriphery>; Memory::SIZE / Instruction::SIZE]>,
 }
 
+impl<ConcretePeriphery: Periphery + 'static> InstructionCache<ConcretePeriphery> {
+    /// Generates the cached instruction for the instruction at the given (physical) address.
+    fn generate_entry(
+        memory: &Memory,
+        address: Address,
+        profile: MachineProfile,
+    ) -> CachedInstruction<ConcretePeriphery> {
+        let opcode = match address >= address_constants::ENTRY_POINT {
+            true => memory.read_opcode(address).ok(),
+            false => None,
+        };
+        match opcode {
+            Some(opcode) => Processor::generate_cached_instruction(opcode, profile),
+            None => Box::new(
+                |_: &mut Processor, _: &mut Memory, _: &mut ConcretePeriphery| {
+                    ExecutionResult::Error
+                },
+            ),
+        }
+    }
+
+    /// Regenerates all cached instructions that overlap the given range of the memory (has to
+    /// be called after modifying code).
+    pub fn regenerate(&mut self, memory: &Memory, range: Range<usize>, profile: MachineProfile) {
+        let first = range.start / Instruction::SIZE;
+        let end = range.end.div_ceil(Instruction::SIZE);
+        for index in first..end.min(self.cache.len()) {
+            self.cache[index] =
+                Self::generate_entry(memory, (index * Instruction::SIZE) as Address, profile);
+        }
+    }
+}
+
+/// The state of a core besides its registers (used by the debugger to restore earlier states).
+#[cfg(feature = "debugger")]
+#[derive(Clone, Debug)]
+pub struct ProcessorState {
+    cycle_count: u64,
+    checkpoint_counter: Word,
+    mode: PrivilegeMode,
+    trap_state: TrapState,
+    mmu_enabled: bool,
+    page_table_base: Address,
+    wake_up_pending: bool,
+    waiting_for_wake_up: bool,
+}
+
+/// A data access of an instruction to the (physical) memory range. The bytes are the contents
+/// of the range before the access.
+#[cfg(feature = "debugger")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataAccess {
+    pub range: Range<usize>,
+    pub access: MemoryAccess,
+    pub old_bytes: Vec<Byte>,
+}
+
 pub struct Processor {
     pub registers: Registers<{ NUM_REGISTERS }>,
     cycle_count: u64,
     exit_on_halt: bool,
     checkpoint_counter: Word,
+    mode: PrivilegeMode,
+    trap_state: TrapState,
+    mmu: Mmu,
+    core_id: Word,
+    num_cores: Word,
+    /// The part of the stack region that belongs to this core.
+    stack_range: Range<Address>,
+    wake_up_pending: bool,
+    waiting_for_wake_up: bool,
+    /// Records the data accesses of the current instruction if set (used for watchpoints).
+    #[cfg(feature = "debugger")]
+    pub data_accesses: Option<Vec<DataAccess>>,
 }
 
 impl Processor {
+    pub const FRAME_POINTER: Register = Register((NUM_REGISTERS - 4) as _);
     pub const FLAGS: Register = Register((NUM_REGISTERS - 3) as _);
     pub const INSTRUCTION_POINTER: Register = Register((NUM_REGISTERS - 2) as _);
     pub const STACK_POINTER: Register = Register((NUM_REGISTERS - 1) as _);
 
-    pub fn new(exit_on_halt: bool) -> Self {
+    /// Creates the core with the given ID. All cores start at the entry point, each with its
+    /// own part of the stack.
+    pub fn new(exit_on_halt: bool, core_id: Word, num_cores: Word) -> Self {
+        assert!(core_id < num_cores && num_cores <= MAX_NUM_CORES);
+        let stack_size_per_core = (address_constants::STACK_SIZE / num_cores as usize / Word::SIZE
+            * Word::SIZE) as Address;
+        let stack_start = address_constants::STACK_START + core_id * stack_size_per_core;
         let mut result = Self {
             registers: Registers([0; NUM_REGISTERS]),
             cycle_count: 0,
             exit_on_halt,
             checkpoint_counter: 0,
+            mode: PrivilegeMode::default(),
+            trap_state: TrapState::default(),
+            mmu: Mmu::default(),
+            core_id,
+            num_cores,
+            stack_range: stack_start..stack_start + stack_size_per_core,
+            wake_up_pending: false,
+            waiting_for_wake_up: false,
+            #[cfg(feature = "debugger")]
+            data_accesses: None,
         };
         result.registers[Self::INSTRUCTION_POINTER] = address_constants::ENTRY_POINT;
-        result.registers[Self::STACK_POINTER] = address_constants::STACK_START;
+        result.registers[Self::STACK_POINTER] = stack_start;
         result
     }
 
@@ -121,36 +251,196 @@ impl Processor {
         self.registers[Self::FLAGS] = flags.bits;
     }
 
+    pub fn set_float_flags(&mut self, result: f32, inexact: bool) {
+        self.set_flag(Flag::Zero, result == 0.0);
+        self.set_flag(Flag::NaN, result.is_nan());
+        self.set_flag(Flag::Infinity, result.is_infinite());
+        self.set_flag(Flag::Inexact, inexact);
+    }
+
     pub fn get_stack_pointer(&self) -> Address {
         self.registers[Self::STACK_POINTER]
     }
 
+    /// The stack pointer may point anywhere, e.g. to a kernel stack outside of the stack region
+    /// or to a virtual address while paging is enabled.
     pub fn set_stack_pointer(&mut self, address: Address) {
-        debug_assert!((address_constants::STACK_START
-            ..=address_constants::STACK_START + address_constants::STACK_SIZE as Address)
-            .contains(&address));
         self.registers[Self::STACK_POINTER] = address;
     }
 
     pub fn advance_stack_pointer(&mut self, step: usize, direction: Direction) {
         match direction {
             Direction::Forwards => {
-                self.set_stack_pointer(self.get_stack_pointer() + step as Address)
+                self.set_stack_pointer(self.get_stack_pointer().wrapping_add(step as Address))
             }
             Direction::Backwards => {
-                self.set_stack_pointer(self.get_stack_pointer() - step as Address)
+                self.set_stack_pointer(self.get_stack_pointer().wrapping_sub(step as Address))
             }
         }
     }
 
-    pub fn stack_push(&mut self, memory: &mut Memory, value: Word) {
-        memory.write_data(self.get_stack_pointer(), value);
+    /// Pushes the value onto the stack. Returns `None` if the access raised a trap.
+    #[must_use]
+    pub fn stack_push(&mut self, memory: &mut Memory, value: Word) -> Option<()> {
+        self.check_stack_access(self.get_stack_pointer(), Word::SIZE)?;
+        let address = self.check_memory_access(
+            memory,
+            self.get_stack_pointer(),
+            Word::SIZE,
+            MemoryAccess::Write,
+        )?;
+        memory.write_data(address, value);
         self.advance_stack_pointer(Word::SIZE, Direction::Forwards);
+        Some(())
     }
 
-    pub fn stack_pop(&mut self, memory: &mut Memory) -> Word {
+    /// Pops a value from the stack. Returns `None` if the access raised a trap.
+    #[must_use]
+    pub fn stack_pop(&mut self, memory: &mut Memory) -> Option<Word> {
+        let top = self.get_stack_pointer().wrapping_sub(Word::SIZE as Address);
+        self.check_stack_access(top, Word::SIZE)?;
+        let address = self.check_memory_access(memory, top, Word::SIZE, MemoryAccess::Read)?;
         self.advance_stack_pointer(Word::SIZE, Direction::Backwards);
-        memory.read_data(self.get_stack_pointer())
+        Some(memory.read_data(address))
+    }
+
+    /// Checks that a stack access of `size` bytes starting at `address` stays within the part of
+    /// the stack region that belongs to this core. Otherwise, a stack fault is raised and `None`
+    /// is returned. Accesses that neither overlap nor border the stack region (e.g. to a stack
+    /// set up by the kernel) and accesses while paging is enabled are not checked.
+    #[must_use]
+    pub fn check_stack_access(&mut self, address: Address, size: usize) -> Option<()> {
+        let start = address as u64;
+        let end = start + size as u64;
+        let stack_region = address_constants::STACK_START as u64
+            ..address_constants::STACK_START as u64 + address_constants::STACK_SIZE as u64;
+        let at_stack_region = start <= stack_region.end && end >= stack_region.start;
+        let in_own_part =
+            start >= self.stack_range.start as u64 && end <= self.stack_range.end as u64;
+        let in_memory = end <= Memory::SIZE as u64;
+        if self.mmu.enabled || (in_memory && (!at_stack_region || in_own_part)) {
+            return Some(());
+        }
+        self.raise_trap(
+            TrapCause::StackFault,
+            address,
+            self.get_instruction_pointer(),
+        );
+        None
+    }
+
+    /// Saves the return address and the current mode, switches into supervisor mode and
+    /// continues execution at the trap vector.
+    pub fn raise_trap(&mut self, cause: TrapCause, value: Word, return_address: Address) {
+        self.trap_state.cause = cause as Word;
+        self.trap_state.value = value;
+        self.trap_state.return_address = return_address;
+        self.trap_state.previous_mode = self.mode;
+        self.mode = PrivilegeMode::Supervisor;
+        self.set_instruction_pointer(self.trap_state.vector);
+        // the instruction is aborted, so its accesses never happened
+        #[cfg(feature = "debugger")]
+        if let Some(data_accesses) = &mut self.data_accesses {
+            data_accesses.clear();
+        }
+    }
+
+    /// Checks whether the current instruction may access the `size` bytes starting at
+    /// `address` and returns the (physical) address to use for the access. Otherwise, a
+    /// protection fault or page fault is raised and `None` is returned. The instruction has to
+    /// be aborted in that case (without any side effects, so that it can be restarted).
+    #[must_use]
+    pub fn check_memory_access(
+        &mut self,
+        memory: &Memory,
+        address: Address,
+        size: usize,
+        access: MemoryAccess,
+    ) -> Option<Address> {
+        let physical_address = if self.mmu.enabled {
+            // accesses are aligned, so they never cross a page boundary
+            let physical_address = self.mmu.translate(memory, address, access, self.mode);
+            if physical_address.is_none() {
+                self.raise_trap(
+                    TrapCause::PageFault,
+                    address,
+                    self.get_instruction_pointer(),
+                );
+            }
+            physical_address?
+        } else {
+            if self.mode == PrivilegeMode::User && !self.trap_state.is_user_memory(address, size) {
+                self.raise_trap(
+                    TrapCause::ProtectionFault,
+                    address,
+                    self.get_instruction_pointer(),
+                );
+                return None;
+            }
+            address
+        };
+        #[cfg(feature = "debugger")]
+        self.record_data_access(memory, physical_address, size, access);
+        Some(physical_address)
+    }
+
+    #[cfg(feature = "debugger")]
+    fn record_data_access(
+        &mut self,
+        memory: &Memory,
+        physical_address: Address,
+        size: usize,
+        access: MemoryAccess,
+    ) {
+        let Some(data_accesses) = &mut self.data_accesses else {
+            return;
+        };
+        if access == MemoryAccess::Execute {
+            return;
+        }
+        let start = (physical_address as usize).min(Memory::SIZE);
+        let range = start..start.saturating_add(size).min(Memory::SIZE);
+        data_accesses.push(DataAccess {
+            old_bytes: memory.data()[range.clone()].to_vec(),
+            range,
+            access,
+        });
+    }
+
+    /// Checks the access to the memory block of the given length starting at `address` and
+    /// returns the physical memory ranges it consists of (one per page when paging is
+    /// enabled). Otherwise, the result the instruction has to return is passed as the error:
+    /// `ExecutionResult::Error` if the block exceeds the address space and
+    /// `ExecutionResult::Normal` if a trap has been raised.
+    pub fn check_block_access(
+        &mut self,
+        memory: &Memory,
+        address: Address,
+        length: Word,
+        access: MemoryAccess,
+    ) -> Result<Vec<Range<usize>>, ExecutionResult> {
+        if !self.mmu.enabled {
+            let range = Memory::block_range(address, length).ok_or(ExecutionResult::Error)?;
+            self.check_memory_access(memory, address, range.len(), access)
+                .ok_or(ExecutionResult::Normal)?;
+            return Ok(vec![range]);
+        }
+        let end = address as u64 + length as u64;
+        if end > 1 << Address::BITS {
+            return Err(ExecutionResult::Error);
+        }
+        let mut ranges = Vec::new();
+        let mut current = address as u64;
+        while current < end {
+            let page_end = ((current / PAGE_SIZE as u64 + 1) * PAGE_SIZE as u64).min(end);
+            let size = (page_end - current) as usize;
+            let physical_address = self
+                .check_memory_access(memory, current as Address, size, access)
+                .ok_or(ExecutionResult::Normal)? as usize;
+            ranges.push(physical_address..physical_address + size);
+            current = page_end;
+        }
+        Ok(ranges)
     }
 
     pub fn set_instruction_pointer(&mut self, address: Address) {
@@ -173,26 +463,82 @@ impl Processor {
         }
     }
 
+    pub fn is_waiting_for_wake_up(&self) -> bool {
+        self.waiting_for_wake_up
+    }
+
+    /// Resumes the core if it is waiting for a wake-up. Otherwise, its next `WaitForWakeUp`
+    /// instruction returns immediately.
+    pub fn wake_up(&mut self) {
+        self.wake_up_pending = true;
+        self.waiting_for_wake_up = false;
+    }
+
     pub fn get_cycle_count(&self) -> u64 {
         self.cycle_count
     }
 
+    #[cfg(feature = "debugger")]
+    pub fn core_id(&self) -> Word {
+        self.core_id
+    }
+
+    /// Returns the state of the core besides its registers.
+    #[cfg(feature = "debugger")]
+    pub fn state(&self) -> ProcessorState {
+        ProcessorState {
+            cycle_count: self.cycle_count,
+            checkpoint_counter: self.checkpoint_counter,
+            mode: self.mode,
+            trap_state: self.trap_state.clone(),
+            mmu_enabled: self.mmu.enabled,
+            page_table_base: self.mmu.page_table_base(),
+            wake_up_pending: self.wake_up_pending,
+            waiting_for_wake_up: self.waiting_for_wake_up,
+        }
+    }
+
+    /// Restores a state returned by `state` (flushes the TLB).
+    #[cfg(feature = "debugger")]
+    pub fn restore_state(&mut self, state: ProcessorState) {
+        self.cycle_count = state.cycle_count;
+        self.checkpoint_counter = state.checkpoint_counter;
+        self.mode = state.mode;
+        self.trap_state = state.trap_state;
+        self.mmu.enabled = state.mmu_enabled;
+        self.mmu.set_page_table_base(state.page_table_base);
+        self.wake_up_pending = state.wake_up_pending;
+        self.waiting_for_wake_up = state.waiting_for_wake_up;
+    }
+
     pub fn increase_cycle_count(&mut self, amount: u64) {
         self.cycle_count += amount;
     }
 
-    pub fn generate_cached_instruction<ConcretePeriphery: Periphery>(
+    /// Jumps to the given address and charges the additional cycles of a taken branch.
+    fn take_branch(&mut self, address: Address, branch_taken_penalty: u64) {
+        self.set_instruction_pointer(address);
+        self.increase_cycle_count(branch_taken_penalty);
+    }
+
+    pub fn generate_cached_instruction<ConcretePeriphery: Periphery + 'static>(
         opcode: Opcode,
+        profile: MachineProfile,
     ) -> CachedInstruction<ConcretePeriphery> {
         use crate::processor::Opcode::*;
+        let num_cycles = opcode.get_num_cycles(profile).into();
+        let branch_taken_penalty = profile.branch_taken_penalty().into();
+        let block_cycles_per_word: u64 = profile.block_cycles_per_word().into();
+        let block_cycles =
+            move |length: Word| (length as u64).div_ceil(Word::SIZE as u64) * block_cycles_per_word;
         let handle_cycle_count_and_instruction_pointer = move |processor: &mut Processor| {
-            processor.increase_cycle_count(opcode.get_num_cycles().into());
+            processor.increase_cycle_count(num_cycles);
             if opcode.should_increment_instruction_pointer() {
                 processor.advance_instruction_pointer(Direction::Forwards);
             }
         };
 
-        match opcode {
+        let instruction = match opcode {
             MoveRegisterImmediate {
                 register,
                 immediate,
@@ -212,6 +558,14 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
                     processor.registers[register] = memory.read_data(address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
@@ -234,6 +588,14 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
                     memory.write_data(address, processor.registers[register]);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
@@ -243,7 +605,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] = memory.read_data(processor.registers[pointer]);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_data(address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -253,7 +623,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_data(processor.registers[pointer], processor.registers[source]);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_data(address, processor.registers[source]);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -266,7 +644,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[register] = memory.read_byte(source_address) as Word;
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        source_address,
+                        Byte::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[register] = memory.read_byte(address) as Word;
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -278,7 +664,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_byte(target_address, processor.registers[register] as u8);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        target_address,
+                        Byte::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_byte(address, processor.registers[register] as u8);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -287,8 +681,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] =
-                        memory.read_byte(processor.registers[pointer]) as Word;
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Byte::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_byte(address) as Word;
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -298,10 +699,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_byte(
+                    let Some(address) = processor.check_memory_access(
+                        memory,
                         processor.registers[pointer],
-                        processor.registers[source] as u8,
-                    );
+                        Byte::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_byte(address, processor.registers[source] as u8);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -314,7 +720,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[register] = memory.read_halfword(source_address).into();
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        source_address,
+                        Halfword::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[register] = memory.read_halfword(address).into();
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -326,7 +740,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_halfword(target_address, processor.registers[register] as u16);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        target_address,
+                        Halfword::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_halfword(address, processor.registers[register] as u16);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -335,8 +757,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] =
-                        memory.read_halfword(processor.registers[pointer]).into();
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Halfword::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_halfword(address).into();
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -346,10 +775,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_halfword(
+                    let Some(address) = processor.check_memory_access(
+                        memory,
                         processor.registers[pointer],
-                        processor.registers[source] as u16,
-                    );
+                        Halfword::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_halfword(address, processor.registers[source] as u16);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -363,10 +797,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_data(
+                    let Some(address) = processor.check_memory_access(
+                        memory,
                         processor.registers[pointer].wrapping_add(immediate),
-                        processor.registers[source],
-                    );
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_data(address, processor.registers[source]);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -379,10 +818,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_byte(
+                    let Some(address) = processor.check_memory_access(
+                        memory,
                         processor.registers[pointer].wrapping_add(immediate),
-                        processor.registers[source] as Byte,
-                    );
+                        Byte::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_byte(address, processor.registers[source] as Byte);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -395,10 +839,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    memory.write_halfword(
+                    let Some(address) = processor.check_memory_access(
+                        memory,
                         processor.registers[pointer].wrapping_add(immediate),
-                        processor.registers[source] as Halfword,
-                    );
+                        Halfword::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_halfword(address, processor.registers[source] as Halfword);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -411,8 +860,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] =
-                        memory.read_data(processor.registers[pointer].wrapping_add(immediate));
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer].wrapping_add(immediate),
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_data(address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -425,9 +881,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] = memory
-                        .read_byte(processor.registers[pointer].wrapping_add(immediate))
-                        .into();
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer].wrapping_add(immediate),
+                        Byte::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_byte(address).into();
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -440,9 +902,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] = memory
-                        .read_halfword(processor.registers[pointer].wrapping_add(immediate))
-                        .into();
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer].wrapping_add(immediate),
+                        Halfword::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_halfword(address).into();
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -469,6 +937,7 @@ impl Processor {
                     (processor.registers[target], did_overflow) = lhs.overflowing_add(rhs);
                     processor.set_flag(Flag::Zero, processor.registers[target] == 0);
                     processor.set_flag(Flag::Carry, did_overflow);
+                    processor.set_flag(Flag::Overflow, (lhs as i32).overflowing_add(rhs as i32).1);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -484,6 +953,7 @@ impl Processor {
                     (processor.registers[target], did_overflow) = lhs.overflowing_sub(rhs);
                     processor.set_flag(Flag::Zero, processor.registers[target] == 0);
                     processor.set_flag(Flag::Carry, did_overflow);
+                    processor.set_flag(Flag::Overflow, (lhs as i32).overflowing_sub(rhs as i32).1);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -508,6 +978,12 @@ impl Processor {
                         Flag::Carry,
                         did_overflow || did_overflow_after_subtracting_carry,
                     );
+                    processor.set_flag(
+                        Flag::Overflow,
+                        signed_result_overflows(
+                            lhs as i32 as i64 - rhs as i32 as i64 - carry_flag_set as i64,
+                        ),
+                    );
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -659,11 +1135,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
+                    let source_value = processor.registers[source];
                     let carry;
-                    (processor.registers[target], carry) =
-                        processor.registers[source].overflowing_add(immediate);
+                    (processor.registers[target], carry) = source_value.overflowing_add(immediate);
                     processor.set_flag(Flag::Zero, processor.registers[target] == 0);
                     processor.set_flag(Flag::Carry, carry);
+                    processor.set_flag(
+                        Flag::Overflow,
+                        (source_value as i32).overflowing_add(immediate as i32).1,
+                    );
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -676,10 +1156,14 @@ impl Processor {
                 move |processor: &mut Processor,
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[target] =
-                        processor.registers[source].wrapping_sub(immediate);
+                    let source_value = processor.registers[source];
+                    processor.registers[target] = source_value.wrapping_sub(immediate);
                     processor.set_flag(Flag::Zero, processor.registers[target] == 0);
-                    processor.set_flag(Flag::Carry, immediate > processor.registers[source]);
+                    processor.set_flag(Flag::Carry, immediate > source_value);
+                    processor.set_flag(
+                        Flag::Overflow,
+                        (source_value as i32).overflowing_sub(immediate as i32).1,
+                    );
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -705,7 +1189,12 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.stack_push(memory, processor.registers[register]);
+                    if processor
+                        .stack_push(memory, processor.registers[register])
+                        .is_none()
+                    {
+                        return ExecutionResult::Normal;
+                    }
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -714,7 +1203,9 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.stack_push(memory, immediate);
+                    if processor.stack_push(memory, immediate).is_none() {
+                        return ExecutionResult::Normal;
+                    }
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -723,7 +1214,10 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.registers[register] = processor.stack_pop(memory);
+                    let Some(value) = processor.stack_pop(memory) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[register] = value;
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -732,7 +1226,9 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.stack_pop(memory);
+                    if processor.stack_pop(memory).is_none() {
+                        return ExecutionResult::Normal;
+                    }
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -741,7 +1237,9 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.push_instruction_pointer(memory);
+                    if processor.push_instruction_pointer(memory).is_none() {
+                        return ExecutionResult::Normal;
+                    }
                     processor.set_instruction_pointer(address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
@@ -752,7 +1250,9 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    let return_address = processor.stack_pop(memory);
+                    let Some(return_address) = processor.stack_pop(memory) else {
+                        return ExecutionResult::Normal;
+                    };
                     processor.set_instruction_pointer(return_address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
@@ -785,7 +1285,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        0 => processor.set_instruction_pointer(address),
+                        0 => processor.take_branch(address, branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     }
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -800,7 +1300,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        1 => processor.set_instruction_pointer(address),
+                        1 => processor.take_branch(address, branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -815,7 +1315,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        Word::MAX => processor.set_instruction_pointer(address),
+                        Word::MAX => processor.take_branch(address, branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -830,7 +1330,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        1 | 0 => processor.set_instruction_pointer(address),
+                        1 | 0 => processor.take_branch(address, branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -845,7 +1345,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        Word::MAX | 0 => processor.set_instruction_pointer(address),
+                        Word::MAX | 0 => processor.take_branch(address, branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     }
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -857,7 +1357,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Zero) {
-                        true => processor.set_instruction_pointer(address),
+                        true => processor.take_branch(address, branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -870,7 +1370,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Zero) {
-                        false => processor.set_instruction_pointer(address),
+                        false => processor.take_branch(address, branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -883,7 +1383,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Carry) {
-                        true => processor.set_instruction_pointer(address),
+                        true => processor.take_branch(address, branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -896,7 +1396,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Carry) {
-                        false => processor.set_instruction_pointer(address),
+                        false => processor.take_branch(address, branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -909,7 +1409,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::DivideByZero) {
-                        true => processor.set_instruction_pointer(address),
+                        true => processor.take_branch(address, branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -922,7 +1422,7 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::DivideByZero) {
-                        false => processor.set_instruction_pointer(address),
+                        false => processor.take_branch(address, branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     }
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -938,7 +1438,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        0 => processor.set_instruction_pointer(processor.registers[pointer]),
+                        0 => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -953,7 +1454,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        1 => processor.set_instruction_pointer(processor.registers[pointer]),
+                        1 => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -968,9 +1470,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        Word::MAX => {
-                            processor.set_instruction_pointer(processor.registers[pointer])
-                        }
+                        Word::MAX => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -985,7 +1486,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        1 | 0 => processor.set_instruction_pointer(processor.registers[pointer]),
+                        1 | 0 => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     }
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1000,9 +1502,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.registers[comparison] {
-                        Word::MAX | 0 => {
-                            processor.set_instruction_pointer(processor.registers[pointer])
-                        }
+                        Word::MAX | 0 => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         _ => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1014,7 +1515,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Zero) {
-                        true => processor.set_instruction_pointer(processor.registers[pointer]),
+                        true => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1026,7 +1528,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Zero) {
-                        false => processor.set_instruction_pointer(processor.registers[pointer]),
+                        false => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1039,7 +1542,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Carry) {
-                        true => processor.set_instruction_pointer(processor.registers[pointer]),
+                        true => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1052,7 +1556,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::Carry) {
-                        false => processor.set_instruction_pointer(processor.registers[pointer]),
+                        false => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1065,7 +1570,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::DivideByZero) {
-                        true => processor.set_instruction_pointer(processor.registers[pointer]),
+                        true => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         false => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1078,7 +1584,8 @@ impl Processor {
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
                     match processor.get_flag(Flag::DivideByZero) {
-                        false => processor.set_instruction_pointer(processor.registers[pointer]),
+                        false => processor
+                            .take_branch(processor.registers[pointer], branch_taken_penalty),
                         true => processor.advance_instruction_pointer(Direction::Forwards),
                     };
                     handle_cycle_count_and_instruction_pointer(processor);
@@ -1111,6 +1618,72 @@ impl Processor {
                 },
             )
                 as CachedInstruction<ConcretePeriphery>,
+            PollMouse {
+                x,
+                y,
+                buttons,
+                wheel,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    let state = periphery.mouse().get_state();
+                    processor.registers[x] = state.x;
+                    processor.registers[y] = state.y;
+                    processor.registers[buttons] = state.buttons.bits();
+                    processor.registers[wheel] = periphery.mouse().take_wheel_delta() as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            GetGamepadButtonsTargetGamepad { target, gamepad } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = periphery
+                        .gamepads()
+                        .get_state(processor.registers[gamepad], processor.get_cycle_count())
+                        .map_or(0, |state| state.buttons);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            GetGamepadAxisTargetGamepadAxis {
+                target,
+                gamepad,
+                axis,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    let state = periphery
+                        .gamepads()
+                        .get_state(processor.registers[gamepad], processor.get_cycle_count());
+                    let value = match (state, GamepadAxis::try_from(processor.registers[axis])) {
+                        (Some(state), Ok(axis)) => state.scaled_axis(axis),
+                        _ => 0,
+                    };
+                    processor.registers[target] = value as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            IsGamepadConnectedTargetGamepad { target, gamepad } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = periphery
+                        .gamepads()
+                        .get_state(processor.registers[gamepad], processor.get_cycle_count())
+                        .is_some()
+                        .into();
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
             PollTime { high, low } => Box::new(
                 move |processor: &mut Processor,
                       _memory: &mut Memory,
@@ -1122,6 +1695,61 @@ impl Processor {
                     ExecutionResult::Normal
                 },
             ) as CachedInstruction<ConcretePeriphery>,
+            GetRandomTarget { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = periphery.random().next_word();
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            PollDateTime {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    let date_time = periphery.rtc().latch(processor.get_cycle_count());
+                    processor.registers[year] = date_time.year() as Word;
+                    processor.registers[month] = date_time.month();
+                    processor.registers[day] = date_time.day();
+                    processor.registers[hour] = date_time.hour();
+                    processor.registers[minute] = date_time.minute();
+                    processor.registers[second] = date_time.second();
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            GetWeekdayTimezoneOffset { weekday, offset } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    let date_time = periphery.rtc().latched();
+                    processor.registers[weekday] = date_time.weekday().num_days_from_monday();
+                    processor.registers[offset] = date_time.offset().local_minus_utc() as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTimezoneOffsetSource { source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      periphery: &mut ConcretePeriphery| {
+                    periphery
+                        .rtc()
+                        .set_timezone_offset(processor.registers[source] as i32);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
             AddWithCarryTargetLhsRhs { target, lhs, rhs } => Box::new(
                 move |processor: &mut Processor,
                       _memory: &mut Memory,
@@ -1133,9 +1761,15 @@ impl Processor {
                         + processor.registers[rhs] as u64
                         + processor.get_flag(Flag::Carry) as u64)
                         > Word::MAX as u64;
+                    let signed_overflow_happened = signed_result_overflows(
+                        processor.registers[lhs] as i32 as i64
+                            + processor.registers[rhs] as i32 as i64
+                            + processor.get_flag(Flag::Carry) as i64,
+                    );
                     processor.registers[target] = result;
                     processor.set_flag(Flag::Zero, processor.registers[target] == 0);
                     processor.set_flag(Flag::Carry, overflow_happened);
+                    processor.set_flag(Flag::Overflow, signed_overflow_happened);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -1145,7 +1779,9 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.push_instruction_pointer(memory);
+                    if processor.push_instruction_pointer(memory).is_none() {
+                        return ExecutionResult::Normal;
+                    }
                     processor.set_instruction_pointer(processor.registers[register]);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
@@ -1155,9 +1791,19 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    processor.push_instruction_pointer(memory);
-                    processor
-                        .set_instruction_pointer(memory.read_data(processor.registers[pointer]));
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    let target_address = memory.read_data(address);
+                    if processor.push_instruction_pointer(memory).is_none() {
+                        return ExecutionResult::Normal;
+                    }
+                    processor.set_instruction_pointer(target_address);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -1245,7 +1891,15 @@ impl Processor {
                 move |processor: &mut Processor,
                       memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    assert_eq!(memory.read_data(processor.registers[pointer]), immediate);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    assert_eq!(memory.read_data(address), immediate);
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
@@ -1361,39 +2015,1668 @@ impl Processor {
                 },
             )
                 as CachedInstruction<ConcretePeriphery>,
-            Checkpoint { immediate } => Box::new(
+            MultiplySignedHighLowLhsRhs {
+                high,
+                low,
+                lhs,
+                rhs,
+            } => Box::new(
                 move |processor: &mut Processor,
                       _memory: &mut Memory,
                       _periphery: &mut ConcretePeriphery| {
-                    if immediate != processor.checkpoint_counter {
-                        panic!(
-                            "checkpoint counter mismatch: expected {}, got {}",
-                            processor.checkpoint_counter, immediate
-                        );
+                    let lhs = processor.registers[lhs] as i32;
+                    let rhs = processor.registers[rhs] as i32;
+                    let result = lhs as i64 * rhs as i64;
+                    processor.registers[high] = (result >> 32) as Word;
+                    processor.registers[low] = result as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[low] == 0);
+                    processor.set_flag(Flag::Overflow, signed_result_overflows(result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            DivmodSignedTargetModLhsRhs {
+                result,
+                remainder,
+                lhs,
+                rhs,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[lhs] as i32;
+                    let rhs = processor.registers[rhs] as i32;
+                    if rhs == 0 {
+                        processor.registers[result] = 0;
+                        processor.registers[remainder] = lhs as Word;
+                        processor.set_flag(Flag::Zero, true);
+                        processor.set_flag(Flag::DivideByZero, true);
+                        processor.set_flag(Flag::Overflow, false);
+                    } else {
+                        // dividing i32::MIN by -1 is the only case that overflows
+                        let (quotient, did_overflow) = lhs.overflowing_div(rhs);
+                        processor.registers[result] = quotient as Word;
+                        processor.registers[remainder] = lhs.wrapping_rem(rhs) as Word;
+                        processor.set_flag(Flag::Zero, processor.registers[result] == 0);
+                        processor.set_flag(Flag::DivideByZero, false);
+                        processor.set_flag(Flag::Overflow, did_overflow);
                     }
-                    processor.checkpoint_counter += 1;
                     handle_cycle_count_and_instruction_pointer(processor);
                     ExecutionResult::Normal
                 },
             ) as CachedInstruction<ConcretePeriphery>,
-        }
-    }
-
-    pub fn execute_next_instruction<ConcretePeriphery: Periphery>(
-        &mut self,
-        memory: &mut Memory,
-        periphery: &mut ConcretePeriphery,
-        instruction_cache: &mut InstructionCache<ConcretePeriphery>,
-    ) -> ExecutionResult {
-        let instruction_address = self.get_instruction_pointer();
-        let cache_index = instruction_address / Instruction::SIZE as Address;
-        instruction_cache.cache[cache_index as usize](self, memory, periphery)
-    }
-
-    fn push_instruction_pointer(&mut self, memory: &mut Memory) {
-        self.stack_push(
-            memory,
-            self.get_instruction_pointer() + Instruction::SIZE as Address,
-        );
+            ArithmeticRightShiftTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[lhs];
+                    let rhs = processor.registers[rhs];
+                    // shifting by 31 or more bits fills the whole word with the sign bit
+                    let result = ((lhs as i32) >> rhs.min(Word::BITS - 1)) as Word;
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(Flag::Carry, lhs != 0 && rhs > lhs.trailing_zeros());
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            CompareSignedTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[lhs] as i32;
+                    let rhs = processor.registers[rhs] as i32;
+                    processor.registers[target] = match lhs.cmp(&rhs) {
+                        std::cmp::Ordering::Less => Word::MAX,
+                        std::cmp::Ordering::Equal => 0,
+                        std::cmp::Ordering::Greater => 1,
+                    };
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedGreater { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        if (processor.registers[lhs] as i32) > (processor.registers[rhs] as i32) {
+                            1
+                        } else {
+                            0
+                        };
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedGreaterOrEquals { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        if (processor.registers[lhs] as i32) >= (processor.registers[rhs] as i32) {
+                            1
+                        } else {
+                            0
+                        };
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedLess { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        if (processor.registers[lhs] as i32) < (processor.registers[rhs] as i32) {
+                            1
+                        } else {
+                            0
+                        };
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedLessOrEquals { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        if (processor.registers[lhs] as i32) <= (processor.registers[rhs] as i32) {
+                            1
+                        } else {
+                            0
+                        };
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatAddTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = f32::from_bits(processor.registers[lhs]);
+                    let rhs = f32::from_bits(processor.registers[rhs]);
+                    let result = lhs + rhs;
+                    processor.registers[target] = result.to_bits();
+                    processor.set_float_flags(result, float_sum_is_inexact(lhs, rhs, result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatSubtractTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = f32::from_bits(processor.registers[lhs]);
+                    let rhs = f32::from_bits(processor.registers[rhs]);
+                    let result = lhs - rhs;
+                    processor.registers[target] = result.to_bits();
+                    processor.set_float_flags(result, float_sum_is_inexact(lhs, -rhs, result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatMultiplyTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = f32::from_bits(processor.registers[lhs]);
+                    let rhs = f32::from_bits(processor.registers[rhs]);
+                    let result = lhs * rhs;
+                    processor.registers[target] = result.to_bits();
+                    // the product of two single precision values is exact in double precision
+                    let exact_result = lhs as f64 * rhs as f64;
+                    processor
+                        .set_float_flags(result, float_result_is_inexact(exact_result, result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatDivideTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = f32::from_bits(processor.registers[lhs]);
+                    let rhs = f32::from_bits(processor.registers[rhs]);
+                    let result = lhs / rhs;
+                    processor.registers[target] = result.to_bits();
+                    let inexact = match (result.is_finite(), rhs == 0.0) {
+                        (_, true) => false,
+                        (false, false) => lhs.is_finite() && rhs.is_finite(),
+                        // the division was exact if multiplying back yields the dividend
+                        (true, false) => float_result_is_inexact(result as f64 * rhs as f64, lhs),
+                    };
+                    processor.set_float_flags(result, inexact);
+                    processor.set_flag(Flag::DivideByZero, rhs == 0.0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatCompareTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = f32::from_bits(processor.registers[lhs]);
+                    let rhs = f32::from_bits(processor.registers[rhs]);
+                    processor.registers[target] = match lhs.partial_cmp(&rhs) {
+                        Some(std::cmp::Ordering::Less) => Word::MAX,
+                        Some(std::cmp::Ordering::Equal) => 0,
+                        Some(std::cmp::Ordering::Greater) => 1,
+                        None => 2,
+                    };
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::NaN, lhs.is_nan() || rhs.is_nan());
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatSquareRootTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = f32::from_bits(processor.registers[source]);
+                    let result = source.sqrt();
+                    processor.registers[target] = result.to_bits();
+                    let inexact = result.is_finite()
+                        && float_result_is_inexact(result as f64 * result as f64, source);
+                    processor.set_float_flags(result, inexact);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatFloorTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let result = f32::from_bits(processor.registers[source]).floor();
+                    processor.registers[target] = result.to_bits();
+                    processor.set_float_flags(result, false);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatCeilTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let result = f32::from_bits(processor.registers[source]).ceil();
+                    processor.registers[target] = result.to_bits();
+                    processor.set_float_flags(result, false);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            IntegerToFloatTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source] as i32;
+                    let result = source as f32;
+                    processor.registers[target] = result.to_bits();
+                    processor
+                        .set_float_flags(result, float_result_is_inexact(source as f64, result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            FloatToIntegerTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = f32::from_bits(processor.registers[source]);
+                    // casting saturates on overflow and turns NaN into 0
+                    let result = source as i32;
+                    let truncated = source.trunc();
+                    let out_of_range = !source.is_nan()
+                        && (truncated < i32::MIN as f32 || truncated >= -(i32::MIN as f32));
+                    processor.registers[target] = result as Word;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(Flag::NaN, source.is_nan());
+                    processor.set_flag(Flag::Infinity, source.is_infinite());
+                    processor.set_flag(Flag::Inexact, source.is_finite() && truncated != source);
+                    processor.set_flag(Flag::Overflow, out_of_range);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            AndTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source] & immediate;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            OrTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source] | immediate;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            XorTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source] ^ immediate;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            LeftShiftTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    if immediate >= Word::BITS {
+                        processor.registers[target] = 0;
+                        processor.set_flag(Flag::Zero, true);
+                        processor.set_flag(Flag::Carry, source > 0);
+                    } else {
+                        let result = source << immediate;
+                        processor.registers[target] = result;
+                        processor.set_flag(Flag::Zero, result == 0);
+                        processor.set_flag(Flag::Carry, immediate > source.leading_zeros());
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            RightShiftTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    if immediate >= Word::BITS {
+                        processor.registers[target] = 0;
+                        processor.set_flag(Flag::Zero, true);
+                        processor.set_flag(Flag::Carry, source > 0);
+                    } else {
+                        let result = source >> immediate;
+                        processor.registers[target] = result;
+                        processor.set_flag(Flag::Zero, result == 0);
+                        processor.set_flag(Flag::Carry, immediate > source.trailing_zeros());
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            ArithmeticRightShiftTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    let result = ((source as i32) >> immediate.min(Word::BITS - 1)) as Word;
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(
+                        Flag::Carry,
+                        source != 0 && immediate > source.trailing_zeros(),
+                    );
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MultiplyTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let result = processor.registers[source] as u64 * immediate as u64;
+                    processor.registers[target] = result as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::Carry, (result >> 32) > 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            DivideTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source]
+                        .checked_div(immediate)
+                        .unwrap_or(0);
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::DivideByZero, immediate == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            ModuloTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    processor.registers[target] = source.checked_rem(immediate).unwrap_or(source);
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::DivideByZero, immediate == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MultiplySignedTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let result =
+                        processor.registers[source] as i32 as i64 * immediate as i32 as i64;
+                    processor.registers[target] = result as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::Overflow, signed_result_overflows(result));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            DivideSignedTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[source] as i32;
+                    let rhs = immediate as i32;
+                    // dividing i32::MIN by -1 is the only case that overflows
+                    let (quotient, did_overflow) = match rhs {
+                        0 => (0, false),
+                        _ => lhs.overflowing_div(rhs),
+                    };
+                    processor.registers[target] = quotient as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::DivideByZero, rhs == 0);
+                    processor.set_flag(Flag::Overflow, did_overflow);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            ModuloSignedTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[source] as i32;
+                    let rhs = immediate as i32;
+                    processor.registers[target] = match rhs {
+                        0 => lhs,
+                        _ => lhs.wrapping_rem(rhs),
+                    } as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::DivideByZero, rhs == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            AddWithCarryTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source_value = processor.registers[source];
+                    let carry_flag_set = processor.get_flag(Flag::Carry);
+                    let result = source_value as u64 + immediate as u64 + carry_flag_set as u64;
+                    processor.registers[target] = result as Word;
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::Carry, result > Word::MAX as u64);
+                    processor.set_flag(
+                        Flag::Overflow,
+                        signed_result_overflows(
+                            source_value as i32 as i64
+                                + immediate as i32 as i64
+                                + carry_flag_set as i64,
+                        ),
+                    );
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SubtractWithCarryTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source_value = processor.registers[source];
+                    let carry_flag_set = processor.get_flag(Flag::Carry);
+                    let (difference, did_overflow) = source_value.overflowing_sub(immediate);
+                    let (result, did_overflow_after_subtracting_carry) =
+                        difference.overflowing_sub(carry_flag_set as _);
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(
+                        Flag::Carry,
+                        did_overflow || did_overflow_after_subtracting_carry,
+                    );
+                    processor.set_flag(
+                        Flag::Overflow,
+                        signed_result_overflows(
+                            source_value as i32 as i64
+                                - immediate as i32 as i64
+                                - carry_flag_set as i64,
+                        ),
+                    );
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            CompareTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[source];
+                    let rhs = immediate;
+                    processor.registers[target] = match lhs.cmp(&rhs) {
+                        std::cmp::Ordering::Less => Word::MAX,
+                        std::cmp::Ordering::Equal => 0,
+                        std::cmp::Ordering::Greater => 1,
+                    };
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            CompareSignedTargetSourceImmediate {
+                target,
+                source,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[source] as i32;
+                    let rhs = immediate as i32;
+                    processor.registers[target] = match lhs.cmp(&rhs) {
+                        std::cmp::Ordering::Less => Word::MAX,
+                        std::cmp::Ordering::Equal => 0,
+                        std::cmp::Ordering::Greater => 1,
+                    };
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] == immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareNotEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] != immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareGreaterImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] > immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareGreaterOrEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] >= immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareLessImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] < immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareLessOrEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.registers[lhs] <= immediate) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedGreaterImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        ((processor.registers[lhs] as i32) > (immediate as i32)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedGreaterOrEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        ((processor.registers[lhs] as i32) >= (immediate as i32)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedLessImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        ((processor.registers[lhs] as i32) < (immediate as i32)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BoolCompareSignedLessOrEqualsImmediate {
+                target,
+                lhs,
+                immediate,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        ((processor.registers[lhs] as i32) <= (immediate as i32)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MemoryCopyDestinationSourceLength {
+                destination,
+                source,
+                length,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let length = processor.registers[length];
+                    let destination = match processor.check_block_access(
+                        memory,
+                        processor.registers[destination],
+                        length,
+                        MemoryAccess::Write,
+                    ) {
+                        Ok(ranges) => ranges,
+                        Err(result) => return result,
+                    };
+                    let source = match processor.check_block_access(
+                        memory,
+                        processor.registers[source],
+                        length,
+                        MemoryAccess::Read,
+                    ) {
+                        Ok(ranges) => ranges,
+                        Err(result) => return result,
+                    };
+                    match (source.as_slice(), destination.as_slice()) {
+                        ([source], [destination]) => memory
+                            .data_mut()
+                            .copy_within(source.clone(), destination.start),
+                        _ => {
+                            // the blocks are scattered across the physical memory
+                            let bytes: Vec<Byte> = source
+                                .into_iter()
+                                .flat_map(|range| memory.data()[range].to_vec())
+                                .collect();
+                            let mut remaining = bytes.as_slice();
+                            for range in destination {
+                                let (chunk, rest) = remaining.split_at(range.len());
+                                memory.data_mut()[range].copy_from_slice(chunk);
+                                remaining = rest;
+                            }
+                        }
+                    }
+                    processor.increase_cycle_count(block_cycles(length));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MemoryFillDestinationValueLength {
+                destination,
+                value,
+                length,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let length = processor.registers[length];
+                    let destination = match processor.check_block_access(
+                        memory,
+                        processor.registers[destination],
+                        length,
+                        MemoryAccess::Write,
+                    ) {
+                        Ok(ranges) => ranges,
+                        Err(result) => return result,
+                    };
+                    for range in destination {
+                        memory.data_mut()[range].fill(processor.registers[value] as Byte);
+                    }
+                    processor.increase_cycle_count(block_cycles(length));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MemoryCompareTargetLhsRhsLength {
+                target,
+                lhs,
+                rhs,
+                length,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let length = processor.registers[length];
+                    let lhs = match processor.check_block_access(
+                        memory,
+                        processor.registers[lhs],
+                        length,
+                        MemoryAccess::Read,
+                    ) {
+                        Ok(ranges) => ranges,
+                        Err(result) => return result,
+                    };
+                    let rhs = match processor.check_block_access(
+                        memory,
+                        processor.registers[rhs],
+                        length,
+                        MemoryAccess::Read,
+                    ) {
+                        Ok(ranges) => ranges,
+                        Err(result) => return result,
+                    };
+                    let bytes = |ranges: Vec<Range<usize>>| {
+                        ranges
+                            .into_iter()
+                            .flat_map(|range| memory.data()[range].iter())
+                    };
+                    processor.registers[target] = match bytes(lhs).cmp(bytes(rhs)) {
+                        std::cmp::Ordering::Less => Word::MAX,
+                        std::cmp::Ordering::Equal => 0,
+                        std::cmp::Ordering::Greater => 1,
+                    };
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.increase_cycle_count(block_cycles(length));
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            RotateLeftTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[lhs];
+                    let rhs = processor.registers[rhs];
+                    let rotation = rhs % Word::BITS;
+                    let result = lhs.rotate_left(rotation);
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(Flag::Carry, rotation != 0 && result & 1 == 1);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            RotateRightTargetLhsRhs { target, lhs, rhs } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let lhs = processor.registers[lhs];
+                    let rhs = processor.registers[rhs];
+                    let rotation = rhs % Word::BITS;
+                    let result = lhs.rotate_right(rotation);
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    processor.set_flag(
+                        Flag::Carry,
+                        rotation != 0 && result >> (Word::BITS - 1) == 1,
+                    );
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            PopulationCountTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source].count_ones();
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            CountLeadingZerosTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    processor.registers[target] = source.leading_zeros();
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::Carry, source == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            CountTrailingZerosTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let source = processor.registers[source];
+                    processor.registers[target] = source.trailing_zeros();
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    processor.set_flag(Flag::Carry, source == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            ByteSwapTargetSource { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.registers[source].swap_bytes();
+                    processor.set_flag(Flag::Zero, processor.registers[target] == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            BitFieldExtractTargetSourceOffsetWidth {
+                target,
+                source,
+                offset,
+                width,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let offset = processor.registers[offset];
+                    let mask = bit_field_mask(processor.registers[width]);
+                    let result =
+                        processor.registers[source].checked_shr(offset).unwrap_or(0) & mask;
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            BitFieldInsertTargetSourceOffsetWidth {
+                target,
+                source,
+                offset,
+                width,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let offset = processor.registers[offset];
+                    let mask = bit_field_mask(processor.registers[width])
+                        .checked_shl(offset)
+                        .unwrap_or(0);
+                    let field = processor.registers[source].checked_shl(offset).unwrap_or(0) & mask;
+                    let result = (processor.registers[target] & !mask) | field;
+                    processor.registers[target] = result;
+                    processor.set_flag(Flag::Zero, result == 0);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetStackOffset { target, immediate } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let address = processor.registers[Processor::STACK_POINTER]
+                        .wrapping_add_signed(immediate as i32);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_data(address);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveStackSourceOffset { source, immediate } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let address = processor.registers[Processor::STACK_POINTER]
+                        .wrapping_add_signed(immediate as i32);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_data(address, processor.registers[source]);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetFrameOffset { target, immediate } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let address = processor.registers[Processor::FRAME_POINTER]
+                        .wrapping_add_signed(immediate as i32);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.registers[target] = memory.read_data(address);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveFrameSourceOffset { source, immediate } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let address = processor.registers[Processor::FRAME_POINTER]
+                        .wrapping_add_signed(immediate as i32);
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        address,
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    memory.write_data(address, processor.registers[source]);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            EnterImmediate { immediate } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    // the local variables have to fit into the stack as well
+                    if processor
+                        .check_stack_access(
+                            processor.get_stack_pointer(),
+                            Word::SIZE + immediate as usize,
+                        )
+                        .is_none()
+                        || processor
+                            .stack_push(memory, processor.registers[Processor::FRAME_POINTER])
+                            .is_none()
+                    {
+                        return ExecutionResult::Normal;
+                    }
+                    processor.registers[Processor::FRAME_POINTER] = processor.get_stack_pointer();
+                    processor.advance_stack_pointer(immediate as usize, Direction::Forwards);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            Leave {} => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let saved_frame_pointer = processor.registers[Processor::FRAME_POINTER]
+                        .wrapping_sub(Word::SIZE as Address);
+                    if processor
+                        .check_stack_access(saved_frame_pointer, Word::SIZE)
+                        .is_none()
+                    {
+                        return ExecutionResult::Normal;
+                    }
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        saved_frame_pointer,
+                        Word::SIZE,
+                        MemoryAccess::Read,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    processor.set_stack_pointer(saved_frame_pointer);
+                    processor.registers[Processor::FRAME_POINTER] = memory.read_data(address);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfEqual {
+                target,
+                source,
+                comparison,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if matches!(processor.registers[comparison], 0) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfGreaterThan {
+                target,
+                source,
+                comparison,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if matches!(processor.registers[comparison], 1) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfLessThan {
+                target,
+                source,
+                comparison,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if matches!(processor.registers[comparison], Word::MAX) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfGreaterThanOrEqual {
+                target,
+                source,
+                comparison,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if matches!(processor.registers[comparison], 1 | 0) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfLessThanOrEqual {
+                target,
+                source,
+                comparison,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if matches!(processor.registers[comparison], Word::MAX | 0) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfZero { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if processor.get_flag(Flag::Zero) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfNotZero { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if !processor.get_flag(Flag::Zero) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfCarry { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if processor.get_flag(Flag::Carry) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfNotCarry { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if !processor.get_flag(Flag::Carry) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfDivideByZero { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if processor.get_flag(Flag::DivideByZero) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            MoveTargetSourceIfNotDivideByZero { target, source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if !processor.get_flag(Flag::DivideByZero) {
+                        processor.registers[target] = processor.registers[source];
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfEqual { target, comparison } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        (matches!(processor.registers[comparison], 0)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfGreaterThan { target, comparison } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        (matches!(processor.registers[comparison], 1)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfLessThan { target, comparison } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        (matches!(processor.registers[comparison], Word::MAX)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfGreaterThanOrEqual { target, comparison } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        (matches!(processor.registers[comparison], 1 | 0)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfLessThanOrEqual { target, comparison } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] =
+                        (matches!(processor.registers[comparison], Word::MAX | 0)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfZero { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.get_flag(Flag::Zero)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfNotZero { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (!processor.get_flag(Flag::Zero)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfCarry { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.get_flag(Flag::Carry)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfNotCarry { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (!processor.get_flag(Flag::Carry)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfDivideByZero { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (processor.get_flag(Flag::DivideByZero)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTargetIfNotDivideByZero { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = (!processor.get_flag(Flag::DivideByZero)) as Word;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SystemCallImmediate { immediate } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let return_address =
+                        processor.get_instruction_pointer() + Instruction::SIZE as Address;
+                    processor.raise_trap(TrapCause::SystemCall, immediate, return_address);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SystemReturn {} => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.mode = std::mem::replace(
+                        &mut processor.trap_state.previous_mode,
+                        PrivilegeMode::User,
+                    );
+                    processor.set_instruction_pointer(processor.trap_state.return_address);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            SetTrapVectorSource { source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.trap_state.vector = processor.registers[source];
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            GetTrapCauseValue { cause, value } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[cause] = processor.trap_state.cause;
+                    processor.registers[value] = processor.trap_state.value;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            GetTrapReturnAddressTarget { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.trap_state.return_address;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetTrapReturnAddressSource { source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.trap_state.return_address = processor.registers[source];
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetUserMemoryStartEnd { start, end } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.trap_state.user_memory_start = processor.registers[start];
+                    processor.trap_state.user_memory_end = processor.registers[end];
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            SetPageTableBaseSource { source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor
+                        .mmu
+                        .set_page_table_base(processor.registers[source]);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            GetPageTableBaseTarget { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.mmu.page_table_base();
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            )
+                as CachedInstruction<ConcretePeriphery>,
+            EnablePaging {} => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.mmu.enabled = true;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            DisablePaging {} => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.mmu.enabled = false;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            FlushTlb {} => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.mmu.flush_tlb();
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            GetCoreIdTarget { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.core_id;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            GetNumCoresTarget { target } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    processor.registers[target] = processor.num_cores;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            CompareAndSwapTargetPointerExpectedDesired {
+                target,
+                pointer,
+                expected,
+                desired,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    let value = memory.read_data(address);
+                    let swapped = value == processor.registers[expected];
+                    if swapped {
+                        memory.write_data(address, processor.registers[desired]);
+                    }
+                    processor.registers[target] = value;
+                    processor.set_flag(Flag::Zero, swapped);
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            FetchAndAddTargetPointerSource {
+                target,
+                pointer,
+                source,
+            } => Box::new(
+                move |processor: &mut Processor,
+                      memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let Some(address) = processor.check_memory_access(
+                        memory,
+                        processor.registers[pointer],
+                        Word::SIZE,
+                        MemoryAccess::Write,
+                    ) else {
+                        return ExecutionResult::Normal;
+                    };
+                    let value = memory.read_data(address);
+                    memory.write_data(address, value.wrapping_add(processor.registers[source]));
+                    processor.registers[target] = value;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            WakeUpCoreSource { source } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    let core_id = processor.registers[source];
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::WakeUpCore(core_id)
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            WaitForWakeUp {} => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    // the instruction is repeated after waking up, then the wake-up is pending
+                    if processor.wake_up_pending {
+                        processor.wake_up_pending = false;
+                        processor.advance_instruction_pointer(Direction::Forwards);
+                    } else {
+                        processor.waiting_for_wake_up = true;
+                    }
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+            Checkpoint { immediate } => Box::new(
+                move |processor: &mut Processor,
+                      _memory: &mut Memory,
+                      _periphery: &mut ConcretePeriphery| {
+                    if immediate != processor.checkpoint_counter {
+                        panic!(
+                            "checkpoint counter mismatch: expected {}, got {}",
+                            processor.checkpoint_counter, immediate
+                        );
+                    }
+                    processor.checkpoint_counter += 1;
+                    handle_cycle_count_and_instruction_pointer(processor);
+                    ExecutionResult::Normal
+                },
+            ) as CachedInstruction<ConcretePeriphery>,
+        };
+
+        if !opcode.is_privileged() {
+            return instruction;
+        }
+        let opcode_number = (opcode.as_instruction() >> (Instruction::BITS - u16::BITS)) as Word;
+        Box::new(
+            move |processor: &mut Processor,
+                  memory: &mut Memory,
+                  periphery: &mut ConcretePeriphery| {
+                if processor.mode == PrivilegeMode::User {
+                    let instruction_address = processor.get_instruction_pointer();
+                    processor.raise_trap(
+                        TrapCause::PrivilegedInstruction,
+                        opcode_number,
+                        instruction_address,
+                    );
+                    return ExecutionResult::Normal;
+                }
+                instruction(processor, memory, periphery)
+            },
+        )
+    }
+
+    pub fn execute_next_instruction<ConcretePeriphery: Periphery>(
+        &mut self,
+        memory: &mut Memory,
+        periphery: &mut ConcretePeriphery,
+        instruction_cache: &mut InstructionCache<ConcretePeriphery>,
+    ) -> ExecutionResult {
+        let Some(instruction_address) = self.check_memory_access(
+            memory,
+            self.get_instruction_pointer(),
+            Instruction::SIZE,
+            MemoryAccess::Execute,
+        ) else {
+            return ExecutionResult::Normal;
+        };
+        // the cache is indexed by physical addresses, so it stays valid when switching between
+        // address spaces
+        let cache_index = instruction_address / Instruction::SIZE as Address;
+        instruction_cache.cache[cache_index as usize](self, memory, periphery)
+    }
+
+    #[must_use]
+    fn push_instruction_pointer(&mut self, memory: &mut Memory) -> Option<()> {
+        self.stack_push(
+            memory,
+            self.get_instruction_pointer() + Instruction::SIZE as Address,
+        )
     }
 }
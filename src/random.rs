@@ -0,0 +1,63 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::Word;
+
+/// The random words are uniformly distributed over `0..=MAX_RANDOM_WORD`.
+pub const MAX_RANDOM_WORD: Word = Word::MAX;
+
+/// Generates pseudo random words (SplitMix64). The same seed always yields the same sequence.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Creates a generator that is seeded from the randomness of the host.
+    pub fn with_host_entropy() -> Self {
+        // the standard library seeds the keys of its hash maps from the operating system
+        let mut hasher = RandomState::new().build_hasher();
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos());
+        hasher.write_u128(nanos);
+        Self::new(hasher.finish())
+    }
+
+    pub fn next_word(&mut self) -> Word {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut result = self.state;
+        result = (result ^ (result >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        result = (result ^ (result >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        result ^= result >> 31;
+        (result >> Word::BITS) as Word
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_yields_same_sequence() {
+        let mut first = Random::new(42);
+        let mut second = Random::new(42);
+        let sequence: Vec<_> = (0..16).map(|_| first.next_word()).collect();
+        let repeated_sequence: Vec<_> = (0..16).map(|_| second.next_word()).collect();
+        assert_eq!(sequence, repeated_sequence);
+        assert_ne!(sequence[0], sequence[1]);
+    }
+
+    #[test]
+    fn different_seeds_yield_different_sequences() {
+        let mut first = Random::new(1);
+        let mut second = Random::new(2);
+        assert!((0..16).any(|_| first.next_word() != second.next_word()));
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::{prelude::*, Duration};
+
+/// Determines where the real-time clock gets the current time from.
+pub enum TimeSource {
+    /// The current time of the host.
+    Host,
+    /// Always the same point in time.
+    Fixed(DateTime<Utc>),
+    /// Starts at the given point in time and advances with the executed cycles.
+    Virtual {
+        start: DateTime<Utc>,
+        cycles_per_second: u64,
+    },
+}
+
+/// Provides the calendar date and time in a configurable timezone. Reading the date and time
+/// latches it, so that all of its parts can be read consistently.
+pub struct Rtc {
+    source: TimeSource,
+    timezone_offset: FixedOffset,
+    latched: DateTime<FixedOffset>,
+}
+
+impl Rtc {
+    pub fn new(source: TimeSource, timezone_offset: FixedOffset) -> Self {
+        Self {
+            source,
+            timezone_offset,
+            latched: Utc
+                .timestamp_opt(0, 0)
+                .unwrap()
+                .with_timezone(&timezone_offset),
+        }
+    }
+
+    /// Creates a clock that uses the current time and the local timezone of the host.
+    pub fn host() -> Self {
+        Self::new(TimeSource::Host, Local::now().offset().fix())
+    }
+
+    /// Latches and returns the current date and time. The cycle count is only used for
+    /// virtual time.
+    pub fn latch(&mut self, cycle_count: u64) -> DateTime<FixedOffset> {
+        let now = match self.source {
+            TimeSource::Host => Utc::now(),
+            TimeSource::Fixed(time) => time,
+            TimeSource::Virtual {
+                start,
+                cycles_per_second,
+            } => {
+                let elapsed_ms = cycle_count as u128 * 1000 / cycles_per_second.max(1) as u128;
+                start + Duration::milliseconds(elapsed_ms.min(i64::MAX as u128) as i64)
+            }
+        };
+        self.latched = now.with_timezone(&self.timezone_offset);
+        self.latched
+    }
+
+    /// Returns the date and time of the last call to `latch`.
+    pub fn latched(&self) -> DateTime<FixedOffset> {
+        self.latched
+    }
+
+    /// Sets the timezone offset (in seconds east of UTC) for all following reads. Offsets of
+    /// a day or more are ignored.
+    pub fn set_timezone_offset(&mut self, seconds: i32) {
+        if let Some(offset) = FixedOffset::east_opt(seconds) {
+            self.timezone_offset = offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(date: (i32, u32, u32), time: (u32, u32, u32)) -> DateTime<Utc> {
+        let (year, month, day) = date;
+        let (hour, minute, second) = time;
+        let naive = NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, second))
+            .unwrap();
+        Utc.from_utc_datetime(&naive)
+    }
+
+    #[test]
+    fn virtual_time_advances_with_cycles() {
+        let start = utc((1999, 12, 31), (23, 59, 59));
+        let mut rtc = Rtc::new(
+            TimeSource::Virtual {
+                start,
+                cycles_per_second: 1000,
+            },
+            FixedOffset::east_opt(0).unwrap(),
+        );
+        assert_eq!(rtc.latch(0), start);
+        assert_eq!(rtc.latch(1000), utc((2000, 1, 1), (0, 0, 0)));
+    }
+
+    #[test]
+    fn timezone_offset_applies_to_latched_time() {
+        let mut rtc = Rtc::new(
+            TimeSource::Fixed(utc((2020, 2, 29), (23, 30, 0))),
+            FixedOffset::east_opt(0).unwrap(),
+        );
+        rtc.set_timezone_offset(3600);
+        let latched = rtc.latch(0);
+        assert_eq!((latched.month(), latched.day(), latched.hour()), (3, 1, 0));
+        rtc.set_timezone_offset(24 * 3600);
+        assert_eq!(rtc.latch(0).offset().local_minus_utc(), 3600);
+    }
+}
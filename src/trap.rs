@@ -0,0 +1,68 @@
+use crate::{memory::Memory, Address, Word};
+
+/// The privilege level the processor is running at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PrivilegeMode {
+    /// Unrestricted access to the memory and the periphery. The processor starts in this mode.
+    #[default]
+    Supervisor,
+    /// Memory accesses are limited to the user memory region and privileged instructions
+    /// cause a trap into supervisor mode.
+    User,
+}
+
+/// The reason for entering a trap. The kernel can query it via `GetTrapCauseValue`.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrapCause {
+    /// `SystemCallImmediate` was executed, the value is the immediate of the instruction.
+    SystemCall = 0,
+    /// A privileged instruction was executed in user mode, the value is its opcode number.
+    PrivilegedInstruction = 1,
+    /// User mode code accessed memory outside of the user memory region, the value is the
+    /// offending address. Only raised while paging is disabled.
+    ProtectionFault = 2,
+    /// The accessed virtual address is not mapped or the page does not permit the access, the
+    /// value is the offending address.
+    PageFault = 3,
+    /// A stack access left the part of the stack region that belongs to the core (a stack
+    /// overflow or underflow) or the memory, the value is the offending address. Only raised
+    /// while paging is disabled.
+    StackFault = 4,
+}
+
+/// Everything the processor has to remember to enter and leave traps.
+#[derive(Clone, Debug)]
+pub struct TrapState {
+    pub vector: Address,
+    pub return_address: Address,
+    pub cause: Word,
+    pub value: Word,
+    /// The mode the processor was running in when the trap was raised, `SystemReturn`
+    /// switches back into it (user mode unless a trap is being handled).
+    pub previous_mode: PrivilegeMode,
+    pub user_memory_start: Address,
+    pub user_memory_end: Address,
+}
+
+impl Default for TrapState {
+    fn default() -> Self {
+        Self {
+            vector: 0,
+            return_address: 0,
+            cause: 0,
+            value: 0,
+            previous_mode: PrivilegeMode::User,
+            user_memory_start: 0,
+            user_memory_end: Memory::SIZE as Address,
+        }
+    }
+}
+
+impl TrapState {
+    /// Returns whether user mode code may access the `size` bytes starting at `address`.
+    pub fn is_user_memory(&self, address: Address, size: usize) -> bool {
+        let end = address as u64 + size as u64;
+        address >= self.user_memory_start && end <= self.user_memory_end as u64
+    }
+}